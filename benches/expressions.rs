@@ -0,0 +1,44 @@
+/*
+Times a loop over short arithmetic and logical expressions, the everyday case for the evaluator's
+iterative walk of binary and logical chains: a chain two or three operators long should cost no
+more than the recursive walk it replaced. Run it on either side of that change to compare.
+`cargo bench --bench expressions`.
+*/
+use std::time::Instant;
+
+use crafting_interpreters::{Interpreter, Parser, Scanner};
+
+const ROUNDS: usize = 200_000;
+const RUNS: usize = 5;
+
+const SOURCE: &str = "
+var a = 3;
+var b = 4;
+var total = 0;
+var hits = 0;
+for (var i = 0; i < ROUNDS; i = i + 1) {
+  total = total + (a * b - i) / 2;
+  if (i > 10 and (a < b or i == 0)) hits = hits + 1;
+  if (!(a == b) and (total > 0 or nil)) total = total - 1;
+}
+";
+
+fn main() {
+    let source = SOURCE.replace("ROUNDS", &ROUNDS.to_string());
+    let mut best = f64::INFINITY;
+    for _ in 0..RUNS {
+        let tokens = Scanner::new(source.clone()).scan_tokens().clone();
+        let statements = Parser::new(tokens).parse();
+        let mut interpreter = Interpreter::with_output(Box::new(std::io::sink()));
+        let started = Instant::now();
+        interpreter.interpret(statements).expect("the benchmark program runs");
+        best = best.min(started.elapsed().as_secs_f64());
+    }
+    println!(
+        "expressions: {} rounds in {:.1} ms, {:.0} ns per round (best of {})",
+        ROUNDS,
+        best * 1000.0,
+        best * 1e9 / ROUNDS as f64,
+        RUNS
+    );
+}
@@ -0,0 +1,43 @@
+/*
+Times a loop of method calls, the case the property access cache is for. A plain binary rather
+than a libtest bench, so that it runs on stable: `cargo bench --bench method_calls`.
+*/
+use std::time::Instant;
+
+use crafting_interpreters::{Interpreter, Parser, Scanner};
+
+const CALLS: usize = 200_000;
+const RUNS: usize = 5;
+
+const SOURCE: &str = "
+class Base { value() { return 1; } }
+class Point < Base {
+  init(x) { this.x = x; }
+  norm() { return this.x * this.x; }
+}
+var p = Point(3);
+var total = 0;
+for (var i = 0; i < CALLS; i = i + 1) {
+  total = total + p.norm() + p.value();
+}
+";
+
+fn main() {
+    let source = SOURCE.replace("CALLS", &CALLS.to_string());
+    let mut best = f64::INFINITY;
+    for _ in 0..RUNS {
+        let tokens = Scanner::new(source.clone()).scan_tokens().clone();
+        let statements = Parser::new(tokens).parse();
+        let mut interpreter = Interpreter::with_output(Box::new(std::io::sink()));
+        let started = Instant::now();
+        interpreter.interpret(statements).expect("the benchmark program runs");
+        best = best.min(started.elapsed().as_secs_f64());
+    }
+    println!(
+        "method_calls: {} calls in {:.1} ms, {:.0} ns per call (best of {})",
+        2 * CALLS,
+        best * 1000.0,
+        best * 1e9 / (2 * CALLS) as f64,
+        RUNS
+    );
+}
@@ -0,0 +1,42 @@
+/*
+Times passing a long string around: through variables, function arguments and arrays. With
+strings stored as `Rc<str>` each copy is a reference count bump, whatever the length of the text.
+`cargo bench --bench string_copies`.
+*/
+use std::time::Instant;
+
+use crafting_interpreters::{Interpreter, Parser, Scanner};
+
+const COPIES: usize = 100_000;
+const RUNS: usize = 5;
+
+const SOURCE: &str = "
+var text = \"0123456789\";
+for (var i = 0; i < 10; i = i + 1) text = text + text;
+fun pass(s) { return s; }
+var kept = [];
+for (var i = 0; i < COPIES; i = i + 1) {
+  var copy = pass(text);
+  kept.push(copy);
+  if (len(kept) > 100) kept = [];
+}
+";
+
+fn main() {
+    let source = SOURCE.replace("COPIES", &COPIES.to_string());
+    let mut best = f64::INFINITY;
+    for _ in 0..RUNS {
+        let tokens = Scanner::new(source.clone()).scan_tokens().clone();
+        let statements = Parser::new(tokens).parse();
+        let mut interpreter = Interpreter::with_output(Box::new(std::io::sink()));
+        let started = Instant::now();
+        interpreter.interpret(statements).expect("the benchmark program runs");
+        best = best.min(started.elapsed().as_secs_f64());
+    }
+    println!(
+        "string_copies: {} copies of a 10 KiB string in {:.1} ms (best of {})",
+        COPIES,
+        best * 1000.0,
+        RUNS
+    );
+}
@@ -0,0 +1,267 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use crate::{compare_values, Evaluator, GcRef, LoxCallable, RuntimeError, Value};
+
+/*
+Arrays are ordered, growable lists of values, written `[1, "two", nil]` and indexed from zero
+with `a[i]`. An array is an object: the value holds a shared reference, so assigning an array to
+another variable, passing it to a function or storing it in a field does not copy it, and a change
+made through one reference is seen through all of them. Two arrays are equal (`==`) when they
+have the same length and their elements are equal pair by pair, whether or not they are the same
+array.
+
+Indices must be whole numbers from 0 up to, but not including, the length. There are no negative
+indices counting from the end; `a[-1]` is an error like any other index out of range.
+`a[i..j]` is a new array of the elements from `i` up to, but not including, `j`; strings can be
+indexed and sliced the same way, by character.
+
+Everything else an array can do is a method, called the same way as a method on an instance:
+`a.push(4)`. Looking up a property on an array goes through `ArrayMethod::lookup`, which gives back
+a native callable bound to that array, so a method can also be taken out and called later, just
+like a bound Lox method. The methods are:
+
+    push(value)         add value at the end
+    pop()               remove and return the last element, or nil if there is none
+    insert(i, value)    add value at index i, shifting the rest up; i may be the length
+    removeAt(i)         remove and return the element at index i, shifting the rest down
+    indexOf(value)      the index of the first element equal to value, or -1
+    contains(value)     whether any element is equal to value
+    slice(start, end)   a new array of the elements from start up to, not including, end
+    concat(other)       a new array of this array's elements followed by other's
+    reverse()           reverse the elements in place
+    join(separator)     the elements as text, as `print` shows them, with separator in between
+    sort()              sort in place: numbers numerically or strings lexicographically
+
+Methods that change the array return nil, except `pop` and `removeAt`, which return what they
+removed. `slice` and `concat` leave their array alone and make a new one. `len(a)` is the number
+of elements, and `sort(a)` is the same as `a.sort()`.
+*/
+
+/// An index into a sequence of `len` elements, or the reason `index` isn't one. With
+/// `allow_end`, the length itself is accepted too, as the position just past the last element.
+pub fn check_index(index: &Value, len: usize, allow_end: bool) -> Result<usize, String> {
+    let Some(n) = index.as_integer() else {
+        return Err(format!("Index must be a whole number, got {}.", index.describe()));
+    };
+    let limit = if allow_end { len } else { len.saturating_sub(1) };
+    if n < 0 || (len == 0 && !allow_end) || n as u64 > limit as u64 {
+        return Err(format!("Index {} is out of range for length {}.", n, len));
+    }
+    Ok(n as usize)
+}
+
+/*
+Sort `array` in place, for both `a.sort()` and `sort(a)`. Before anything moves, each element is
+compared with the first: if all of those have an order, the elements are all numbers or all
+strings and every pair has one. Otherwise the first pair that doesn't is reported in the order it
+appears in the array, rather than whichever way round the sort happened to compare them, and the
+array is left as it was.
+*/
+fn sort_array(interpreter: &mut Evaluator, array: &Rc<RefCell<Vec<Value>>>) -> Result<(), RuntimeError> {
+    let call_site = interpreter.call_site();
+    let unordered = {
+        let elements = array.borrow();
+        elements.split_first().and_then(|(first, rest)| {
+            rest.iter()
+                .find(|element| compare_values(first, element, &call_site).is_err())
+                .map(|element| (first.clone(), element.clone()))
+        })
+    };
+    if let Some((a, b)) = unordered {
+        return Err(interpreter.order_error(&a, &b, &call_site));
+    }
+    array
+        .borrow_mut()
+        .sort_by(|a, b| compare_values(a, b, &call_site).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct ArrayMethod {
+    array: Rc<RefCell<Vec<Value>>>,
+    name: &'static str,
+    arity: usize,
+}
+
+impl ArrayMethod {
+    /// The method called `name`, bound to `array`, if arrays have one by that name.
+    pub fn lookup(array: &Rc<RefCell<Vec<Value>>>, name: &str) -> Option<ArrayMethod> {
+        let (name, arity) = match name {
+            "push" => ("push", 1),
+            "pop" => ("pop", 0),
+            "insert" => ("insert", 2),
+            "removeAt" => ("removeAt", 1),
+            "indexOf" => ("indexOf", 1),
+            "contains" => ("contains", 1),
+            "slice" => ("slice", 2),
+            "concat" => ("concat", 1),
+            "reverse" => ("reverse", 0),
+            "join" => ("join", 1),
+            "sort" => ("sort", 0),
+            _ => return None,
+        };
+        Some(ArrayMethod {
+            array: Rc::clone(array),
+            name,
+            arity,
+        })
+    }
+}
+
+impl LoxCallable for ArrayMethod {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let mut arguments = arguments.into_iter();
+        let mut argument = || arguments.next().unwrap_or(Value::Nil);
+        let len = self.array.borrow().len();
+        let index_error = |interpreter: &Evaluator, message: String| {
+            interpreter.native_error(format!("{} (in {}()).", message.trim_end_matches('.'), self.name))
+        };
+
+        match self.name {
+            "push" => {
+                interpreter.check_allocation(&interpreter.call_site(), len.checked_add(1))?;
+                self.array.borrow_mut().push(argument());
+                Ok(Value::Nil)
+            }
+            "pop" => Ok(self.array.borrow_mut().pop().unwrap_or(Value::Nil)),
+            "insert" => {
+                let index = check_index(&argument(), len, true)
+                    .map_err(|message| index_error(interpreter, message))?;
+                interpreter.check_allocation(&interpreter.call_site(), len.checked_add(1))?;
+                self.array.borrow_mut().insert(index, argument());
+                Ok(Value::Nil)
+            }
+            "removeAt" => {
+                let index = check_index(&argument(), len, false)
+                    .map_err(|message| index_error(interpreter, message))?;
+                Ok(self.array.borrow_mut().remove(index))
+            }
+            "indexOf" => {
+                let value = argument();
+                let position = self.array.borrow().iter().position(|element| element.equals(&value));
+                Ok(Value::Int(position.map_or(-1, |i| i as i64)))
+            }
+            "contains" => {
+                let value = argument();
+                let found = self.array.borrow().iter().any(|element| element.equals(&value));
+                Ok(Value::Bool(found))
+            }
+            "slice" => {
+                let start = check_index(&argument(), len, true)
+                    .map_err(|message| index_error(interpreter, message))?;
+                let end = check_index(&argument(), len, true)
+                    .map_err(|message| index_error(interpreter, message))?;
+                if start > end {
+                    return Err(interpreter.native_error(format!(
+                        "slice() start {} is past end {}.",
+                        start, end
+                    )));
+                }
+                let elements = self.array.borrow()[start..end].to_vec();
+                Ok(interpreter.heap.array(elements))
+            }
+            "concat" => match argument() {
+                Value::Array(other) => {
+                    // clone `other` first: it may be this very array
+                    let tail = other.borrow().clone();
+                    let mut elements = self.array.borrow().clone();
+                    interpreter.check_allocation(&interpreter.call_site(), elements.len().checked_add(tail.len()))?;
+                    elements.extend(tail);
+                    Ok(interpreter.heap.array(elements))
+                }
+                other => Err(interpreter.native_error(format!(
+                    "concat() expects an array, got {}.",
+                    other.type_name()
+                ))),
+            },
+            "reverse" => {
+                self.array.borrow_mut().reverse();
+                Ok(Value::Nil)
+            }
+            "join" => match argument() {
+                Value::String(separator) => {
+                    // an instance with a `toString` joins as it prints; the method may change the array
+                    let elements = self.array.borrow().clone();
+                    let call_site = interpreter.call_site();
+                    let parts = elements
+                        .iter()
+                        .map(|element| interpreter.stringify(element, &call_site))
+                        .collect::<Result<Vec<String>, RuntimeError>>()?;
+                    // the parts, plus a separator between each pair of them
+                    let separators = separator.len().checked_mul(parts.len().saturating_sub(1));
+                    let size = parts
+                        .iter()
+                        .try_fold(0usize, |size, part| size.checked_add(part.len()))
+                        .and_then(|size| size.checked_add(separators?));
+                    interpreter.check_allocation(&call_site, size)?;
+                    Ok(Value::String(parts.join(&separator).into()))
+                }
+                other => Err(interpreter.native_error(format!(
+                    "join() expects a string separator, got {}.",
+                    other.type_name()
+                ))),
+            },
+            "sort" => {
+                sort_array(interpreter, &self.array)?;
+                Ok(Value::Nil)
+            }
+            _ => unreachable!("ArrayMethod::lookup only makes known methods"),
+        }
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        visit(GcRef::Array(Rc::clone(&self.array)));
+    }
+}
+
+impl fmt::Display for ArrayMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/// `sort(array)`, the native form of `array.sort()`.
+#[derive(Debug)]
+pub struct SortFn;
+
+impl LoxCallable for SortFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "sort" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match &arguments[0] {
+            Value::Array(array) => {
+                sort_array(interpreter, array)?;
+                Ok(Value::Nil)
+            }
+            other => Err(interpreter.native_error(format!(
+                "sort() expects an array, got {}.",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SortFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
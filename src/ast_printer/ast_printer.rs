@@ -0,0 +1,441 @@
+use crate::lexer::{Literal, Token, TokenType};
+use crate::parser::{Expr, ExprId, ParseError, Visitor};
+use crate::{stringify_number, RuntimeError, Stmt, StmtVisitor, Value};
+
+/*
+The AST printer turns a syntax tree back into text, so that we can see what the parser made of
+a piece of source code. It does not try to reproduce the original source. Instead, every node is
+written out in a fully parenthesized, Lisp-like prefix form, which makes the structure (and the
+precedence the parser chose) explicit:
+
+    -123 * (45.67)   =>   (* (- 123) (group 45.67))
+
+Like the resolver, the printer is just another implementation of the expression visitor. The
+visitor's methods hand back a `Value`, so each one returns its text as a `Value::String`, and
+`print` unwraps it.
+
+Lox has no negative number literals. `-5` is scanned as a minus token followed by the number 5,
+so it parses as a unary negation and prints as `(- 5)`. That is correct, but noisy when a tree is
+full of negative constants. With `fold_negative_literals` set, a minus applied directly to a number
+literal is shown as a single negative literal, `-5`, instead. This only affects how the tree is
+displayed - the tree itself is untouched, and it still evaluates as a negation.
+
+Statements are printed the same way, except that anything with a body spreads over several lines,
+its children indented by two spaces under it:
+
+    (fun greet (name)
+      (print name))
+
+Lox has no `else if`; `if (a) ... else if (b) ... else ...` is an `if` whose else branch is
+another `if`. Printed literally, every link in such a chain would be nested one level deeper than
+the last. The printer recognizes an else branch that is itself an `if` and lays the chain out flat
+instead, the way it was written:
+
+    (if a
+      (print 1)
+     else-if b
+      (print 2)
+     else
+      (print 3))
+*/
+
+#[derive(Debug, Default)]
+pub struct AstPrinter {
+    // show `Unary(-, Literal(n))` as the literal `-n`
+    fold_negative_literals: bool,
+}
+
+impl AstPrinter {
+    pub fn new() -> Self {
+        Self {
+            fold_negative_literals: false,
+        }
+    }
+
+    pub fn fold_negative_literals(&self) -> bool {
+        self.fold_negative_literals
+    }
+
+    pub fn set_fold_negative_literals(&mut self, enabled: bool) {
+        self.fold_negative_literals = enabled;
+    }
+
+    pub fn print(&mut self, expr: &Expr) -> String {
+        match expr.accept(self) {
+            Ok(Value::String(text)) => text.to_string(),
+            // every visit method below returns its text as a string
+            _ => unreachable!("the AST printer only produces strings"),
+        }
+    }
+
+    pub fn print_stmt(&mut self, stmt: &Stmt) -> String {
+        stmt.accept(self)
+    }
+
+    // a node with children on their own lines, each indented under the head: `(head\n  child)`
+    fn parenthesize_lines(&mut self, head: &str, children: &[String]) -> String {
+        let mut text = format!("({}", head);
+        for child in children {
+            text.push('\n');
+            text.push_str(&indent(child, "  "));
+        }
+        text.push(')');
+        text
+    }
+
+    fn parenthesize(&mut self, name: &str, exprs: &[&Expr]) -> Result<Value, RuntimeError> {
+        let mut text = format!("({}", name);
+        for expr in exprs {
+            text.push(' ');
+            text.push_str(&self.print(expr));
+        }
+        text.push(')');
+        Ok(Value::String(text.into()))
+    }
+}
+
+impl Visitor for AstPrinter {
+    fn visit_literal_expr(&mut self, value: &Literal) -> Result<Value, RuntimeError> {
+        let text = match value {
+            Literal::Int(n) => n.to_string(),
+            Literal::Float(n) => stringify_number(*n),
+            Literal::String(s) => s.clone(),
+            Literal::Bool(b) => b.to_string(),
+            Literal::Nil => "nil".to_string(),
+        };
+        Ok(Value::String(text.into()))
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        self.parenthesize("group", &[expr])
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<Value, RuntimeError> {
+        if self.fold_negative_literals && operator.token_type == TokenType::Minus {
+            match right {
+                // an int literal is never negative, so this can't overflow
+                Expr::Literal { value: Literal::Int(n) } => return Ok(Value::String((-n).to_string().into())),
+                Expr::Literal { value: Literal::Float(n) } => return Ok(Value::String(stringify_number(-n).into())),
+                _ => {}
+            }
+        }
+        self.parenthesize(&operator.lexeme, &[right])
+    }
+
+    fn visit_binary_expr(
+        &mut self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        self.parenthesize(&operator.lexeme, &[left, right])
+    }
+
+    fn visit_variable_expr(&mut self, token: &Token, _id: ExprId) -> Result<Value, RuntimeError> {
+        Ok(Value::String(token.lexeme.clone().into()))
+    }
+
+    fn visit_assign_expr(&mut self, token: &Token, value: &Expr, _id: ExprId) -> Result<Value, RuntimeError> {
+        self.parenthesize(&format!("= {}", token.lexeme), &[value])
+    }
+
+    fn visit_logical_expr(
+        &mut self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        self.parenthesize(&operator.lexeme, &[left, right])
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        _open_paren: &Token,
+        _paren: &Token,
+        arguments: &[Expr],
+        names: &[Option<Token>],
+    ) -> Result<Value, RuntimeError> {
+        let mut text = format!("(call {}", self.print(callee));
+        for (argument, name) in arguments.iter().zip(names) {
+            text.push(' ');
+            if let Some(name) = name {
+                text.push_str(&format!("{}: ", name.lexeme));
+            }
+            text.push_str(&self.print(argument));
+        }
+        text.push(')');
+        Ok(Value::String(text.into()))
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token, optional: bool, _id: ExprId) -> Result<Value, RuntimeError> {
+        let operator = if optional { "?." } else { "." };
+        self.parenthesize(&format!("{} {}", operator, name.lexeme), &[object])
+    }
+
+    fn visit_set_expr(&mut self, object: &Expr, name: &Token, value: &Expr) -> Result<Value, RuntimeError> {
+        self.parenthesize(&format!("= .{}", name.lexeme), &[object, value])
+    }
+
+    fn visit_this_expr(&mut self, _this: &Token, _id: ExprId) -> Result<Value, RuntimeError> {
+        Ok(Value::String("this".to_string().into()))
+    }
+
+    fn visit_super_expr(&mut self, _keyword: &Token, method: &Token, _id: ExprId) -> Result<Value, RuntimeError> {
+        Ok(Value::String(format!("(super {})", method.lexeme).into()))
+    }
+
+    fn visit_array_expr(&mut self, elements: &[Expr]) -> Result<Value, RuntimeError> {
+        let elements: Vec<&Expr> = elements.iter().collect();
+        self.parenthesize("array", &elements)
+    }
+
+    // `(block-value\n  stmt\n  value)`, the statements and then the value, if there is one
+    fn visit_block_expr(&mut self, statements: &[Stmt], value: Option<&Expr>) -> Result<Value, RuntimeError> {
+        let mut children: Vec<String> = statements.iter().map(|stmt| self.print_stmt(stmt)).collect();
+        if let Some(value) = value {
+            children.push(self.print(value));
+        }
+        Ok(Value::String(self.parenthesize_lines("block-value", &children).into()))
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> Result<Value, RuntimeError> {
+        let mut text = "(map".to_string();
+        for (key, value) in entries {
+            text.push_str(&format!(" {}: {}", self.print(key), self.print(value)));
+        }
+        text.push(')');
+        Ok(Value::String(text.into()))
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> Result<Value, RuntimeError> {
+        self.parenthesize("[]", &[object, index])
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        start: &Expr,
+        end: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        self.parenthesize("[..]", &[object, start, end])
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        self.parenthesize("= []", &[object, index, value])
+    }
+}
+
+// `text` with `prefix` put in front of every line
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| format!("{}{}", prefix, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// a labeled loop is written with its label in front, `(outer: while ...)`
+fn label_prefix(label: &Option<Token>) -> String {
+    label.as_ref().map_or(String::new(), |label| format!("{}: ", label.lexeme))
+}
+
+impl StmtVisitor<String> for AstPrinter {
+    fn visit_expression_stmt(&mut self, stmt: &Stmt) -> String {
+        match stmt {
+            Stmt::Expression { expression } => format!("(; {})", self.print(expression)),
+            _ => unreachable!("visit_expression_stmt is only called on expression statements"),
+        }
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &Stmt) -> String {
+        match stmt {
+            Stmt::Print { expression, .. } => format!("(print {})", self.print(expression)),
+            _ => unreachable!("visit_print_stmt is only called on print statements"),
+        }
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &Stmt) -> String {
+        match stmt {
+            Stmt::Var { name, initializer: Some(initializer), constant } => {
+                let keyword = if *constant { "const" } else { "var" };
+                format!("({} {} {})", keyword, name.lexeme, self.print(initializer))
+            }
+            Stmt::Var { name, initializer: None, .. } => format!("(var {})", name.lexeme),
+            _ => unreachable!("visit_var_stmt is only called on var statements"),
+        }
+    }
+
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> String {
+        let children: Vec<String> = statements.iter().map(|stmt| self.print_stmt(stmt)).collect();
+        self.parenthesize_lines("block", &children)
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> String {
+        let mut text = format!("(if {}\n{}", self.print(condition), indent(&self.print_stmt(then_branch), "  "));
+
+        // follow the chain of `else if`s, printing each link at the same depth as the first
+        let mut else_branch = else_branch.as_deref();
+        while let Some(branch) = else_branch {
+            match branch {
+                Stmt::If { conditional, consequent, alternative } => {
+                    text.push_str(&format!("\n else-if {}\n", self.print(conditional)));
+                    text.push_str(&indent(&self.print_stmt(consequent), "  "));
+                    else_branch = alternative.as_deref();
+                }
+                _ => {
+                    text.push_str("\n else\n");
+                    text.push_str(&indent(&self.print_stmt(branch), "  "));
+                    else_branch = None;
+                }
+            }
+        }
+        text.push(')');
+        text
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        _keyword: &Token,
+        label: &Option<Token>,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Box<Expr>>,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> String {
+        let head = format!("{}while {}", label_prefix(label), self.print(condition));
+        let mut children = vec![self.print_stmt(body)];
+        // the increment of a desugared `for`, which runs after the body on every iteration
+        if let Some(increment) = increment {
+            children.push(format!("(step {})", self.print(increment)));
+        }
+        let mut text = self.parenthesize_lines(&head, &children);
+        // laid out like the else of an `if`
+        if let Some(else_branch) = else_branch {
+            text.pop();
+            text.push_str("\n else\n");
+            text.push_str(&indent(&self.print_stmt(else_branch), "  "));
+            text.push(')');
+        }
+        text
+    }
+
+    fn visit_continue_stmt(&mut self, _keyword: &Token, label: &Option<Token>) -> String {
+        match label {
+            Some(label) => format!("(continue {})", label.lexeme),
+            None => "(continue)".to_string(),
+        }
+    }
+
+    fn visit_break_stmt(&mut self, _keyword: &Token, label: &Option<Token>) -> String {
+        match label {
+            Some(label) => format!("(break {})", label.lexeme),
+            None => "(break)".to_string(),
+        }
+    }
+
+    fn visit_repeat_stmt(&mut self, _keyword: &Token, label: &Option<Token>, count: &Expr, body: &Stmt) -> String {
+        let head = format!("{}repeat {}", label_prefix(label), self.print(count));
+        let body = self.print_stmt(body);
+        self.parenthesize_lines(&head, &[body])
+    }
+
+    fn visit_do_while_stmt(&mut self, _keyword: &Token, label: &Option<Token>, body: &Stmt, condition: &Expr) -> String {
+        let head = format!("{}do while {}", label_prefix(label), self.print(condition));
+        let body = self.print_stmt(body);
+        self.parenthesize_lines(&head, &[body])
+    }
+
+    // `(try (block ...) catch e (block ...))`, the two blocks laid out like the branches of an `if`
+    fn visit_try_catch_stmt(&mut self, _keyword: &Token, body: &[Stmt], error_name: &Token, handler: &[Stmt]) -> String {
+        let mut block = |statements: &[Stmt]| {
+            let children: Vec<String> = statements.iter().map(|stmt| self.print_stmt(stmt)).collect();
+            self.parenthesize_lines("block", &children)
+        };
+        let (body, handler) = (block(body), block(handler));
+        format!("(try\n{}\n catch {}\n{})", indent(&body, "  "), error_name.lexeme, indent(&handler, "  "))
+    }
+
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) -> String {
+        format!("(throw {})", self.print(value))
+    }
+
+    fn visit_import_stmt(&mut self, _keyword: &Token, path: &Token, alias: &Option<Token>) -> String {
+        match alias {
+            Some(alias) => format!("(import {} as {})", path.lexeme, alias.lexeme),
+            None => format!("(import {})", path.lexeme),
+        }
+    }
+
+    fn visit_for_in_stmt(&mut self, _keyword: &Token, label: &Option<Token>, index: &Option<Token>, name: &Token, iterable: &Expr, body: &Stmt) -> String {
+        let names = match index {
+            Some(index) => format!("{}, {}", index.lexeme, name.lexeme),
+            None => name.lexeme.clone(),
+        };
+        let head = format!("{}for {} in {}", label_prefix(label), names, self.print(iterable));
+        let body = self.print_stmt(body);
+        self.parenthesize_lines(&head, &[body])
+    }
+
+    fn visit_fun_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        param_types: &[Option<Token>],
+        body: &[Stmt],
+        return_type: &Option<Token>,
+        _id: ExprId,
+    ) -> String {
+        let params: Vec<String> = params
+            .iter()
+            .zip(param_types)
+            .map(|(param, param_type)| match param_type {
+                Some(param_type) => format!("{}: {}", param.lexeme, param_type.lexeme),
+                None => param.lexeme.clone(),
+            })
+            .collect();
+        let mut head = format!("fun {} ({})", name.lexeme, params.join(" "));
+        if let Some(return_type) = return_type {
+            head.push_str(&format!(" -> {}", return_type.lexeme));
+        }
+        let children: Vec<String> = body.iter().map(|stmt| self.print_stmt(stmt)).collect();
+        self.parenthesize_lines(&head, &children)
+    }
+
+    fn visit_return_stmt(&mut self, _keyword: &Token, value: &Option<Box<Expr>>) -> String {
+        match value {
+            Some(value) => format!("(return {})", self.print(value)),
+            None => "(return)".to_string(),
+        }
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        methods: &[Result<Stmt, ParseError>],
+        superclass: &Option<Box<Expr>>,
+    ) -> String {
+        let head = match superclass {
+            Some(superclass) => format!("class {} < {}", name.lexeme, self.print(superclass)),
+            None => format!("class {}", name.lexeme),
+        };
+        // a method that failed to parse has already been reported, and has nothing to show
+        let children: Vec<String> = methods
+            .iter()
+            .filter_map(|method| method.as_ref().ok())
+            .map(|method| self.print_stmt(method))
+            .collect();
+        self.parenthesize_lines(&head, &children)
+    }
+}
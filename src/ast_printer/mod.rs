@@ -0,0 +1,2 @@
+pub mod ast_printer;
+pub use ast_printer::*;
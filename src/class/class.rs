@@ -1,9 +1,26 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
-use crate::{Environment, Evaluator, LoxCallable, LoxFunction, RuntimeError, Stmt, Token, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::{Evaluator, GcRef, Literal, LoxCallable, LoxFunction, LoxMap, RuntimeError, Token, Value};
+
+/*
+Classes and instances are compared by identity: two values are equal only if they are the same
+object. Instances are shared through an `Rc`, so that is simply whether two handles point at the
+same allocation. Classes are still passed around by value, so every class gets an id when it is
+created, and its copies keep that id. Two copies of one class are the same class as far as the
+program can tell; two classes declared separately never are, however alike they look.
+*/
+static NEXT_OBJECT_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn next_object_id() -> usize {
+    NEXT_OBJECT_ID.fetch_add(1, Ordering::Relaxed)
+}
 
 #[derive(Clone, Debug)]
 pub struct LoxClass {
+    id: usize,
     superclass: Option<Box<LoxClass>>,
     name: String,
     methods: HashMap<String, LoxFunction>,
@@ -12,11 +29,33 @@ pub struct LoxClass {
 
 impl LoxClass {
     pub fn new(name: String, methods: HashMap<String, LoxFunction>, superclass: Option<Box<LoxClass>>) -> Self {
-        Self { name, methods, superclass}
+        Self { id: next_object_id(), name, methods, superclass}
     }
-    
-    pub fn stringify(&self) -> String {
-        self.name.clone()
+
+    /// Whether `self` and `other` are the same class, not merely alike.
+    pub fn same_object(&self, other: &LoxClass) -> bool {
+        self.id == other.id
+    }
+
+    // what tells classes apart, for caches that must not hold on to a whole class
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this class is `other` or inherits from it, however many levels up.
+    pub fn is_subclass_of(&self, other: &LoxClass) -> bool {
+        let mut class = Some(self);
+        while let Some(current) = class {
+            if current.same_object(other) {
+                return true;
+            }
+            class = current.superclass.as_deref();
+        }
+        false
     }
 
     pub fn find_method(&self, name: String) -> Option<LoxFunction> {
@@ -37,6 +76,15 @@ impl LoxClass {
     pub fn get_method(&self, name: &str) -> Option<&LoxFunction> {
         self.methods.get(name)
     }
+
+    pub(crate) fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        for method in self.methods.values() {
+            method.trace(visit);
+        }
+        if let Some(ref superclass) = self.superclass {
+            superclass.trace(visit);
+        }
+    }
 }
 
 /*
@@ -46,6 +94,10 @@ however you like.
 */
 
 impl LoxCallable for LoxClass {
+    fn is_native(&self) -> bool {
+        false
+    }
+
     fn arity(&self) -> usize {
         // If there is an initializer, that method's arity determines how many arguments
         // to pass when the class is called
@@ -61,6 +113,18 @@ impl LoxCallable for LoxClass {
         }
     }
 
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // the arguments of a class call go to `init`, so those are its parameters
+    fn param_names(&self) -> Option<Vec<String>> {
+        match self.find_method("init".to_string()) {
+            Some(init) => init.param_names(),
+            None => Some(Vec::new()),
+        }
+    }
+
     /*
     When you `call` a class, it instantiates a new LoxInstance for that class and returns it. The arity
     method is how the interpreter validates that you passed the right number of arguments to a callable. For now
@@ -75,14 +139,15 @@ impl LoxCallable for LoxClass {
         When a class is called, after the LoxInstance is created, we look for an "init" method. If we find oine,
         we immediately bind and invoke it like a normal method call. The argument list is fowarded along.
         */
-        let instance = LoxInstance::new(self.clone());
+        let instance = interpreter.heap.instance(LoxInstance::new(self.clone()));
 
         // Look for the "init" method of the class and call it if it exists
         if let Some(init_method) = self.find_method("init".parse().unwrap()) {
-            // Bind the init method to the instance and call it
-            init_method
-                .bind(instance.clone())
-                .call(interpreter, arguments)?;
+            // Bind the init method to the instance and call it. An initializer always returns
+            // its `this`, which is the shared instance itself, so that is what the class call gives
+            return init_method
+                .bind(instance)
+                .call(interpreter, arguments);
         }
 
         // Return the initialized instance
@@ -108,31 +173,52 @@ They appear on the left side of an assignment.
 Setters do not chain. However, the reference to call allows any high-precedence expression
 before the last dot, including any number of getters.
 
+An instance can be frozen with the `freeze` native. A frozen instance still answers gets as usual,
+but any attempt to set a field on it is a runtime error. This is handy for defensive scripting, where
+an object that is handed around should not be changed behind its creator's back. There is no way to
+unfreeze an instance.
+
+An instance is shared, never copied: a `Value::LoxInstance` is an `Rc<RefCell<LoxInstance>>`, so
+every variable, field, array slot and bound `this` that holds the instance holds the same object.
+Setting a field through any one of them is seen through all of the others.
+
+Fields are kept in a `LoxMap` keyed by name, so they remember the order they were first set in,
+the way map keys do. `fields(obj)` lists them in that order, the same on every run.
 
 */
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct LoxInstance {
     klass: LoxClass,
-    fields: HashMap<String, Value>, // Stores properties of the instance
+    fields: LoxMap, // Stores properties of the instance, keyed by name as a string literal
+    frozen: bool, // set by `freeze`, after which fields can no longer be set
 }
 
 impl LoxInstance {
     pub fn new(klass: LoxClass) -> Self {
         LoxInstance {
             klass,
-            fields: HashMap::new(),
+            fields: LoxMap::new(),
+            frozen: false,
         }
     }
 
-    pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
-        if let Some(value) = self.fields.get(&name.lexeme) {
-            return Ok(value.clone()); // Return the value of the property
+    // a method is bound to the shared handle rather than to the instance itself, which is why
+    // `get` takes the `Rc` and not `&self`
+    pub fn get(instance: &Rc<RefCell<LoxInstance>>, name: &Token) -> Result<Value, RuntimeError> {
+        if let Some(value) = instance.borrow().field(&name.lexeme) {
+            return Ok(value); // Return the value of the property
         }
 
         // If the property is a method, bind it to the current instance (this)
-        if let Some(method) = self.klass.find_method(name.lexeme.clone()) {
-            return Ok(Value::Callable(Rc::new(method.bind(self.clone())))); // Bind the method
+        let method = instance.borrow().klass.find_method(name.lexeme.clone());
+        if let Some(method) = method {
+            return Ok(Value::Callable(Rc::new(method.bind(Rc::clone(instance))))); // Bind the method
+        }
+
+        // every instance has `clone()`, unless its class declares a method by that name
+        if name.lexeme == "clone" {
+            return Ok(Value::Callable(Rc::new(CloneMethod { instance: Rc::clone(instance) })));
         }
 
         // If the property doesn't exist, throw a runtime error
@@ -142,11 +228,380 @@ impl LoxInstance {
         ))
     }
     
-    pub fn set(&mut self, name: &Token, value: &Value) {
-        self.fields.insert(name.clone().lexeme, value.clone());
+    pub fn set(&mut self, name: &Token, value: &Value) -> Result<(), RuntimeError> {
+        if self.frozen {
+            return Err(RuntimeError::new(
+                name.clone(),
+                "Cannot set property on frozen instance.".to_string(),
+            ));
+        }
+        self.fields.insert(Literal::String(name.lexeme.clone()), value.clone());
+        Ok(())
     }
-    
-    pub fn stringify(&self) -> String {
-        format!("{} instance", self.klass.stringify())
+
+    /// The value of the field called `name`, if the instance has one. Methods are not fields.
+    pub fn field(&self, name: &str) -> Option<Value> {
+        self.fields.get(&Literal::String(name.to_string())).cloned()
     }
-}
\ No newline at end of file
+
+    /// Whether the instance has a field called `name`. Methods are not fields.
+    pub fn has_field(&self, name: &str) -> bool {
+        self.fields.contains_key(&Literal::String(name.to_string()))
+    }
+
+    /// Remove the field called `name`, returning whether there was one. Like setting a field, this
+    /// is refused on a frozen instance; the error is the caller's to report, it has no token here.
+    pub fn remove_field(&mut self, name: &str) -> Result<bool, String> {
+        if self.frozen {
+            return Err("Cannot remove field from frozen instance.".to_string());
+        }
+        Ok(self.fields.remove(&Literal::String(name.to_string())).is_some())
+    }
+
+    /// The names of the fields, in the order they were first set.
+    pub fn field_names(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .filter_map(|(key, _)| match key {
+                Literal::String(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The values of the fields, for the cycle collector to walk.
+    pub(crate) fn field_values(&self) -> Vec<Value> {
+        self.fields.values()
+    }
+
+    /// Remove every field, frozen or not, for the cycle collector to break a cycle through them.
+    pub(crate) fn take_fields(&mut self) -> LoxMap {
+        std::mem::take(&mut self.fields)
+    }
+
+    /*
+    A shallow copy: a new instance of the same class whose fields start out with the same values
+    as this one's. It is a separate object with its own identity, so setting a field on one
+    leaves the other alone, but a field holding an object refers to that same object in both.
+    The copy is never frozen, even if the original is; getting a mutable copy of a frozen
+    instance is one of the main reasons to clone it.
+    */
+    pub fn shallow_copy(&self) -> LoxInstance {
+        LoxInstance {
+            klass: self.klass.clone(),
+            fields: self.fields.clone(),
+            frozen: false,
+        }
+    }
+
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn class_name(&self) -> &str {
+        self.klass.name()
+    }
+
+    /// The class this instance was created from.
+    pub fn class(&self) -> &LoxClass {
+        &self.klass
+    }
+
+    pub(crate) fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        self.klass.trace(visit);
+        for (_, value) in self.fields.iter() {
+            value.trace(visit);
+        }
+    }
+}
+
+// a class prints as its name, and an instance as `Name instance`, just like jlox
+impl fmt::Display for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl fmt::Display for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} instance", self.klass.name)
+    }
+}
+
+/*
+`freeze(value)` marks an instance read-only and hands the same instance back, so it can be used
+inline: `var origin = freeze(Point(0, 0));`. Only instances have fields to protect, so any other
+value is returned unchanged rather than treated as an error.
+*/
+#[derive(Debug)]
+pub struct FreezeFn;
+
+impl LoxCallable for FreezeFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "freeze" }
+
+    fn call(
+        &self,
+        _interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let value = arguments.into_iter().next().unwrap_or(Value::Nil);
+        match value {
+            Value::LoxInstance(instance) => {
+                instance.borrow_mut().freeze();
+                Ok(Value::LoxInstance(instance))
+            }
+            other => Ok(other),
+        }
+    }
+}
+
+impl fmt::Display for FreezeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+// `instance.clone()`, the built-in method behind `LoxInstance::shallow_copy`
+#[derive(Debug)]
+struct CloneMethod {
+    instance: Rc<RefCell<LoxInstance>>,
+}
+
+impl LoxCallable for CloneMethod {
+    fn arity(&self) -> usize { 0 }
+
+    fn name(&self) -> &str { "clone" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        _arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let copy = self.instance.borrow().shallow_copy();
+        Ok(Value::LoxInstance(interpreter.heap.instance(copy)))
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        visit(GcRef::Instance(Rc::clone(&self.instance)));
+    }
+}
+
+impl fmt::Display for CloneMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`clone(value)` is a deep copy, where `instance.clone()` is a shallow one: every instance, array
+and map reachable from `value` is copied too, so nothing done to the copy, however deep, shows up
+in the original. Instances are copied as new instances of the same class, and like a shallow copy
+they are never frozen. Everything else is returned as it is: numbers, strings, booleans and nil
+can't be changed anyway, and functions and classes are shared between the original and the copy,
+closures included, so a function stored in a field of both still sees the same captured variables.
+
+The copy has the same shape as the original. An object reached twice is copied once, and both
+places in the copy refer to that one copy; an object that refers back to itself, directly or
+through others, is copied into one that refers back to its copy. Objects are copied one at a time
+from a list of those still to fill in, rather than by recursion, so a long linked list doesn't
+overflow the stack.
+*/
+#[derive(Debug)]
+pub struct CloneFn;
+
+impl LoxCallable for CloneFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "clone" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let copy = deep_copy(&arguments[0]);
+        interpreter.heap.register_tree(&copy);
+        Ok(copy)
+    }
+}
+
+impl fmt::Display for CloneFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/// The copy `clone(value)` makes, for Rust code that wants one too.
+pub fn deep_copy(value: &Value) -> Value {
+    // each object copied so far, by the address of the original, and the ones still empty
+    let mut copies: HashMap<*const (), Value> = HashMap::new();
+    let mut unfilled: Vec<(Value, Value)> = Vec::new();
+    let copy = empty_copy(value, &mut copies, &mut unfilled);
+    while let Some((original, copy)) = unfilled.pop() {
+        match (original, copy) {
+            (Value::Array(original), Value::Array(copy)) => {
+                let elements = original
+                    .borrow()
+                    .iter()
+                    .map(|element| empty_copy(element, &mut copies, &mut unfilled))
+                    .collect();
+                *copy.borrow_mut() = elements;
+            }
+            (Value::Map(original), Value::Map(copy)) => {
+                for (key, value) in original.borrow().iter() {
+                    let value = empty_copy(value, &mut copies, &mut unfilled);
+                    copy.borrow_mut().insert(key.clone(), value);
+                }
+            }
+            (Value::LoxInstance(original), Value::LoxInstance(copy)) => {
+                for (key, value) in original.borrow().fields.iter() {
+                    let value = empty_copy(value, &mut copies, &mut unfilled);
+                    copy.borrow_mut().fields.insert(key.clone(), value);
+                }
+            }
+            _ => unreachable!(), // only objects are queued, each with a copy of its own kind
+        }
+    }
+    copy
+}
+
+// the copy of `value`: the one already made, or a new, empty object queued to be filled in.
+// The original and its copy are different objects, so filling one never borrows the other
+fn empty_copy(value: &Value, copies: &mut HashMap<*const (), Value>, unfilled: &mut Vec<(Value, Value)>) -> Value {
+    let address = match value {
+        Value::Array(array) => Rc::as_ptr(array) as *const (),
+        Value::Map(map) => Rc::as_ptr(map) as *const (),
+        Value::LoxInstance(instance) => Rc::as_ptr(instance) as *const (),
+        other => return other.clone(),
+    };
+    if let Some(copy) = copies.get(&address) {
+        return copy.clone();
+    }
+    let copy = match value {
+        Value::Array(_) => Value::Array(Rc::new(RefCell::new(Vec::new()))),
+        Value::Map(_) => Value::Map(Rc::new(RefCell::new(LoxMap::new()))),
+        Value::LoxInstance(instance) => {
+            Value::LoxInstance(Rc::new(RefCell::new(LoxInstance::new(instance.borrow().klass.clone()))))
+        }
+        _ => unreachable!(),
+    };
+    copies.insert(address, copy.clone());
+    unfilled.push((value.clone(), copy.clone()));
+    copy
+}
+
+/*
+`hasField(obj, name)`, `removeField(obj, name)` and `fields(obj)` look at the fields of an instance
+by name, for code that handles objects it knows nothing about, like a serializer or a debugger
+written in Lox. They see fields only: a method is found through the class, and is never one of
+an instance's fields, so `hasField(point, "norm")` is false even when `point.norm()` works.
+
+`removeField` gives back whether the field was there, and fails on a frozen instance, the same as
+setting a field would. `fields` lists the field names in the order they were first set. All three
+take nothing but an instance, and say what they were given otherwise.
+*/
+fn expect_instance(interpreter: &Evaluator, native: &str, value: &Value) -> Result<Rc<RefCell<LoxInstance>>, RuntimeError> {
+    match value {
+        Value::LoxInstance(instance) => Ok(Rc::clone(instance)),
+        other => Err(interpreter.native_error(format!(
+            "{}() expects an instance, got {}.",
+            native,
+            other.type_name()
+        ))),
+    }
+}
+
+fn expect_field_name(interpreter: &Evaluator, native: &str, value: &Value) -> Result<String, RuntimeError> {
+    match value {
+        Value::String(name) => Ok(name.to_string()),
+        other => Err(interpreter.native_error(format!(
+            "{}() expects a field name string, got {}.",
+            native,
+            other.type_name()
+        ))),
+    }
+}
+
+#[derive(Debug)]
+pub struct HasFieldFn;
+
+impl LoxCallable for HasFieldFn {
+    fn arity(&self) -> usize { 2 }
+
+    fn name(&self) -> &str { "hasField" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let instance = expect_instance(interpreter, "hasField", &arguments[0])?;
+        let name = expect_field_name(interpreter, "hasField", &arguments[1])?;
+        Ok(Value::Bool(instance.borrow().has_field(&name)))
+    }
+}
+
+impl fmt::Display for HasFieldFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+#[derive(Debug)]
+pub struct RemoveFieldFn;
+
+impl LoxCallable for RemoveFieldFn {
+    fn arity(&self) -> usize { 2 }
+
+    fn name(&self) -> &str { "removeField" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let instance = expect_instance(interpreter, "removeField", &arguments[0])?;
+        let name = expect_field_name(interpreter, "removeField", &arguments[1])?;
+        let removed = instance.borrow_mut().remove_field(&name);
+        removed
+            .map(Value::Bool)
+            .map_err(|message| interpreter.native_error(message))
+    }
+}
+
+impl fmt::Display for RemoveFieldFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldsFn;
+
+impl LoxCallable for FieldsFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "fields" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let instance = expect_instance(interpreter, "fields", &arguments[0])?;
+        let names: Vec<Value> = instance.borrow().field_names().into_iter().map(|name| Value::String(name.into())).collect();
+        Ok(interpreter.heap.array(names))
+    }
+}
+
+impl fmt::Display for FieldsFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
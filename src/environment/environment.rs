@@ -1,14 +1,53 @@
-use std::collections::HashMap;
-use crate::{Literal, RuntimeError, Stmt, TokenType, Value};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::{Literal, RuntimeError, TokenType, Value};
 use crate::lexer::Token;
 
-#[derive(Debug, Clone, Default)]
+/*
+Environments are shared, not copied. Every scope lives behind an `Rc<RefCell<Environment>>`,
+and a nested scope holds a handle to its parent rather than owning a copy of it.
+
+This matters for closures. When a function is declared, it captures a handle to the environment
+that is active at that point. If two functions are declared in the same scope, they capture the
+*same* environment, so a write made through one of them is observed by the other:
+
+    fun make() {
+        var n = 0;
+        fun inc() { n = n + 1; }
+        fun get() { return n; }
+    }
+
+Here `inc` and `get` both close over the one environment holding `n`. The same goes for a closure
+created before a later assignment in its scope - it reads the variable when it runs, not when it
+was created, so it sees the newest value.
+
+We share whole environments rather than boxing each captured variable into its own upvalue cell.
+That is the design the book uses for jlox, and it keeps variable lookup a plain walk up the chain.
+
+Entering a block or calling a function therefore costs one small, empty child environment that
+points at the current one. Nothing in the outer scopes is copied, however large the values bound
+there are. `Environment` deliberately does not implement `Clone`, so a scope can only ever be
+shared through its `Rc`, never duplicated by accident.
+
+A binding made by `const` can't be assigned to afterwards. Each environment keeps the names of its
+constant bindings next to the values, and both `assign` and `assign_at` check the environment
+that holds the binding, so the error is the same however the variable was resolved. Nor can a
+constant be declared again, with `var`, `const`, `fun` or `class`, in the same scope: the
+resolver reports that for a local, and `declare` refuses it for a global, which a later REPL line
+or an imported file can declare again long after the resolver has looked at the first one.
+*/
+
+#[derive(Debug, Default)]
 pub struct Environment {
     /// Bindings for *this* scope
-    values: HashMap<String, Value>,
+    pub(crate) values: HashMap<String, Value>,
 
-    /// Optional parent scope
-    pub(crate) enclosing: Option<Box<Environment>>,
+    /// Optional parent scope, shared with every closure that captured it
+    pub(crate) enclosing: Option<Rc<RefCell<Environment>>>,
+
+    /// The names in `values` that were declared with `const`
+    constants: HashSet<String>,
 }
 
 impl Environment {
@@ -17,28 +56,76 @@ impl Environment {
         Environment {
             values: HashMap::new(),
             enclosing: None,
+            constants: HashSet::new(),
         }
     }
 
-    /// Create a nested environment that owns its parent (`Box`).
-    pub fn new_enclosed(enclosing: Environment) -> Self {
+    /// Create a nested environment that points at its (shared) parent.
+    pub fn new_enclosed(enclosing: Rc<RefCell<Environment>>) -> Self {
         Environment {
             values: HashMap::new(),
-            enclosing: Some(Box::new(enclosing)),
+            enclosing: Some(enclosing),
+            constants: HashSet::new(),
         }
     }
 
     pub fn define(&mut self, name: String, value: Value) {
         // Insert or shadow without extra checks.
+        self.constants.remove(&name);
+        self.values.insert(name, value);
+    }
+
+    /// Define a binding that `assign` and `assign_at` will refuse to change.
+    pub fn define_constant(&mut self, name: String, value: Value) {
+        self.constants.insert(name.clone());
         self.values.insert(name, value);
     }
-    
+
+    /// Bind `name` for a declaration in the program, a constant one with `constant`. Unlike
+    /// `define`, this is an error when the name is already a constant in this environment.
+    pub fn declare(&mut self, name: &Token, value: Value, constant: bool) -> Result<(), RuntimeError> {
+        if self.constants.contains(&name.lexeme) {
+            return Err(RuntimeError::new(
+                name.clone(),
+                format!("Cannot redeclare constant '{}'.", name.lexeme),
+            ));
+        }
+        if constant {
+            self.define_constant(name.lexeme.clone(), value);
+        } else {
+            self.define(name.lexeme.clone(), value);
+        }
+        Ok(())
+    }
+
+    // an assignment to `name` in this environment is an error if it was declared with `const`
+    fn check_assignable(&self, name: &Token) -> Result<(), RuntimeError> {
+        if self.constants.contains(&name.lexeme) {
+            return Err(RuntimeError::new(
+                name.clone(),
+                format!("Cannot assign to constant '{}'.", name.lexeme),
+            ));
+        }
+        Ok(())
+    }
+
+    /*
+    `get` and `assign` walk up the chain in a loop rather than by recursion, holding one handle at
+    a time, so a lookup costs no Rust stack however many scopes lie between the variable and the
+    code reading it. Deeply nested blocks and closures could otherwise overflow the stack on a
+    plain read of a global.
+    */
     pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
         if let Some(v) = self.values.get(&name.lexeme) {
             return Ok(v.clone());
         }
-        if let Some(ref parent) = self.enclosing {
-            return parent.get(name); // recursive borrow is fine
+        let mut next = self.enclosing.clone();
+        while let Some(environment) = next {
+            let environment = environment.borrow();
+            if let Some(v) = environment.values.get(&name.lexeme) {
+                return Ok(v.clone());
+            }
+            next = environment.enclosing.clone();
         }
         Err(RuntimeError::new(
             name.clone(),
@@ -48,11 +135,19 @@ impl Environment {
 
     pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
         if self.values.contains_key(&name.lexeme) {
+            self.check_assignable(name)?;
             self.values.insert(name.lexeme.clone(), value);
             return Ok(());
         }
-        if let Some(ref mut parent) = self.enclosing {
-            return parent.assign(name, value); // recurse mutably
+        let mut next = self.enclosing.clone();
+        while let Some(environment) = next {
+            let mut environment = environment.borrow_mut();
+            if environment.values.contains_key(&name.lexeme) {
+                environment.check_assignable(name)?;
+                environment.values.insert(name.lexeme.clone(), value);
+                return Ok(());
+            }
+            next = environment.enclosing.clone();
         }
         Err(RuntimeError::new(
             name.clone(),
@@ -61,37 +156,44 @@ impl Environment {
     }
 
     pub fn assign_at(&mut self, distance: usize, name: &Token, value: Value) -> Result<(), RuntimeError> {
-        // Get the correct ancestor environment at the given depth and mutably borrow it
-        let ancestor = self.ancestor_mut(distance);
-        ancestor.values.insert(name.lexeme.clone(), value); // Insert at the correct environment
-        Ok(())
-    }
-
-    pub fn ancestor_mut(&mut self, distance: usize) -> &mut Environment {
-        let mut environment = self;
-        for _ in 0..distance {
-            match &mut environment.enclosing {
-                Some(parent) => environment = parent,
-                None => panic!("Ancestor not found, should not happen"),
-            }
+        if distance == 0 {
+            self.check_assignable(name)?;
+            self.values.insert(name.lexeme.clone(), value);
+        } else {
+            // Get the correct ancestor environment at the given depth and mutably borrow it
+            let ancestor = self.ancestor(distance);
+            let mut ancestor = ancestor.borrow_mut();
+            ancestor.check_assignable(name)?;
+            ancestor.values.insert(name.lexeme.clone(), value); // Insert at the correct environment
         }
-        environment
+        Ok(())
     }
 
-    pub fn ancestor(&self, distance: usize) -> &Environment {
-        let mut environment = self;
-        for _ in 0..distance {
-            match &environment.enclosing {
-                Some(parent) => environment = parent,
-                None => panic!("Ancestor not found, should not happen"),
-            }
+    /// Walk `distance` hops up the chain. `distance` must be at least one, since the
+    /// environment at distance zero is `self` and we do not hold a handle to it.
+    pub fn ancestor(&self, distance: usize) -> Rc<RefCell<Environment>> {
+        let mut environment = self
+            .enclosing
+            .clone()
+            .expect("Ancestor not found, should not happen");
+        for _ in 1..distance {
+            let parent = environment
+                .borrow()
+                .enclosing
+                .clone()
+                .expect("Ancestor not found, should not happen");
+            environment = parent;
         }
         environment
     }
 
     pub fn get_at(&self, distance: usize, name: &str) -> Result<Value, RuntimeError> {
-        let ancestor = self.ancestor(distance);
-        ancestor.values.get(name).cloned().ok_or_else(|| {
+        let value = if distance == 0 {
+            self.values.get(name).cloned()
+        } else {
+            self.ancestor(distance).borrow().values.get(name).cloned()
+        };
+        value.ok_or_else(|| {
             let dummy_token = Token {
                 token_type: TokenType::LeftParen,
                 lexeme: name.to_string(),
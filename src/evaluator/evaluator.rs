@@ -8,13 +8,17 @@ The user sees these as Lox objects, but they are implemented in the underlying l
 That means bridging the lands of Lox's dynamic typing and Java's static types. A variable in Lox can
 store a value of any (Lox) type and can even store values of different types at different points in time.
 */
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::lexer::{Literal, TokenType};
-use crate::parser::expr::{Expr, Visitor};
-use crate::{Environment, Interpreter, LoxFunction, LoxInstance, Stmt, StmtVisitor, Token};
-use crate::{LoxClass};
+use crate::parser::expr::{Expr, ExprId, Visitor};
+use crate::{Environment, GcRef, Heap, LoxFunction, LoxInstance, Stmt, StmtVisitor, Token};
+use crate::{check_index, key_value, map_key, ArrayMethod, LoxClass, LoxMap, MapMethod, Namespace, Rng};
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Formatter;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
 use std::rc::Rc;
 
 /*
@@ -26,50 +30,275 @@ which is observed at runtime.
 */
 
 pub struct Evaluator {
-    globals: Environment,
-    pub(crate) environment: Environment,
-    locals: HashMap<Expr, usize>,
+    pub(crate) globals: Rc<RefCell<Environment>>,
+    pub(crate) environment: Rc<RefCell<Environment>>,
+    // how many scopes out each resolved variable reference lives, keyed by the expression's id.
+    // References that the resolver left out are globals
+    locals: HashMap<ExprId, usize>,
+    // for each `object.name` site, the class of the last instance it looked a method up on and
+    // the method it found, see `get_property`
+    method_cache: HashMap<ExprId, (usize, LoxFunction)>,
+    // the text of every string literal evaluated so far, see `intern`
+    strings: HashSet<Rc<str>>,
+    // where `print` writes to. This is stdout unless the embedder hands us something else,
+    // e.g. an in-memory buffer so the output of a program can be inspected
+    output: Box<dyn Write>,
+    // where `eprint` writes to, stderr unless replaced the same way
+    error_output: Box<dyn Write>,
+    // every environment we create, so that cycles between them can be collected
+    pub(crate) heap: Heap,
+    // the largest string (in bytes) or collection (in elements) a program may build in one step
+    allocation_limit: usize,
+    // report operand type errors with the book's exact wording, for matching its test suite
+    book_messages: bool,
+    // let division by zero produce inf or NaN, as IEEE 754 does, instead of raising an error
+    ieee_division: bool,
+    // functions (by the id of their `Stmt::Function` declaration) that return a value on some
+    // paths but can also fall off the end of their body, as found by the resolver
+    implicit_returns: HashSet<ExprId>,
+    // make falling off the end of one of those functions a runtime error
+    strict_returns: bool,
+    // how many times one run of a loop may go round, if there is a limit
+    max_loop_iterations: Option<usize>,
+    // the limits on a whole run, and what is left of them in the current one, see `Limits`.
+    // `budget` is None when there are no limits, so keeping count costs a single branch
+    limits: Limits,
+    budget: Option<Budget>,
+    // every statement executed over the evaluator's life, for `RunReport`
+    statements_executed: u64,
+    // the built-in `Error` class, which runtime errors are turned into instances of, see `error_value`
+    pub(crate) error_class: Option<LoxClass>,
+    // the array the host put in the global `args`, see `Interpreter::set_args`
+    pub(crate) script_args: Option<Rc<RefCell<Vec<Value>>>>,
+    // when the evaluator was created, the zero point for `clock()`
+    pub(crate) started: Instant,
+    // the generator behind `random` and friends, one per evaluator so interpreters don't share it
+    pub(crate) rng: Rng,
+    // how many calls are in progress, and how many may be before we report a stack overflow
+    call_depth: usize,
+    max_call_depth: usize,
+    // the closing paren of the call being made, so natives can report errors at the call site
+    call_site: Option<Token>,
+    // the Lox functions being run, outermost first, for the stack trace of a runtime error
+    frames: Vec<CallFrame>,
+    // the instances whose `toString` is running, see `stringify`
+    stringifying: Vec<*const ()>,
+    // how many `try` bodies the code running is in, within the current call, see `visit_return_stmt`
+    pub(crate) try_depth: usize,
+}
+
+/*
+When a runtime error surfaces several calls deep, the line it happened on is not always enough:
+the same function may be called from many places, and only one of them led to the error. So the
+evaluator keeps a stack of the Lox functions being run, each with the line it was called from.
+`LoxFunction::call` pushes a frame on the way in and pops it on the way out, whether the call
+returned, raised an error, or was cut short by the call-depth limit.
+
+An error records a copy of the stack the first time it leaves a function, and the copy stays with
+it from then on, so a later error starts from a clean slate. `RuntimeError::stack_trace` turns it
+into a line like
+
+    in method area (line 12), called from function main (line 30), called from script (line 40)
+
+which reads from the innermost call outwards. A tail call replaces the frame of the function
+making it, as it replaces the function itself (see `LoxFunction::call`), so the function that made
+the tail call does not appear.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    pub function: String,
+    pub is_method: bool,
+    // the line of the call that started this frame
+    pub call_line: usize,
+}
+
+impl Display for CallFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_method { "method" } else { "function" };
+        write!(f, "{} {}", kind, self.function)
+    }
+}
+
+/// Default for `Evaluator::max_call_depth`.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// Default for `Evaluator::allocation_limit`: 16 MiB of string data, or that many collection elements.
+pub const DEFAULT_ALLOCATION_LIMIT: usize = 1 << 24;
+
+/*
+How much work one run of a program may do before it is stopped with "Execution budget exceeded.",
+for a host running scripts it doesn't trust. `max_steps` counts statements executed, `max_millis`
+is wall-clock time since the run started. Either may be left unset, and by default both are.
+
+Every statement is charged a step, but the budget is only checked where a program can go on
+forever: each pass of a loop and each call. Straight-line code between those is bounded by the
+length of the program anyway, and this way there is always a token to report the error at. The
+clock is only read on every 64th check, as reading it costs more than the rest put together.
+The budget starts afresh with every run, so an interpreter that ran out can run something else.
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_steps: Option<u64>,
+    pub max_millis: Option<u64>,
+}
+
+impl Limits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_steps.is_none() && self.max_millis.is_none()
+    }
+}
+
+// what is left of the limits during a run
+#[derive(Debug)]
+struct Budget {
+    max_steps: Option<u64>,
+    deadline: Option<Instant>,
+    steps: u64,
+    checks: u32,
+}
+
+impl Budget {
+    fn start(limits: Limits) -> Self {
+        Self {
+            max_steps: limits.max_steps,
+            deadline: limits.max_millis.map(|millis| Instant::now() + Duration::from_millis(millis)),
+            steps: 0,
+            checks: 0,
+        }
+    }
+
+    fn exceeded(&mut self) -> bool {
+        if self.max_steps.is_some_and(|max| self.steps > max) {
+            return true;
+        }
+        self.checks = self.checks.wrapping_add(1);
+        self.checks.is_multiple_of(64) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // `exceeded`, reading the clock every time, for the rare check that has to be exact
+    fn spent(&self) -> bool {
+        self.max_steps.is_some_and(|max| self.steps > max) || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
 }
 
 // representation of lox values at runtime
 #[derive(Debug, Clone)]
 pub enum Value {
-    Number(f64),
+    // whole numbers written without a decimal point, and what arithmetic on them produces
+    Int(i64),
+    Float(f64),
     Bool(bool),
-    String(String),
+    // immutable, so copies share one allocation; literals are interned, see `Evaluator::intern`
+    String(Rc<str>),
     Nil,
     Callable(Rc<dyn LoxCallable>),
     LoxClass(LoxClass),
-    LoxInstance(LoxInstance),
+    LoxInstance(Rc<RefCell<LoxInstance>>),
     LoxFunction(LoxFunction),  // Add this variant for LoxFunction
+    // shared, so every reference to an array sees changes made through the others
+    Array(Rc<RefCell<Vec<Value>>>),
+    // shared like arrays, see map.rs
+    Map(Rc<RefCell<LoxMap>>),
+    // what `import "path" as name;` binds name to, see namespace.rs
+    Namespace(Namespace),
+    // `start..end`, the whole numbers from `start` up to but not including `end`. It is only the
+    // two bounds, however many numbers it stands for
+    Range { start: i64, end: i64 },
+}
+
+/*
+How many arguments a call may pass: exactly as many as there are parameters for almost every
+callable, while a variadic native like `format` takes a fixed number followed by any number more.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(self, given: usize) -> bool {
+        match self {
+            Arity::Exact(n) => given == n,
+            Arity::AtLeast(n) => given >= n,
+        }
+    }
+}
+
+// as it reads in "Expected ... arguments": `2`, or `at least 1`
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Arity::Exact(n) => write!(f, "{}", n),
+            Arity::AtLeast(n) => write!(f, "at least {}", n),
+        }
+    }
 }
 
-pub trait LoxCallable: std::fmt::Debug {
+// every callable says how it prints: `<fn name>` for functions, `<native fn>` for natives
+pub trait LoxCallable: std::fmt::Debug + std::fmt::Display {
+    /// The number of parameters. For a variadic callable, the number of arguments it needs at least.
     fn arity(&self) -> usize;
+
+    /// How many arguments a call may pass, which the evaluator checks before making the call.
+    /// Exactly `arity()` unless the callable is variadic.
+    fn expected_arity(&self) -> Arity {
+        Arity::Exact(self.arity())
+    }
+
+    /// The name the callable goes by: a function's or class's declared name, or the global a
+    /// native is defined under. Errors about a call use it to say which callable was meant.
+    fn name(&self) -> &str;
+
     fn call(
         &self,
         interpreter: &mut Evaluator,
         arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError>;
+
+    /// The names of the parameters, for binding arguments given by name. Natives have none,
+    /// so they only take positional arguments.
+    fn param_names(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Whether this callable is built into the interpreter rather than declared in Lox, which
+    /// `type()` reports as "native" instead of "function".
+    fn is_native(&self) -> bool {
+        true
+    }
+
+    /// The function itself, if this callable is one declared in Lox, for tail calls to it.
+    fn as_lox_function(&self) -> Option<&LoxFunction> {
+        None
+    }
+
+    /// Report every heap object this callable keeps alive to the cycle collector.
+    /// Natives do not capture anything, so the default reports nothing.
+    fn trace(&self, _visit: &mut dyn FnMut(GcRef)) {}
 }
 
+/*
+`clock()` is the number of seconds, as a float, since the interpreter was created. It is measured
+with `Instant`, which is monotonic: it never goes backwards and is not affected by the system
+clock being adjusted, so the difference between two calls is always a meaningful duration. The
+flip side is that it is not the time of day. Its resolution is whatever the platform's monotonic
+clock offers, which is well below a millisecond everywhere we run.
+*/
 #[derive(Debug)]
 pub struct ClockFn;
 
 impl LoxCallable for ClockFn {
     fn arity(&self) -> usize { 0 }
 
+    fn name(&self) -> &str { "clock" }
+
     fn call(
         &self,
-        _interpreter: &mut Evaluator,
+        interpreter: &mut Evaluator,
         _arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let secs = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs_f64();
-        Ok(Value::Number(secs))
+        Ok(Value::Float(interpreter.started.elapsed().as_secs_f64()))
     }
 }
 
@@ -79,17 +308,322 @@ impl fmt::Display for ClockFn {
     }
 }
 
+impl Value {
+    /// The name of this value's runtime type, as it appears in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            // one type as far as programs can tell: `1` and `1.0` are both numbers, and equal
+            Value::Int(_) | Value::Float(_) => "number",
+            Value::Bool(_) => "boolean",
+            Value::String(_) => "string",
+            Value::Nil => "nil",
+            Value::Callable(callable) if callable.is_native() => "native",
+            Value::Callable(_) | Value::LoxFunction(_) => "function",
+            Value::LoxClass(_) => "class",
+            Value::LoxInstance(_) => "instance",
+            Value::Array(_) => "array",
+            Value::Map(_) => "map",
+            Value::Namespace(_) => "module",
+            Value::Range { .. } => "range",
+        }
+    }
+
+    /*
+    Equality, as `==` and `!=` see it. This is the one place that decides it, so anything else
+    that needs to compare values should come here too.
+
+    Numbers, strings and booleans are equal when their values are. An int and a float are equal
+    when they are the same number, exactly, so `1 == 1.0`, but 9007199254740993 is not equal
+    to any float. `nil` is only equal to `nil`, and values of different types are never equal; there are
+    no other implicit conversions. Following IEEE 754, NaN is not equal to anything, itself
+    included.
+
+    Classes, instances, maps, modules and functions are compared by identity: they are equal only
+    when both sides refer to the same object. Two instances with identical fields are still two
+    instances.
+    Arrays are the exception among objects: they are equal when their elements are, in order.
+    Ranges are values like numbers, equal when their bounds are.
+    */
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(b1), Value::Bool(b2)) => b1 == b2,
+            (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+                compare_numbers(self, other) == Some(Ordering::Equal)
+            }
+            // an interned string is often compared with itself, which saves looking at the text
+            (Value::String(s1), Value::String(s2)) => Rc::ptr_eq(s1, s2) || s1 == s2,
+            (Value::Callable(c1), Value::Callable(c2)) => std::ptr::addr_eq(Rc::as_ptr(c1), Rc::as_ptr(c2)),
+            (Value::LoxFunction(f1), Value::LoxFunction(f2)) => f1.same_object(f2),
+            (Value::LoxClass(k1), Value::LoxClass(k2)) => k1.same_object(k2),
+            (Value::LoxInstance(i1), Value::LoxInstance(i2)) => Rc::ptr_eq(i1, i2),
+            (Value::Map(m1), Value::Map(m2)) => Rc::ptr_eq(m1, m2),
+            (Value::Namespace(n1), Value::Namespace(n2)) => n1.same_object(n2),
+            (Value::Range { start: s1, end: e1 }, Value::Range { start: s2, end: e2 }) => s1 == s2 && e1 == e2,
+            (Value::Array(a1), Value::Array(a2)) => {
+                // an array is always equal to itself, which also saves borrowing it twice
+                Rc::ptr_eq(a1, a2) || {
+                    let (a1, a2) = (a1.borrow(), a2.borrow());
+                    a1.len() == a2.len() && a1.iter().zip(a2.iter()).all(|(x, y)| x.equals(y))
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Like `Display`, except that strings are quoted (and escaped) so they can be told apart
+    /// from other values, e.g. `"nil"` from `nil`. Meant for debugging output and the REPL's echo.
+    pub fn repr(&self) -> String {
+        match self {
+            Value::String(s) => format!("{:?}", s),
+            _ => self.to_string(),
+        }
+    }
+
+    /// The value of a number as a float, which is how ints take part in mixed arithmetic.
+    /// `None` for anything that isn't a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The value of a whole number: any int, or a float with no fractional part that fits in an
+    /// i64, so that `a[2.0]` works like `a[2]`. `None` for anything else.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Int(n) => Some(*n),
+            Value::Float(n) => float_to_int(*n),
+            _ => None,
+        }
+    }
+
+    // the type and the value itself, e.g. `string ("abc")`, for pointing at a bad operand
+    pub(crate) fn describe(&self) -> String {
+        format!("{} ({})", self.type_name(), self.repr())
+    }
+}
+
+/*
+The ordering used by anything that sorts or ranks values. Only numbers and strings have one:
+numbers compare numerically and strings lexicographically, by Unicode code point, so "B" comes
+before "a". Anything else, a number against a string, or NaN (which is neither less than, equal
+to nor greater than any number), is a runtime error reported at `token`. Quietly putting such
+values somewhere would give a sort that depends on the order of its input.
+
+Equality is deliberately not defined in terms of this: `Value::equals` holds for any two values,
+while most pairs have no order at all.
+*/
+pub fn compare_values(a: &Value, b: &Value, token: &Token) -> Result<Ordering, RuntimeError> {
+    let ordering = match (a, b) {
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => compare_numbers(a, b),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ => None,
+    };
+    ordering.ok_or_else(|| RuntimeError::new(token.clone(), cant_order(&a.describe(), &b.describe())))
+}
+
+fn cant_order(a: &str, b: &str) -> String {
+    format!("Can't order {} and {}.", a, b)
+}
+
+/*
+Numbers come in two representations. Whole numbers written without a decimal point are ints,
+64-bit and exact, and `+`, `-` and `*` on two ints give an int. Everything else is a float, an
+f64: literals with a decimal point, the result of `/`, and any arithmetic that mixes the two, where
+the int is converted first. An int result that would overflow becomes the float the operation
+would have produced instead, so arithmetic never fails, though it stops being exact there.
+
+Programs rarely need to care which one they have: they are the same type, `number`, ints and
+floats compare and hash by their numeric value, and both are accepted wherever a whole number is
+expected as long as the float has no fractional part. `divInt` is the integer division.
+*/
+
+/// The order of two numbers, either representation, by their exact values. `None` when either is
+/// NaN, or if either isn't a number.
+pub fn compare_numbers(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) => Some(x.cmp(y)),
+        (Value::Float(x), Value::Float(y)) => x.partial_cmp(y),
+        (Value::Int(x), Value::Float(y)) => compare_int_float(*x, *y),
+        (Value::Float(x), Value::Int(y)) => compare_int_float(*y, *x).map(Ordering::reverse),
+        _ => None,
+    }
+}
+
+// Converting the int to a float would round anything past 2^53, so instead split the float into
+// its whole part, which does fit in an i64 once the out-of-range floats are dealt with, and its
+// fraction, which only matters when the whole parts are equal
+fn compare_int_float(int: i64, float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        None
+    } else if float >= 9_223_372_036_854_775_808.0 {
+        Some(Ordering::Less)
+    } else if float < -9_223_372_036_854_775_808.0 {
+        Some(Ordering::Greater)
+    } else {
+        let whole = float.trunc();
+        Some(int.cmp(&(whole as i64)).then(0.0.partial_cmp(&(float - whole))?))
+    }
+}
+
+/// The int with the same value as `n`, if there is one.
+pub fn float_to_int(n: f64) -> Option<i64> {
+    // 2^63 itself is out of range, while -2^63 is i64::MIN
+    (n.fract() == 0.0 && (-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&n))
+        .then_some(n as i64)
+}
+
+// `+`, `-` and `*` on two numbers: exact on two ints while the result fits, a float otherwise
+fn arithmetic(left: &Value, right: &Value, int_op: fn(i64, i64) -> Option<i64>, float_op: fn(f64, f64) -> f64) -> Value {
+    if let (Value::Int(a), Value::Int(b)) = (left, right)
+        && let Some(n) = int_op(*a, *b)
+    {
+        return Value::Int(n);
+    }
+    match (left.as_f64(), right.as_f64()) {
+        (Some(a), Some(b)) => Value::Float(float_op(a, b)),
+        _ => unreachable!(), // the operands have been checked to be numbers
+    }
+}
+
+/*
+Floats are printed so that the ones holding whole numbers look like ints, the way jlox prints
+every number: `print 4 / 2;` shows `2`, not `2.0`. This is the one place floats are turned into
+text, so `print`, string concatenation and the AST printer all agree (ints print as themselves):
+
+- whole numbers have no decimal point, and negative zero keeps its sign: `5`, `-0`
+- everything else uses the shortest text that reads back as the same f64: `0.1`, `2.5`
+- NaN and the infinities are `nan`, `inf` and `-inf`
+*/
+pub fn stringify_number(n: f64) -> String {
+    if n.is_nan() {
+        "nan".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        // Rust's Display for f64 is already shortest round-trip, and leaves off a zero fraction
+        format!("{}", n)
+    }
+}
+
+/*
+This is how a value reads when the program prints it, and the only place that decides it. `print`,
+string concatenation and the values interpolated into error messages all go through here.
+Strings are shown as they are, without quotes; see `Value::repr` for the quoted form.
+
+`print`, concatenation, `str`, `eprint` and `join` go through `Evaluator::stringify`, which is the
+same except that it lets an instance describe itself with a `toString` method. So do the values
+shown in type errors, through `Evaluator::describe`, but those fall back on this, which never runs
+any Lox code, when `toString` fails: an error should not be able to raise another one.
+*/
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Value::Number(n) => write!(f, "{}", n),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(n) => write!(f, "{}", stringify_number(*n)),
             Value::Bool(b) => write!(f, "{}", b),
             Value::String(s) => write!(f, "{}", s),
             Value::Nil => write!(f, "nil"),
-            Value::Callable(_) => write!(f, "<fn>"),
-            Value::LoxClass(klass) => write!(f, "{}", klass.stringify()),
-            Value::LoxInstance(instance) => write!(f, "{}", instance.stringify()),
+            Value::Callable(callable) => write!(f, "{}", callable),
+            Value::LoxClass(klass) => write!(f, "{}", klass),
+            Value::LoxInstance(instance) => write!(f, "{}", instance.borrow()),
             Value::LoxFunction(fun) => write!(f, "{}", fun),
+            Value::Namespace(namespace) => write!(f, "{}", namespace),
+            Value::Range { start, end } => write!(f, "{}..{}", start, end),
+            Value::Array(_) | Value::Map(_) => {
+                let mut text = String::new();
+                self.write_text(&mut text, &mut Vec::new(), &mut |instance| {
+                    Ok::<_, fmt::Error>(instance.borrow().to_string())
+                })?;
+                f.write_str(&text)
+            }
+        }
+    }
+}
+
+// how `Value::write_text` turns an instance into text
+type InstanceText<'a, E> = dyn FnMut(&Rc<RefCell<LoxInstance>>) -> Result<String, E> + 'a;
+
+impl Value {
+    /*
+    How a value is written inside an array or a map. A string on its own prints as its bare text,
+    but as an element it is quoted, as `repr` does, so that `["1", 1]` shows which is which and a
+    string containing ", " can't pass for two elements: `print ["a", [1, "b"]]` shows exactly that.
+
+    An array or map can contain itself, directly or through others, and printing it naively would
+    never finish. `open` holds the collections we are in the middle of writing; one that turns up
+    again inside itself is written as `...`, so after `var a = [1]; a.push(a);`, `a` prints as
+    `[1, ...]`. The same collection appearing twice side by side is not a cycle, and is written out
+    both times.
+
+    How an instance reads is up to `instance_text`, so the same walk serves `Display`, which never
+    runs Lox code, and `Evaluator::stringify`, which calls `toString` and can fail.
+    */
+    fn write_text<E>(
+        &self,
+        out: &mut String,
+        open: &mut Vec<*const ()>,
+        instance_text: &mut InstanceText<'_, E>,
+    ) -> Result<(), E> {
+        match self {
+            Value::LoxInstance(instance) => out.push_str(&instance_text(instance)?),
+            Value::Array(array) => {
+                let address = Rc::as_ptr(array) as *const ();
+                if open.contains(&address) {
+                    out.push_str("...");
+                    return Ok(());
+                }
+                open.push(address);
+                out.push('[');
+                for (i, element) in array.borrow().iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    element.write_element(out, open, instance_text)?;
+                }
+                open.pop();
+                out.push(']');
+            }
+            Value::Map(map) => {
+                let address = Rc::as_ptr(map) as *const ();
+                if open.contains(&address) {
+                    out.push_str("...");
+                    return Ok(());
+                }
+                open.push(address);
+                out.push('{');
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    key_value(key).write_element(out, open, instance_text)?;
+                    out.push_str(": ");
+                    value.write_element(out, open, instance_text)?;
+                }
+                open.pop();
+                out.push('}');
+            }
+            other => out.push_str(&other.to_string()),
+        }
+        Ok(())
+    }
+
+    // `write_text` for a value inside a collection, where strings are quoted
+    fn write_element<E>(
+        &self,
+        out: &mut String,
+        open: &mut Vec<*const ()>,
+        instance_text: &mut InstanceText<'_, E>,
+    ) -> Result<(), E> {
+        match self {
+            Value::String(s) => {
+                out.push_str(&format!("{:?}", s));
+                Ok(())
+            }
+            other => other.write_text(out, open, instance_text),
         }
     }
 }
@@ -100,11 +634,13 @@ impl Visitor for Evaluator {
     // // Now, we take the AST expression and unpack its value.
     fn visit_literal_expr(&mut self, literal: &Literal) -> Result<Value, RuntimeError> {
         match literal {
-            Literal::Number(n) => Ok(Value::Number(*n)),
+            Literal::Int(n) => Ok(Value::Int(*n)),
+            Literal::Float(n) => Ok(Value::Float(*n)),
             Literal::Bool(true) => Ok(Value::Bool(true)),
             Literal::Bool(false) => Ok(Value::Bool(false)),
             Literal::Nil => Ok(Value::Nil),
-            Literal::String(s) => Ok(Value::String(s.clone())),
+            // every evaluation of the same text shares one string, see `intern`
+            Literal::String(s) => Ok(Value::String(self.intern(s))),
         }
     }
     // Since a grouping node has a reference to an expression inside parentheses,
@@ -117,13 +653,19 @@ impl Visitor for Evaluator {
     fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<Value, RuntimeError> {
         let right_val = self.evaluate(right)?;
 
+        // `-v` on an instance whose class has `negate()`
+        if let Some(result) = self.call_operator_method(operator, &right_val, Vec::new()) {
+            return result;
+        }
+
         match operator.token_type {
             TokenType::Minus => {
                 self.check_number_operand(operator.clone(), &right_val)?;
-                if let Value::Number(n) = right_val {
-                    Ok(Value::Number(-n))
-                } else {
-                    unreachable!() // this can't happen due to check_number_operand
+                match right_val {
+                    // the one int without a negation is i64::MIN
+                    Value::Int(n) => Ok(n.checked_neg().map_or(Value::Float(-(n as f64)), Value::Int)),
+                    Value::Float(n) => Ok(Value::Float(-n)),
+                    _ => unreachable!(), // this can't happen due to check_number_operand
                 }
             }
             TokenType::Bang => Ok(Value::Bool(!self.is_truthy(&right_val))),
@@ -143,142 +685,39 @@ impl Visitor for Evaluator {
         // a consequence of post-order traversal of AST is that we evaluate the left and right
         // subexpressions first before applying the operator. As a consequence, if there is
         // an error and our sub-expressions have side effects, they will be produced first before
-        // raising a runtime error
-        let value_left: Value = self.evaluate(left)?;
-        let value_right: Value = self.evaluate(right)?;
-        match operator.token_type {
-            TokenType::Minus => {
-                self.check_number_operands(operator.clone(), &value_right, &value_left)?;
-                match value_left {
-                    Value::Number(n1) => match value_right {
-                        // note the subtly here that we evaluate from left-to-right.
-                        // This means side effects will also be processed in left-to-right order
-                        Value::Number(n2) => Ok(Value::Number(n1 - n2)),
-                        _ => {
-                            panic!("Right subexpression is not a number")
-                        }
-                    },
-                    _ => {
-                        panic!("Left subexpression is not a number")
-                    }
-                }
-            }
-            TokenType::Slash => {
-                self.check_number_operands(operator.clone(), &value_right, &value_left)?;
-                match value_left {
-                    Value::Number(n1) => match value_right {
-                        Value::Number(n2) => Ok(Value::Number(n1 / n2)),
-                        _ => {
-                            panic!("Right subexpression is not a number")
-                        }
-                    },
-                    _ => {
-                        panic!("Left subexpression is not a number")
-                    }
-                }
-            }
-            TokenType::Star => {
-                self.check_number_operands(operator.clone(), &value_right, &value_left)?;
-                match value_left {
-                    Value::Number(n1) => match value_right {
-                        Value::Number(n2) => Ok(Value::Number(n1 * n2)),
-                        _ => {
-                            panic!("Right subexpression is not a number")
-                        }
-                    },
-                    _ => {
-                        panic!("Left subexpression is not a number")
-                    }
-                }
-            }
-            TokenType::Plus => {
-                // left to right traversal
-                match value_left {
-                    Value::Number(n1) => match value_right {
-                        Value::Number(n2) => Ok(Value::Number(n1 + n2)),
-                        _ => {
-                            panic!("Right subexpression is not a number")
-                        }
-                    },
-                    Value::String(s1) => match value_right {
-                        Value::String(s2) => Ok(Value::String(format!("{}{}", s1, s2))),
-                        _ => {
-                            panic!("Right subexpression is not a string")
-                        }
-                    },
-                    _ => Err(RuntimeError::new(
-                        operator.clone(),
-                        "Operands must be two numbers or string".parse().unwrap(),
-                    )),
-                }
-            }
-            TokenType::Greater => {
-                self.check_number_operands(operator.clone(), &value_right, &value_left)?;
-                match value_left {
-                    Value::Number(n1) => match value_right {
-                        Value::Number(n2) => Ok(Value::Bool(n1 > n2)),
-                        _ => {
-                            panic!("Right subexpression is not a number")
-                        }
-                    },
-                    _ => {
-                        panic!("Left subexpression is not a number")
-                    }
-                }
-            }
-            TokenType::GreaterEqual => {
-                self.check_number_operands(operator.clone(), &value_right, &value_left)?;
-                match value_left {
-                    Value::Number(n1) => match value_right {
-                        Value::Number(n2) => Ok(Value::Bool(n1 >= n2)),
-                        _ => {
-                            panic!("Right subexpression is not a number")
-                        }
-                    },
-                    _ => {
-                        panic!("Left subexpression is not a number")
-                    }
-                }
-            }
-            TokenType::Less => {
-                self.check_number_operands(operator.clone(), &value_right, &value_left)?;
-                match value_left {
-                    Value::Number(n1) => match value_right {
-                        Value::Number(n2) => Ok(Value::Bool(n1 < n2)),
-                        _ => {
-                            panic!("Right subexpression is not a number")
-                        }
-                    },
-                    _ => {
-                        panic!("Left subexpression is not a number")
-                    }
-                }
-            }
-            TokenType::LessEqual => {
-                self.check_number_operands(operator.clone(), &value_right, &value_left)?;
-                match value_left {
-                    Value::Number(n1) => match value_right {
-                        Value::Number(n2) => Ok(Value::Bool(n1 <= n2)),
-                        _ => {
-                            panic!("Right subexpression is not a number")
-                        }
-                    },
-                    _ => {
-                        panic!("Left subexpression is not a number")
-                    }
+        // raising a runtime error.
+        //
+        // `1 + 2 + 3` is `(1 + 2) + 3`, so a long chain hangs off the left. Walking it with a loop
+        // rather than recursion keeps a generated chain of thousands of terms off the Rust stack.
+        // Most operands are not chains at all, and those skip building the spine
+        if !matches!(left, Expr::Binary { .. } | Expr::Grouping { .. }) {
+            let value_left = self.evaluate(left)?;
+            let value_right = self.evaluate(right)?;
+            return self.apply_binary(operator, value_left, value_right);
+        }
+        let mut spine = vec![(operator, right)];
+        let mut innermost = left;
+        loop {
+            match innermost {
+                Expr::Binary { left, operator, right } => {
+                    spine.push((operator, right));
+                    innermost = left;
                 }
-            }
-            TokenType::BangEqual => Ok(Value::Bool(self.is_equal(&value_left, &value_right))),
-            TokenType::EqualEqual => Ok(Value::Bool(self.is_equal(&value_left, &value_right))),
-            _ => {
-                panic!("Not a valid binary operator")
+                Expr::Grouping { expression } => innermost = expression,
+                _ => break,
             }
         }
-    }
 
+        let mut value = self.evaluate(innermost)?;
+        for (operator, right) in spine.into_iter().rev() {
+            let value_right = self.evaluate(right)?;
+            value = self.apply_binary(operator, value, value_right)?;
+        }
+        Ok(value)
+    }
 
-    fn visit_variable_expr(&mut self, token: &Token, _initializer: &Option<Box<Expr>>) -> Result<Value, RuntimeError> {
-        self.environment.get(token)
+    fn visit_variable_expr(&mut self, token: &Token, id: ExprId) -> Result<Value, RuntimeError> {
+        self.look_up_variable(token, id)
     }
 
     // first we evaluate the expression embedded in the unary expression,
@@ -293,106 +732,112 @@ impl Visitor for Evaluator {
     // in-order traversal: left child -> parent -> right child
     // depth order traversal: breadth-first search
 
-    fn visit_assign_expr(&mut self, token: &Token, value: &Expr) -> Result<Value, RuntimeError> {
+    fn visit_assign_expr(&mut self, token: &Token, value: &Expr, id: ExprId) -> Result<Value, RuntimeError> {
         let value = self.evaluate(value)?;
-        self.environment.assign(&token, value.clone())?;
+        // assign to exactly the binding the resolver found, the same one a read would see
+        if let Some(&distance) = self.locals.get(&id) {
+            self.environment.borrow_mut().assign_at(distance, token, value.clone())?;
+        } else {
+            self.globals.borrow_mut().assign(token, value.clone())?;
+        }
         Ok(value)
     }
-
     fn visit_logical_expr(
         &mut self,
         left: &Expr,
         operator: &Token,
         right: &Expr,
     ) -> Result<Value, RuntimeError> {
-        let left_val = self.evaluate(left)?;
-
-        match operator.token_type {
-            TokenType::Or => {
-                // short-circuit when the left side is truthy
-                if self.is_truthy(&left_val) {
-                    return Ok(left_val);
-                }
-            }
-            TokenType::And => {
-                // short-circuit when the left side is falsy
-                if !self.is_truthy(&left_val) {
-                    return Ok(left_val);
-                }
-            }
-            _ => {
-                return Err(RuntimeError::new(
-                    operator.clone(),
-                    "Unknown logical operator.".to_string(),
-                ))
+        // `a or b or c` is `(a or b) or c`, walked with a loop like the operands of a binary
+        // chain, see `visit_binary_expr`
+        if !matches!(left, Expr::Logical { .. }) {
+            let left_val = self.evaluate(left)?;
+            if self.short_circuits(operator, &left_val)? {
+                return Ok(left_val);
             }
+            // need the right-hand side value
+            return self.evaluate(right);
+        }
+        let mut spine = vec![(operator, right)];
+        let mut innermost = left;
+        while let Expr::Logical { left, operator, right } = innermost {
+            spine.push((operator, right));
+            innermost = left;
         }
 
-        // need the right-hand side value
-        self.evaluate(right)
+        let mut left_val = self.evaluate(innermost)?;
+        for (operator, right) in spine.into_iter().rev() {
+            if !self.short_circuits(operator, &left_val)? {
+                left_val = self.evaluate(right)?;
+            }
+        }
+        Ok(left_val)
     }
 
-    fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> Result<Value, RuntimeError> {
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        open_paren: &Token,
+        paren: &Token,
+        arguments: &[Expr],
+        names: &[Option<Token>],
+    ) -> Result<Value, RuntimeError> {
         /*
         First, we evaluate the expression for the callee. Typically, this
         expression is just an identifier that looks up the expression by name, but it could
         be anything. We evaluate each of the argument expressions in order and store
         the resulting values in a list.
         */
-        let callee_val = self.evaluate(callee)?;
-
-        // 2. Evaluate each argument
-        let mut arg_vals = Vec::with_capacity(arguments.len());
-        for arg in arguments {
-            arg_vals.push(self.evaluate(arg)?);
-        }
+        let (callee_val, arg_vals) = self.evaluate_call(callee, paren, arguments, names)?;
+        self.call_value(callee_val, open_paren, paren, arg_vals)
+    }
 
-        /* performing the call
-        We do that by casting the callee to a LoxCallable and then
-        invoking a `call()` method on it. The Java representation of any Lox
-        object thay can be called like a function implement this interface.
-        This includes user-defined functions and also class objects since classes are
-        'called' to construct new instances.
-        */
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token, optional: bool, id: ExprId) -> Result<Value, RuntimeError> {
+        let object = self.evaluate(object)?;
 
-        // 3. Check that the callee is actually callable
-        match callee_val {
-            Value::Callable(ref function) => {
-                // 3a. Arity check (optional but nice to keep the book’s behaviour)
-                if arg_vals.len() != function.arity() {
-                    return Err(RuntimeError::new(
-                        paren.clone(),
-                        format!(
-                            "Expected {} arguments but got {}.",
-                            function.arity(),
-                            arg_vals.len()
-                        ),
-                    ));
-                }
-                // 3b. Make the call
-                function.call(self, arg_vals)
-            }
+        // `a?.b` on a nil `a` gives nil. In a chain `a?.b?.c`, that nil then reaches the
+        // next `?.`, which passes it along too
+        if optional && matches!(object, Value::Nil) {
+            return Ok(Value::Nil);
+        }
 
-            _ => Err(RuntimeError::new(
-                paren.clone(),
-                "Can only call functions and classes.".to_string(),
-            )),
+        // neither do maps, see map.rs
+        if let Value::Map(ref map) = object {
+            return match MapMethod::lookup(map, &name.lexeme) {
+                Some(method) => Ok(Value::Callable(Rc::new(method))),
+                None => Err(RuntimeError::new(
+                    name.clone(),
+                    format!("Maps have no method '{}'.", name.lexeme),
+                )),
+            };
         }
-    }
 
-    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<Value, RuntimeError> {
-        let object = self.evaluate(object)?;
+        // arrays have no fields, only the built-in methods, see array.rs
+        if let Value::Array(ref array) = object {
+            return match ArrayMethod::lookup(array, &name.lexeme) {
+                Some(method) => Ok(Value::Callable(Rc::new(method))),
+                None => Err(RuntimeError::new(
+                    name.clone(),
+                    format!("Arrays have no method '{}'.", name.lexeme),
+                )),
+            };
+        }
 
-        // Check if the object is an instance (LoxInstance or similar in Rust)
-        if let Value::LoxInstance(instance) = object {
-            // Call the `get` method to retrieve the property
-            instance.get(name)
-        } else {
+        match object {
+            // a field, or a method found through this site's cache
+            Value::LoxInstance(instance) => self.get_property(&instance, name, id),
+            // something an imported module declared
+            Value::Namespace(namespace) => namespace.get(name),
+            // the usual way to get here is a variable that was never set, so say so plainly
+            Value::Nil => Err(RuntimeError::new(
+                name.clone(),
+                format!("Cannot access property '{}' on nil.", name.lexeme),
+            )),
             // If it's not an instance, throw an error
-            Err(RuntimeError::new(
+            other => Err(RuntimeError::new(
                 name.clone(),
-                "Only instances have properties.".to_string(),
-            ))
+                format!("Only instances have properties, got {}.", other.type_name()),
+            )),
         }
     }
 
@@ -401,12 +846,13 @@ impl Visitor for Evaluator {
         let object = self.evaluate(object)?;
 
         // Check if the object is a LoxInstance
-        if let Value::LoxInstance(mut instance) = object {
+        if let Value::LoxInstance(instance) = object {
             // Evaluate the value to be set
             let value = self.evaluate(value)?;
 
-            // Call the set method on the LoxInstance
-            instance.set(name, &value);
+            // Call the set method on the LoxInstance, which refuses if the instance is frozen.
+            // The instance is shared, so every other handle to it sees the new field as well
+            instance.borrow_mut().set(name, &value)?;
 
             // Return the value that was set
             Ok(value)
@@ -419,32 +865,34 @@ impl Visitor for Evaluator {
         }
     }
 
-    fn visit_this_expr(&mut self, this: &Token) -> Result<Value, RuntimeError> {
-        self.look_up_variable(this, &Expr::This { keyword: this.clone() })
+    fn visit_this_expr(&mut self, this: &Token, id: ExprId) -> Result<Value, RuntimeError> {
+        self.look_up_variable(this, id)
     }
-    fn visit_super_expr(&mut self, keyword: &Token, method: &Token) -> Result<Value, RuntimeError> {
+    fn visit_super_expr(&mut self, keyword: &Token, method: &Token, id: ExprId) -> Result<Value, RuntimeError> {
         // Look up the 'super' in the current environment
-        let distance = self.locals.get(&Expr::Super {
-            keyword: keyword.clone(),
-            method: method.clone()
-        }); // Get the distance of the `super` keyword in the environment
+        let distance = self.locals.get(&id); // Get the distance of the `super` keyword in the environment
 
         if let Some(distance) = distance {
             // Access the superclass value from the environment at the given distance
-            let superclass = self.environment.get_at(*distance, "super")?;
+            let superclass = self.environment.borrow().get_at(*distance, "super")?;
 
             // Check if the superclass is of type LoxClass
             if let Value::LoxClass(superclass_class) = superclass {
                 // Access the `this` object, which is the current instance
-                let object_value = self.environment.get_at(*distance - 1, "this")?;
+                let object_value = self.environment.borrow().get_at(*distance - 1, "this")?;
 
                 // Match on the value to ensure it's a LoxInstance
                 if let Value::LoxInstance(object) = object_value {
-                    // Look up the method in the superclass
-                    if let Some(method_fn) = superclass_class.find_method(method.lexeme.clone()) {
-                        // Bind the method to the instance and return the result
-                        return Ok(Value::LoxFunction(method_fn.bind(object)));
-                    }
+                    // Look up the method in the superclass, and the ones above it
+                    return match superclass_class.find_method(method.lexeme.clone()) {
+                        // Bind the method to the instance and return it as a callable, like any
+                        // other method looked up on an instance
+                        Some(method_fn) => Ok(Value::Callable(Rc::new(method_fn.bind(object)))),
+                        None => Err(RuntimeError::new(
+                            method.clone(),
+                            format!("Undefined property '{}'.", method.lexeme),
+                        )),
+                    };
                 } else {
                     return Err(RuntimeError::new(
                         keyword.clone(),
@@ -460,16 +908,151 @@ impl Visitor for Evaluator {
         ))
     }
 
-}
+    fn visit_array_expr(&mut self, elements: &[Expr]) -> Result<Value, RuntimeError> {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            values.push(self.evaluate(element)?);
+        }
+        Ok(self.heap.array(values))
+    }
 
-/*
-memory safe means that through a combination of static and runtime checking, a program can never
-incorrectly interpret the data stored in bits of memory
+    fn visit_block_expr(&mut self, statements: &[Stmt], value: Option<&Expr>) -> Result<Value, RuntimeError> {
+        let child_env = Environment::new_enclosed(Rc::clone(&self.environment));
+        self.evaluate_block(statements, value, child_env)
+    }
 
-static and syntax errors are both errors that are detected and caught before any code is evaluated.
-runtime errors are code that raises an error during evaluation
+    fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<Value, RuntimeError> {
+        let mut map = LoxMap::new();
+        for (key, value) in entries {
+            let key = self.evaluate(key)?;
+            let key = map_key(&key).map_err(|message| RuntimeError::new(brace.clone(), message))?;
+            let value = self.evaluate(value)?;
+            // a repeated key keeps its first position and its last value, as if set in order
+            map.insert(key, value);
+        }
+        Ok(self.heap.map(map))
+    }
 
-For example, this expression is valid:
+    fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Result<Value, RuntimeError> {
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        match object {
+            Value::Array(array) => {
+                let array = array.borrow();
+                let i = check_index(&index, array.len(), false)
+                    .map_err(|message| RuntimeError::new(bracket.clone(), message))?;
+                Ok(array[i].clone())
+            }
+            // strings index by character, not byte, the same way `len` counts them
+            Value::String(s) => {
+                let i = check_index(&index, s.chars().count(), false)
+                    .map_err(|message| RuntimeError::new(bracket.clone(), message))?;
+                Ok(Value::String(s.chars().nth(i).map(String::from).unwrap_or_default().into()))
+            }
+            // a key that isn't there gives nil, like `get`
+            Value::Map(map) => {
+                let key = map_key(&index).map_err(|message| RuntimeError::new(bracket.clone(), message))?;
+                Ok(map.borrow().get(&key).cloned().unwrap_or(Value::Nil))
+            }
+            other => Err(RuntimeError::new(
+                bracket.clone(),
+                format!("Only strings, arrays and maps can be indexed, got {}.", other.type_name()),
+            )),
+        }
+    }
+
+    /*
+    `a[start..end]` is a new array, or string, of the elements, or characters, from `start` up to
+    but not including `end`. Both bounds follow the rules for indices, except that either may
+    also be the length, so `s[0..len(s)]` is all of `s` and `s[len(s)..len(s)]` is empty.
+    */
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        start: &Expr,
+        end: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        let object = self.evaluate(object)?;
+        let start = self.evaluate(start)?;
+        let end = self.evaluate(end)?;
+        let len = match &object {
+            Value::String(s) => s.chars().count(),
+            Value::Array(array) => array.borrow().len(),
+            other => {
+                return Err(RuntimeError::new(
+                    bracket.clone(),
+                    format!("Only strings and arrays can be sliced, got {}.", other.type_name()),
+                ));
+            }
+        };
+        let bound = |value: &Value| {
+            check_index(value, len, true).map_err(|message| RuntimeError::new(bracket.clone(), message))
+        };
+        let (start, end) = (bound(&start)?, bound(&end)?);
+        if start > end {
+            return Err(RuntimeError::new(
+                bracket.clone(),
+                format!("Slice start {} is past its end {}.", start, end),
+            ));
+        }
+        match object {
+            Value::String(s) => Ok(Value::String(s.chars().skip(start).take(end - start).collect::<String>().into())),
+            Value::Array(array) => {
+                let elements = array.borrow()[start..end].to_vec();
+                Ok(self.heap.array(elements))
+            }
+            _ => unreachable!("only strings and arrays got this far"),
+        }
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        // left to right, like everything else: the array, then the index, then the value
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        let value = self.evaluate(value)?;
+        match object {
+            Value::Array(array) => {
+                let mut array = array.borrow_mut();
+                let i = check_index(&index, array.len(), false)
+                    .map_err(|message| RuntimeError::new(bracket.clone(), message))?;
+                array[i] = value.clone();
+                Ok(value)
+            }
+            Value::String(_) => Err(RuntimeError::new(
+                bracket.clone(),
+                "Strings are immutable.".to_string(),
+            )),
+            Value::Map(map) => {
+                let key = map_key(&index).map_err(|message| RuntimeError::new(bracket.clone(), message))?;
+                let len = map.borrow().len();
+                self.check_allocation(bracket, len.checked_add(1))?;
+                map.borrow_mut().insert(key, value.clone());
+                Ok(value)
+            }
+            other => Err(RuntimeError::new(
+                bracket.clone(),
+                format!("Only array elements and map entries can be assigned, got {}.", other.type_name()),
+            )),
+        }
+    }
+
+}
+
+/*
+memory safe means that through a combination of static and runtime checking, a program can never
+incorrectly interpret the data stored in bits of memory
+
+static and syntax errors are both errors that are detected and caught before any code is evaluated.
+runtime errors are code that raises an error during evaluation
+
+For example, this expression is valid:
     2 * (3 / -"muffin")
 And we should report the runtime error in the inner expression when that inner expression is being
 evaluated. Moreover, when an error is encountered, we should halt the evaluation process
@@ -487,11 +1070,14 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
     }
 
     fn visit_print_stmt(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
-        if let Stmt::Print { expression } = stmt {
+        if let Stmt::Print { keyword, expression } = stmt {
             match self.evaluate(expression) {
                 Ok(value) => {
-                    println!("{}", value);
-                    Ok(())
+                    let text = self.stringify(&value, keyword)?;
+                    // the output may be an embedder's writer, which can fail like any other
+                    writeln!(self.output, "{}", text).map_err(|err| {
+                        RuntimeError::new(keyword.clone(), format!("Can't write to output: {}.", err))
+                    })
                 }
                 Err(err) => Err(err),
             }
@@ -501,22 +1087,21 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
     }
 
     fn visit_var_stmt(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
-        if let Stmt::Var { name, initializer} = stmt {
+        if let Stmt::Var { name, initializer, constant } = stmt {
             let value = if let Some(expr) = initializer {
                 Some(self.evaluate(expr)?)
             } else {
                 Some(Value::Nil)
             };
 
-            self.environment.define(name.lexeme.clone(), value.unwrap());
-            Ok(())
+            self.environment.borrow_mut().declare(name, value.unwrap(), *constant)
         } else {
             unreachable!("Expected Var statement in visit_var_stmt")
         }
     }
 
-    fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> Result<(), RuntimeError> {
-        let child_env = Environment::new_enclosed(self.environment.clone());
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
+        let child_env = Environment::new_enclosed(Rc::clone(&self.environment));
         self.execute_block(statements, child_env)
     }
 
@@ -534,17 +1119,158 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<(), RuntimeError> {
+    fn visit_while_stmt(
+        &mut self,
+        keyword: &Token,
+        label: &Option<Token>,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Box<Expr>>,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> Result<(), RuntimeError> {
+        let mut ran = false;
+        // counted afresh every time the loop is entered, see `set_max_loop_iterations`
+        let mut iterations: usize = 0;
         while {
             let cond_val = self.evaluate(condition)?;
             self.is_truthy(&cond_val)
         } {
-            self.execute(body)?;
+            ran = true;
+            self.count_iteration(keyword, &mut iterations)?;
+            self.check_budget(keyword)?;
+            // a `continue` only cuts the body short, the increment below still runs, while a
+            // `break` leaves the loop at once, skipping the increment
+            if !loop_goes_on(self.execute(body), label)? {
+                return Ok(());
+            }
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+        // the condition was false on the very first check
+        if let (false, Some(else_branch)) = (ran, else_branch) {
+            self.execute(else_branch)?;
         }
         Ok(())
     }
 
-    fn visit_fun_stmt(&mut self, name: &Token, params: &Vec<Token>, body: &Vec<Stmt>) -> Result<(), RuntimeError> {
+    fn visit_continue_stmt(&mut self, _keyword: &Token, label: &Option<Token>) -> Result<(), RuntimeError> {
+        // Like return, this unwinds through any blocks until the enclosing loop catches it
+        Err(RuntimeError::Continue(label.as_ref().map(|label| label.lexeme.clone())))
+    }
+
+    fn visit_break_stmt(&mut self, _keyword: &Token, label: &Option<Token>) -> Result<(), RuntimeError> {
+        Err(RuntimeError::Break(label.as_ref().map(|label| label.lexeme.clone())))
+    }
+
+    fn visit_repeat_stmt(&mut self, keyword: &Token, label: &Option<Token>, count: &Expr, body: &Stmt) -> Result<(), RuntimeError> {
+        // the count is evaluated once, up front; changing its variables in the body changes nothing
+        let value = self.evaluate(count)?;
+        let times = match value.as_integer() {
+            Some(n) if n >= 0 => n,
+            _ => {
+                return Err(RuntimeError::new(
+                    keyword.clone(),
+                    format!("Repeat count must be a non-negative whole number, got {}.", self.describe(&value, keyword)),
+                ));
+            }
+        };
+        // counted like the iterations of a `while`, see `set_max_loop_iterations`
+        let mut iterations: usize = 0;
+        let mut done = 0;
+        while done < times {
+            self.count_iteration(keyword, &mut iterations)?;
+            self.check_budget(keyword)?;
+            if !loop_goes_on(self.execute(body), label)? {
+                break;
+            }
+            done += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_do_while_stmt(&mut self, keyword: &Token, label: &Option<Token>, body: &Stmt, condition: &Expr) -> Result<(), RuntimeError> {
+        // counted like the iterations of a `while`, see `set_max_loop_iterations`
+        let mut iterations: usize = 0;
+        loop {
+            self.count_iteration(keyword, &mut iterations)?;
+            self.check_budget(keyword)?;
+            // a `continue` skips to the condition, which decides whether to go round again
+            if !loop_goes_on(self.execute(body), label)? {
+                return Ok(());
+            }
+            let cond_val = self.evaluate(condition)?;
+            if !self.is_truthy(&cond_val) {
+                return Ok(());
+            }
+        }
+    }
+
+    /*
+    The elements are taken up front: a copy of an array's elements, a map's keys in the order they
+    were inserted, or a string's characters. Changing the collection in the body therefore changes
+    neither how many times the loop runs nor what it sees. Each element gets a new environment, so
+    a closure made in the body keeps the element it was made for.
+    */
+    fn visit_for_in_stmt(
+        &mut self,
+        keyword: &Token,
+        label: &Option<Token>,
+        index: &Option<Token>,
+        name: &Token,
+        iterable: &Expr,
+        body: &Stmt,
+    ) -> Result<(), RuntimeError> {
+        // each element along with its position, or for a map each key along with its value. A
+        // loop with one name is given the element, or the key. A range counts up as it goes
+        // rather than listing its numbers first, as there may be many
+        let mut by_key = false;
+        let entries: Box<dyn Iterator<Item = (Value, Value)>> = match self.evaluate(iterable)? {
+            Value::Array(array) => indexed(array.borrow().clone().into_iter()),
+            Value::Map(map) => {
+                by_key = true;
+                let entries: Vec<(Value, Value)> = map.borrow().iter().map(|(key, value)| (key_value(key), value.clone())).collect();
+                Box::new(entries.into_iter())
+            }
+            Value::String(s) => indexed(s.chars().map(|c| Value::String(c.to_string().into())).collect::<Vec<_>>().into_iter()),
+            Value::Range { start, end } => indexed((start..end).map(Value::Int)),
+            other => {
+                return Err(RuntimeError::new(
+                    keyword.clone(),
+                    format!("Can only loop over arrays, maps, strings and ranges, got {}.", other.type_name()),
+                ));
+            }
+        };
+        // counted like the iterations of a `while`, see `set_max_loop_iterations`
+        let mut iterations: usize = 0;
+        for (position, element) in entries {
+            self.count_iteration(keyword, &mut iterations)?;
+            self.check_budget(keyword)?;
+            let mut env = Environment::new_enclosed(Rc::clone(&self.environment));
+            match index {
+                Some(index) => {
+                    env.define(index.lexeme.clone(), position);
+                    env.define(name.lexeme.clone(), element);
+                }
+                None if by_key => env.define(name.lexeme.clone(), position),
+                None => env.define(name.lexeme.clone(), element),
+            }
+            if !loop_goes_on(self.execute_block(std::slice::from_ref(body), env), label)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_fun_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        param_types: &[Option<Token>],
+        body: &[Stmt],
+        return_type: &Option<Token>,
+        id: ExprId,
+    ) -> Result<(), RuntimeError> {
         /*
         This is similar to how we interpret other literal expressions. We take a function
         syntax node, a compile-time representation of the function - and convert it to a runtime
@@ -557,12 +1283,16 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
         */
         let func_decl = Stmt::Function {
             name: name.clone(),
-            params: params.clone(),
-            body: body.clone(),
+            params: params.to_vec(),
+            param_types: param_types.to_vec(),
+            body: body.to_vec(),
+            return_type: return_type.clone(),
+            id,
         };
 
 
-        let closure: Rc<Environment> = Rc::from(self.environment.clone());
+        // capture a handle to the current scope, not a snapshot of it
+        let closure: Rc<RefCell<Environment>> = Rc::clone(&self.environment);
 
         // wrap it into a callable object
         /*
@@ -570,16 +1300,35 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
         defined a function with that name. In that case, there is no this to return. To avoid that weird edge
         case, we'll directly store whether the LoxFunction represents an initialized method.
         */
-        let function_obj = Value::Callable(Rc::new(LoxFunction::new(func_decl, closure, false)));
+        let function_obj = Value::Callable(Rc::new(LoxFunction::new(func_decl, closure, Rc::clone(&self.globals), false)));
 
         // define the variable in the *current* environment
-        self.environment.define(name.lexeme.clone(), function_obj);
+        self.environment.borrow_mut().declare(name, function_obj, false)?;
 
 
         Ok(())
     }
 
     fn visit_return_stmt(&mut self, _keyword: &Token, value: &Option<Box<Expr>>) -> Result<(), RuntimeError> {
+        // `return f(args);` calling a Lox function is a tail call, which the function we are
+        // returning from makes for us once it is gone, see `LoxFunction::call`. Not inside a
+        // `try`, though: an error in `f` would then be raised past the `catch` meant for it
+        if self.try_depth == 0
+            && let Some(Expr::Call { callee, open_paren, paren, arguments, names }) = value.as_deref()
+        {
+            let (callee, arguments) = self.evaluate_call(callee, paren, arguments, names)?;
+            if let Value::Callable(function) = &callee
+                && let Some(function) = function.as_lox_function()
+            {
+                check_arity(paren, function, arguments.len())?;
+                // a tail call never goes through `enter_call`, and can loop forever all the same
+                self.check_budget(paren)?;
+                return Err(RuntimeError::TailCall(function.clone(), arguments));
+            }
+            let result = self.call_value(callee, open_paren, paren, arguments)?;
+            return Err(RuntimeError::Return(Some(result)));
+        }
+
         let result = if let Some(expr) = value {
             Some(self.evaluate(expr)?)
         } else {
@@ -590,13 +1339,87 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
         Err(RuntimeError::Return(result))
     }
 
+    /*
+    A runtime error raised in the body, however deep in calls, unwinds to here, and the handler
+    runs with the error bound as an `Error` instance (see `error_value`), so `e.message` and
+    `e.line` say what went wrong and where. What a `throw` raised is bound as it is, whatever it
+    is. Anything that only travels as an error passes through untouched: `return`, `break` and
+    `continue` still leave the function or loop around the `try`, and neither is `exit` caught. An
+    error raised once the execution budget has run out isn't caught either, or a handler could
+    keep a sandboxed program running forever.
+    */
+    fn visit_try_catch_stmt(&mut self, _keyword: &Token, body: &[Stmt], error_name: &Token, handler: &[Stmt]) -> Result<(), RuntimeError> {
+        self.try_depth += 1;
+        let result = self.execute_block(body, Environment::new_enclosed(Rc::clone(&self.environment)));
+        self.try_depth -= 1;
+        match result {
+            Err(err @ RuntimeError::Error { .. }) if !self.budget.as_ref().is_some_and(Budget::spent) => {
+                let error = match (&err, self.error_value(&err)) {
+                    (RuntimeError::Error { thrown: Some(value), .. }, _) => (**value).clone(),
+                    (_, Some(error)) => {
+                        self.heap.register_value(&error);
+                        error
+                    }
+                    // only before the prelude has defined `Error`
+                    (_, None) => Value::String(err.to_string().into()),
+                };
+                let mut env = Environment::new_enclosed(Rc::clone(&self.environment));
+                env.define(error_name.lexeme.clone(), error);
+                self.execute_block(handler, env)
+            }
+            other => other,
+        }
+    }
+
+    /*
+    `throw value;` raises a runtime error carrying the value, which a `catch` hands over as it is,
+    and whose message is the value as `print` shows it, so that one nothing catches is reported
+    like any other error. An `Error` (or an instance of a subclass) gets the `throw`'s line the
+    first time it is raised, if it has none yet, and is reported at whatever line it has: throwing
+    a caught error again from its handler keeps the line it first went wrong at, and is reported
+    without the `throw` keyword, which isn't on that line.
+    */
+    fn visit_throw_stmt(&mut self, keyword: &Token, value: &Expr) -> Result<(), RuntimeError> {
+        let value = self.evaluate(value)?;
+        let mut token = keyword.clone();
+        if let Value::LoxInstance(instance) = &value
+            && let Some(class) = &self.error_class
+            && instance.borrow().class().is_subclass_of(class)
+        {
+            let line = instance.borrow().field("line");
+            match line {
+                // that line has no `throw` on it, so the error is reported at the line alone
+                Some(Value::Int(line)) if line > 0 && line as usize != keyword.line => {
+                    token.line = line as usize;
+                    token.lexeme = String::new();
+                }
+                None | Some(Value::Nil) => {
+                    let field = Token::new(TokenType::Identifier, "line".to_string(), Literal::Nil, keyword.line);
+                    // a frozen error keeps its nil line, and is reported at the `throw` regardless
+                    let _ = instance.borrow_mut().set(&field, &Value::Int(keyword.line as i64));
+                }
+                _ => {}
+            }
+        }
+        let message = self.stringify(&value, keyword)?;
+        Err(RuntimeError::Error { token, message, trace: Vec::new(), thrown: Some(Box::new(value)) })
+    }
+
     // we convert the AST representation into LoxClass, the runtime representation
+    // imports need the whole interpreter, to scan, parse and resolve the file, so the top-level
+    // loop in `Interpreter::execute_program` runs them and they never get here, see `import`
+    fn visit_import_stmt(&mut self, keyword: &Token, _path: &Token, _alias: &Option<Token>) -> Result<(), RuntimeError> {
+        Err(RuntimeError::new(keyword.clone(), "Can only import at the top level.".to_string()))
+    }
+
     // by declaring the class in the environment first allows methods to reference itself
-    // Where an instance stores state, the class stores behavior. LoxInstance has its map of fields, and LoxClass gets a map of methods. Even though methods are owned by the class, they are still accessed through instances of that class.
+    // Where an instance stores state, the class stores behavior. LoxInstance has its map of
+    // fields, and LoxClass gets a map of methods. Even though methods are owned by the class,
+    // they are still accessed through instances of that class.
     fn visit_class_stmt(
         &mut self,
         name: &Token,
-        methods: &Vec<Result<Stmt, ParseError>>,
+        methods: &[Result<Stmt, ParseError>],
         superclass: &Option<Box<Expr>>,
     ) -> Result<(), RuntimeError> {
 
@@ -618,19 +1441,22 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
         };
         
         // Define the class in the environment (similar to declaring it)
-        self.environment.define(name.lexeme.clone(), Value::Nil);
+        self.environment.borrow_mut().declare(name, Value::Nil, false)?;
 
         /*
-        In the environment, we store a reference to the superclass - the acutal LoxClass object for the superclass which we have now that we are in the runtime.
-        Then we create the LoxFunction for each method. Those will capture the current environment - the one where we bound "super" as their closure, holding
-        on to the superclass like we need.
+        In the environment, we store a reference to the superclass - the acutal LoxClass object
+        for the superclass which we have now that we are in the runtime. Then we create the
+        LoxFunction for each method. Those will capture the current environment - the one where
+        we bound "super" as their closure, holding on to the superclass like we need.
         */
+        let previous = Rc::clone(&self.environment);
         if let Some(superclass_value) = &superclass_value {
             // Create an environment with "super" as a variable
-            let mut env = Environment::new_enclosed(self.environment.clone());
+            let mut env = Environment::new_enclosed(Rc::clone(&self.environment));
             env.define("super".to_string(), Value::LoxClass(*superclass_value.clone()));
             // We need to use this environment for method resolution
-            self.environment = env;
+            self.environment = Rc::new(RefCell::new(env));
+            self.heap.register(&self.environment);
         }
         
         // Create a HashMap to store methods
@@ -638,19 +1464,13 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
 
         // Iterate over each method in the class
         for method in methods {
-            if let Ok(Stmt::Function { name, params, body }) = method {
+            if let Ok(stmt @ Stmt::Function { name, .. }) = method {
                 // Create a LoxFunction for the method
-                match method {
-                    Ok(stmt) => {
-                        let function = LoxFunction::new(stmt.clone(), Rc::from(self.environment.clone()),
-                        name.lexeme.eq("init")
-                        );
-                        // Store the function in the methods map
-                        class_methods.insert(name.lexeme.clone(), function);
-                    }
-                    Err(e) => {}
-                }
-
+                let function = LoxFunction::new(stmt.clone(), Rc::clone(&self.environment),
+                Rc::clone(&self.globals), name.lexeme.eq("init")
+                );
+                // Store the function in the methods map
+                class_methods.insert(name.lexeme.clone(), function);
             }
         }
 
@@ -661,30 +1481,137 @@ impl StmtVisitor<Result<(), RuntimeError>> for Evaluator {
             superclass_value.clone(),
         );
 
-        if superclass_value.is_some() {
-            self.environment = *self.environment.enclosing.clone().unwrap();
-        }
+        self.environment = previous;
 
         // Assign the class to the environment
-        self.environment.assign(name, Value::LoxClass(class))?;
+        self.environment.borrow_mut().assign(name, Value::LoxClass(class))?;
 
         Ok(())
     }
 
 }
 
+// the elements of a for-in loop paired with their zero-based positions
+fn indexed(elements: impl Iterator<Item = Value> + 'static) -> Box<dyn Iterator<Item = (Value, Value)>> {
+    Box::new(elements.enumerate().map(|(i, element)| (Value::Int(i as i64), element)))
+}
+
+/*
+Whether a loop labeled `label`, if it has one, goes round again after its body ended with `result`.
+A `break` or `continue` without a label is for the innermost loop, which is the one asking. One
+with a label is for the loop that has it, and unwinds through every loop in between, each of
+which passes it on like any other error. So do returns and runtime errors.
+*/
+fn loop_goes_on(result: Result<(), RuntimeError>, label: &Option<Token>) -> Result<bool, RuntimeError> {
+    let for_this_loop = |target: &Option<String>| match target {
+        None => true,
+        Some(target) => label.as_ref().is_some_and(|label| label.lexeme == *target),
+    };
+    match result {
+        Ok(()) => Ok(true),
+        Err(RuntimeError::Continue(target)) if for_this_loop(&target) => Ok(true),
+        Err(RuntimeError::Break(target)) if for_this_loop(&target) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
 #[derive(Debug)]
 pub enum RuntimeError {
     Error {
         token: Token,
         message: String,
+        // the calls in progress when the error was raised, outermost first, see `CallFrame`
+        trace: Vec<CallFrame>,
+        // the value a `throw` raised, for a `catch` to bind; None for the interpreter's own errors
+        thrown: Option<Box<Value>>,
     },
     Return(Option<Value>),
+    // unwinds to the innermost loop, or the one with this label, which moves on to its next
+    // iteration
+    Continue(Option<String>),
+    // unwinds to the innermost loop, or the one with this label, which stops
+    Break(Option<String>),
+    // raised by `return f(args);` in place of calling `f`: unwinds to the Lox function being
+    // returned from, which calls `f` with these arguments itself, see `LoxFunction::call`
+    TailCall(LoxFunction, Vec<Value>),
+    // raised by `exit(code)`: unwinds all the way out of `Interpreter::interpret`, which records
+    // the code for the embedder (or the runner) to act on
+    Exit(i32),
 }
 
 impl RuntimeError {
     pub fn new(token: Token, message: String) -> Self {
-        RuntimeError::Error { token, message }
+        RuntimeError::Error { token, message, trace: Vec::new(), thrown: None }
+    }
+
+    /// The calls that were in progress when the error was raised, outermost first. Empty for an
+    /// error raised outside of any function, and for the control-flow signals.
+    pub fn trace(&self) -> &[CallFrame] {
+        match self {
+            RuntimeError::Error { trace, .. } => trace,
+            _ => &[],
+        }
+    }
+
+    /// The trace as one line, innermost call first, or None if there is no trace to show.
+    /// A run of identical callers, as deep recursion leaves behind, is shown once with a count.
+    pub fn stack_trace(&self) -> Option<String> {
+        let RuntimeError::Error { token, trace, .. } = self else {
+            return None;
+        };
+        let (innermost, callers) = trace.split_last()?;
+        let mut text = format!("in {} (line {})", innermost, token.line);
+        // each frame was called from the line its caller had reached
+        let mut call_line = innermost.call_line;
+        let mut callers = callers.iter().rev().peekable();
+        while let Some(frame) = callers.next() {
+            // the frame shows as `frame` at `call_line`; so does the next one if it is the same
+            // function, making the same call from the same line
+            let line = call_line;
+            let mut times = 1;
+            let mut outermost = frame;
+            while let Some(next) = callers.next_if(|next| {
+                next.function == frame.function && next.is_method == frame.is_method && outermost.call_line == line
+            }) {
+                outermost = next;
+                times += 1;
+            }
+            text.push_str(&format!(", called from {} (line {})", frame, line));
+            if times > 1 {
+                text.push_str(&format!(" {} times", times));
+            }
+            call_line = outermost.call_line;
+        }
+        text.push_str(&format!(", called from script (line {})", call_line));
+        Some(text)
+    }
+}
+
+// a call must pass exactly as many arguments as the callee has parameters, or at least that many
+// to a variadic one; the error is reported at the call's closing paren and says which callee it was
+fn check_arity(paren: &Token, callee: &dyn LoxCallable, given: usize) -> Result<(), RuntimeError> {
+    let expected = callee.expected_arity();
+    if !expected.accepts(given) {
+        return Err(RuntimeError::new(
+            paren.clone(),
+            format!(
+                "Expected {} arguments to {} but got {}.",
+                expected,
+                describe_callee(callee),
+                given
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// how a call error refers to the callee: `'distance'` for a function, method or class declared in
+// Lox, and `<native fn clock>` for a native, which has no declaration to point to
+fn describe_callee(callee: &dyn LoxCallable) -> String {
+    if callee.is_native() {
+        format!("<native fn {}>", callee.name())
+    } else {
+        format!("'{}'", callee.name())
     }
 }
 
@@ -694,7 +1621,11 @@ use crate::parser::ParseError;
 impl Display for RuntimeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            RuntimeError::Error { token, message } => {
+            // a rethrown error has no token of its own on the line it is reported at
+            RuntimeError::Error { token, message, .. } if token.lexeme.is_empty() => {
+                write!(f, "[line {}] RuntimeError: {}", token.line, message)
+            }
+            RuntimeError::Error { token, message, .. } => {
                 write!(
                     f,
                     "[line {}] RuntimeError at '{}': {}",
@@ -702,6 +1633,10 @@ impl Display for RuntimeError {
                 )
             }
             RuntimeError::Return(_) => write!(f, "<return control flow>"),
+            RuntimeError::Continue(_) => write!(f, "<continue control flow>"),
+            RuntimeError::Break(_) => write!(f, "<break control flow>"),
+            RuntimeError::TailCall(function, _) => write!(f, "<tail call to {}>", function),
+            RuntimeError::Exit(code) => write!(f, "<exit with code {}>", code),
         }
     }
 }
@@ -710,30 +1645,753 @@ impl std::error::Error for RuntimeError {}
 
 
 impl Evaluator {
-    pub fn new(environment: Environment) -> Self {
+    /*
+    `instance.name`, as `LoxInstance::get` would find it, but with an inline cache. A site like
+    `point.norm()` in a loop nearly always sees instances of one class, and so nearly always finds
+    the same method, yet `find_method` pays for a string hash per class up the chain each time. So
+    every site remembers the class it last looked a method up on, by identity, and the method it
+    found there. An instance of another class, a subclass overriding the method included, misses,
+    and the site then caches that class instead. Classes never change once declared, so a hit is
+    never stale.
+
+    Fields still come first, as they shadow methods and can come and go at any time. The method is
+    bound afresh on every access: caching the bound method as well would make `a.m == a.m` true
+    only sometimes, since bound methods are compared by identity.
+    */
+    fn get_property(&mut self, instance: &Rc<RefCell<LoxInstance>>, name: &Token, id: ExprId) -> Result<Value, RuntimeError> {
+        if let Some(value) = instance.borrow().field(&name.lexeme) {
+            return Ok(value);
+        }
+        let class_id = instance.borrow().class().id();
+        let method = match self.method_cache.get(&id) {
+            Some((cached_class, method)) if *cached_class == class_id => Some(method.clone()),
+            _ => {
+                let method = instance.borrow().class().find_method(name.lexeme.clone());
+                if let Some(method) = &method {
+                    self.method_cache.insert(id, (class_id, method.clone()));
+                }
+                method
+            }
+        };
+        match method {
+            Some(method) => Ok(Value::Callable(Rc::new(method.bind(Rc::clone(instance))))),
+            // no such method: `clone()`, or the error, as `get` gives them
+            None => LoxInstance::get(instance, name),
+        }
+    }
+
+    /*
+    The shared copy of the string `text`. A string literal is evaluated every time its expression
+    runs, in a loop perhaps, and would otherwise be copied into a new allocation each time. Instead
+    all of them, from every literal with the same text, share the one kept here, which also lets
+    `==` see that they are equal without comparing the text. Only literals go through here: the
+    result of a concatenation or a native is a new string, since most of those are only made once.
+    The table lives as long as the evaluator, but it can only grow as large as the program's text.
+    */
+    pub(crate) fn intern(&mut self, text: &str) -> Rc<str> {
+        if let Some(interned) = self.strings.get(text) {
+            return Rc::clone(interned);
+        }
+        let interned: Rc<str> = Rc::from(text);
+        self.strings.insert(Rc::clone(&interned));
+        interned
+    }
+
+    // Whether the logical `operator` gives its left operand without looking at its right.
+    fn short_circuits(&self, operator: &Token, left_val: &Value) -> Result<bool, RuntimeError> {
+        match operator.token_type {
+            // short-circuit when the left side is truthy
+            TokenType::Or => Ok(self.is_truthy(left_val)),
+            // short-circuit when the left side is falsy
+            TokenType::And => Ok(!self.is_truthy(left_val)),
+            // short-circuit on anything but nil. Unlike `or`, false is kept
+            TokenType::QuestionQuestion => Ok(!matches!(left_val, Value::Nil)),
+            _ => Err(RuntimeError::new(
+                operator.clone(),
+                "Unknown logical operator.".to_string(),
+            )),
+        }
+    }
+
+    // The value of `value_left operator value_right`, both sides already evaluated.
+    fn apply_binary(
+        &mut self,
+        operator: &Token,
+        value_left: Value,
+        value_right: Value,
+    ) -> Result<Value, RuntimeError> {
+
+        // an instance on the left may implement the operator itself, see `call_operator_method`
+        if let Some(result) = self.call_operator_method(operator, &value_left, vec![value_right.clone()]) {
+            return match operator.token_type {
+                // equality is always a boolean, whatever `equals` returned
+                TokenType::EqualEqual => result.map(|equal| Value::Bool(self.is_truthy(&equal))),
+                TokenType::BangEqual => result.map(|equal| Value::Bool(!self.is_truthy(&equal))),
+                _ => result,
+            };
+        }
+
+        match operator.token_type {
+            // a bound can be a float only when it is a whole number, as with an index
+            TokenType::DotDot => match (value_left.as_integer(), value_right.as_integer()) {
+                (Some(start), Some(end)) => Ok(Value::Range { start, end }),
+                _ => Err(RuntimeError::new(
+                    operator.clone(),
+                    format!(
+                        "Range bounds must be integers, got {} and {}.",
+                        self.describe(&value_left, operator),
+                        self.describe(&value_right, operator)
+                    ),
+                )),
+            },
+            // note the subtly here that we evaluate from left-to-right.
+            // This means side effects will also be processed in left-to-right order
+            TokenType::Minus => {
+                self.check_number_operands(operator.clone(), &value_left, &value_right)?;
+                Ok(arithmetic(&value_left, &value_right, i64::checked_sub, |n1, n2| n1 - n2))
+            }
+            TokenType::Slash => {
+                self.check_number_operands(operator.clone(), &value_left, &value_right)?;
+                // always a float, even for two ints: `7 / 2` is 3.5. `divInt` rounds down instead
+                let (Some(n1), Some(n2)) = (value_left.as_f64(), value_right.as_f64()) else {
+                    unreachable!() // this can't happen due to check_number_operands
+                };
+                self.check_divisor(operator, n2)?;
+                Ok(Value::Float(n1 / n2))
+            }
+            TokenType::Star => {
+                // a string times a whole number repeats the string, in either order
+                match (&value_left, &value_right) {
+                    (Value::String(s), count @ (Value::Int(_) | Value::Float(_)))
+                    | (count @ (Value::Int(_) | Value::Float(_)), Value::String(s)) => {
+                        return self.repeat_string(operator, s, count);
+                    }
+                    _ => {}
+                }
+                self.check_number_operands(operator.clone(), &value_left, &value_right)?;
+                Ok(arithmetic(&value_left, &value_right, i64::checked_mul, |n1, n2| n1 * n2))
+            }
+            TokenType::Plus => {
+                // two numbers add. If either side is a string, the other side is turned into
+                // text the same way `print` would show it and the two are concatenated, so
+                // `"count: " + 3` and `3 + "x"` both work. Anything else is an error
+                match (value_left, value_right) {
+                    (left @ (Value::Int(_) | Value::Float(_)), right @ (Value::Int(_) | Value::Float(_))) => {
+                        Ok(arithmetic(&left, &right, i64::checked_add, |n1, n2| n1 + n2))
+                    }
+                    (left @ Value::String(_), right) | (left, right @ Value::String(_)) => {
+                        let (s1, s2) = (self.stringify(&left, operator)?, self.stringify(&right, operator)?);
+                        self.check_allocation(operator, s1.len().checked_add(s2.len()))?;
+                        Ok(Value::String((s1 + &s2).into()))
+                    }
+                    (left, right) => Err(self.operands_error(
+                        operator.clone(),
+                        "two numbers or a string",
+                        "Operands must be two numbers or two strings.",
+                        &left,
+                        &right,
+                    )),
+                }
+            }
+            TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual => {
+                self.check_number_operands(operator.clone(), &value_left, &value_right)?;
+                // `None` is NaN on either side, which makes every comparison false
+                let ordering = compare_numbers(&value_left, &value_right);
+                Ok(Value::Bool(match operator.token_type {
+                    TokenType::Greater => ordering == Some(Ordering::Greater),
+                    TokenType::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                    TokenType::Less => ordering == Some(Ordering::Less),
+                    _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                }))
+            }
+            TokenType::BangEqual => Ok(Value::Bool(!value_left.equals(&value_right))),
+            TokenType::EqualEqual => Ok(Value::Bool(value_left.equals(&value_right))),
+            /*
+            `object is Class` is true when the object is an instance of that class or of one
+            of its subclasses. Any value can be asked, and only instances can answer yes, but
+            the right side has to be a class: `x is 3` can't mean anything.
+            */
+            TokenType::Is => match value_right {
+                Value::LoxClass(ref class) => Ok(Value::Bool(matches!(
+                    value_left,
+                    Value::LoxInstance(ref instance) if instance.borrow().class().is_subclass_of(class)
+                ))),
+                other => Err(RuntimeError::new(
+                    operator.clone(),
+                    format!("Right operand of 'is' must be a class, got {}.", self.describe(&other, operator)),
+                )),
+            },
+            _ => {
+                panic!("Not a valid binary operator")
+            }
+        }
+    }
+
+
+    pub fn new(environment: Rc<RefCell<Environment>>) -> Self {
+        Self::with_output(environment, Box::new(io::stdout()))
+    }
+
+    /// Create an evaluator whose `print` statements write to `output` instead of stdout.
+    pub fn with_output(environment: Rc<RefCell<Environment>>, output: Box<dyn Write>) -> Self {
+        let mut heap = Heap::new();
+        heap.register(&environment);
         Self {
-            globals: environment.clone(),
+            globals: Rc::clone(&environment),
             environment,
             locals: HashMap::new(),
+            method_cache: HashMap::new(),
+            strings: HashSet::new(),
+            output,
+            error_output: Box::new(io::stderr()),
+            heap,
+            allocation_limit: DEFAULT_ALLOCATION_LIMIT,
+            book_messages: false,
+            ieee_division: false,
+            implicit_returns: HashSet::new(),
+            strict_returns: false,
+            max_loop_iterations: None,
+            limits: Limits::default(),
+            budget: None,
+            statements_executed: 0,
+            error_class: None,
+            script_args: None,
+            started: Instant::now(),
+            rng: Rng::from_time(),
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            call_site: None,
+            frames: Vec::new(),
+            stringifying: Vec::new(),
+            try_depth: 0,
+        }
+    }
+
+    /*
+    The text of a value as `print` shows it. That is its `Display`, except for an instance whose
+    class has (or inherits) a `toString()` method taking no arguments: the method is called on the
+    instance and what it returns is the text, so `print point;` can show `(1, 2)` rather than
+    `Point instance`. This goes for instances inside arrays and maps as well. Anything but a string
+    coming back from `toString` is an error, reported at `token`, the print or operator that asked.
+
+    A `toString` that prints its own instance, or builds a string out of it, would ask for itself
+    again and recurse until the stack overflowed. While an instance's `toString` is running, the
+    instance reads as the plain `Name instance` instead.
+    */
+    pub(crate) fn stringify(&mut self, value: &Value, token: &Token) -> Result<String, RuntimeError> {
+        let mut text = String::new();
+        value.write_text(&mut text, &mut Vec::new(), &mut |instance| self.instance_text(instance, token))?;
+        Ok(text)
+    }
+
+    /*
+    A value as a type error shows it, its type and then the value: `string ("abc")`, or for an
+    instance with a `toString`, what that returns, `instance (V(3))`. A `toString` that fails
+    here is not allowed to replace the error being reported; the instance is then shown as
+    `Value::describe` shows it, without running any Lox code.
+    */
+    pub(crate) fn describe(&mut self, value: &Value, token: &Token) -> String {
+        let text = match value {
+            Value::String(_) => Ok(value.repr()),
+            _ => self.stringify(value, token),
+        };
+        match text {
+            Ok(text) => format!("{} ({})", value.type_name(), text),
+            Err(_) => value.describe(),
+        }
+    }
+
+    // The error for a pair of values with no order between them, as `compare_values` gives it,
+    // with the values described by `describe`.
+    pub(crate) fn order_error(&mut self, a: &Value, b: &Value, token: &Token) -> RuntimeError {
+        let message = cant_order(&self.describe(a, token), &self.describe(b, token));
+        RuntimeError::new(token.clone(), message)
+    }
+
+    fn instance_text(&mut self, instance: &Rc<RefCell<LoxInstance>>, token: &Token) -> Result<String, RuntimeError> {
+        let address = Rc::as_ptr(instance) as *const ();
+        let to_string = instance.borrow().class().find_method("toString".to_string());
+        let to_string = match to_string {
+            Some(method) if method.arity() == 0 && !self.stringifying.contains(&address) => method,
+            _ => return Ok(instance.borrow().to_string()),
+        };
+
+        self.stringifying.push(address);
+        let bound = Value::Callable(Rc::new(to_string.bind(Rc::clone(instance))));
+        let result = self.call_value(bound, token, token, Vec::new());
+        self.stringifying.pop();
+
+        match result? {
+            Value::String(text) => Ok(text.to_string()),
+            _ => Err(RuntimeError::new(token.clone(), "toString must return a string.".to_string())),
+        }
+    }
+
+    /*
+    Evaluate the callee and the arguments of a call, in that order, and put any named arguments
+    into the position of the parameter they name. What comes back is ready to be handed to
+    `call_value`, or, for a call in tail position, to be turned into a `RuntimeError::TailCall`.
+    */
+    fn evaluate_call(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+        names: &[Option<Token>],
+    ) -> Result<(Value, Vec<Value>), RuntimeError> {
+        let callee_val = self.evaluate(callee)?;
+
+        let mut arg_vals = Vec::with_capacity(arguments.len());
+        for arg in arguments {
+            arg_vals.push(self.evaluate(arg)?);
+        }
+
+        // put named arguments into the position of the parameter they name
+        if names.iter().any(Option::is_some) {
+            let callable: Option<&dyn LoxCallable> = match &callee_val {
+                Value::Callable(function) => Some(function.as_ref()),
+                Value::LoxClass(klass) => Some(klass),
+                _ => None,
+            };
+            if let Some(callable) = callable
+                && let Some(param_names) = callable.param_names()
+            {
+                arg_vals = self.bind_named_arguments(paren, callable, &param_names, arg_vals, names)?;
+            } else if matches!(callee_val, Value::Callable(_)) {
+                return Err(RuntimeError::new(
+                    paren.clone(),
+                    format!("{} does not take named arguments.", callee_val),
+                ));
+            }
         }
+        Ok((callee_val, arg_vals))
+    }
+
+    /* performing the call
+    We do that by casting the callee to a LoxCallable and then
+    invoking a `call()` method on it. The Java representation of any Lox
+    object thay can be called like a function implement this interface.
+    This includes user-defined functions and also class objects since classes are
+    'called' to construct new instances.
+    */
+    /*
+    Operator overloading. A class can give its instances arithmetic and comparisons by declaring
+    methods with these names:
+
+        a + b   a.plus(b)          a == b   a.equals(b)
+        a - b   a.minus(b)         a != b   !a.equals(b)
+        a * b   a.times(b)         a < b    a.less(b)
+        a / b   a.div(b)           -a       a.negate()
+
+    The operand on the left (the only one, for `-a`) is the one asked, so `vector * 2` can call
+    `Vector.times` but `2 * vector` is still an error. If that operand is not an instance, or its
+    class has no method for the operator, this returns `None` and the operator works as it always
+    does: `==` compares by identity, and the others raise their usual type errors. The rest of the
+    comparisons are not derived from `less`, since `a > b` would mean asking `b`.
+
+    The method is called like any other, reported as called from the operator, so an error raised
+    inside it has the line of the expression that used the operator in its stack trace.
+    */
+    fn call_operator_method(&mut self, operator: &Token, operand: &Value, arguments: Vec<Value>) -> Option<Result<Value, RuntimeError>> {
+        let Value::LoxInstance(instance) = operand else {
+            return None;
+        };
+        let name = match (&operator.token_type, arguments.len()) {
+            (TokenType::Plus, 1) => "plus",
+            (TokenType::Minus, 1) => "minus",
+            (TokenType::Star, 1) => "times",
+            (TokenType::Slash, 1) => "div",
+            (TokenType::EqualEqual | TokenType::BangEqual, 1) => "equals",
+            (TokenType::Less, 1) => "less",
+            (TokenType::Minus, 0) => "negate",
+            _ => return None,
+        };
+        let method = instance.borrow().class().find_method(name.to_string())?;
+        let bound = Value::Callable(Rc::new(method.bind(Rc::clone(instance))));
+        Some(self.call_value(bound, operator, operator, arguments))
+    }
+
+    fn call_value(
+        &mut self,
+        callee_val: Value,
+        open_paren: &Token,
+        paren: &Token,
+        arg_vals: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        // Check that the callee is actually callable
+        match callee_val {
+            Value::Callable(ref function) => {
+                // Arity check (optional but nice to keep the book’s behaviour)
+                check_arity(paren, function.as_ref(), arg_vals.len())?;
+                // Make the call, remembering where it was made for natives that raise errors
+                self.enter_call(paren)?;
+                let enclosing_call_site = self.call_site.replace(paren.clone());
+                let result = function.call(self, arg_vals);
+                self.call_site = enclosing_call_site;
+                self.call_depth -= 1;
+                result
+            }
+
+            // calling a class constructs a new instance of it
+            Value::LoxClass(ref klass) => {
+                check_arity(paren, klass, arg_vals.len())?;
+                self.enter_call(paren)?;
+                // the initializer's frame is reported as called from here
+                let enclosing_call_site = self.call_site.replace(paren.clone());
+                let result = klass.call(self, arg_vals);
+                self.call_site = enclosing_call_site;
+                self.call_depth -= 1;
+                result
+            }
+
+            // anything else, a string, nil, an instance, ..., is a mistake in what comes before the
+            // `(`, so that is where we point, saying what it turned out to be
+            other => Err(RuntimeError::new(
+                open_paren.clone(),
+                format!("Can only call functions and classes, got {}.", other.type_name()),
+            )),
+        }
+    }
+
+    /*
+    Order the arguments of a call with named arguments the way the parameters are declared.
+    Positional arguments, which all come first, fill the leading parameters. Each named one goes
+    to the parameter with its name, which must exist and must not have been given already. Every
+    parameter has to end up with exactly one argument, since Lox has no default values.
+    */
+    fn bind_named_arguments(
+        &self,
+        paren: &Token,
+        callee: &dyn LoxCallable,
+        param_names: &[String],
+        arguments: Vec<Value>,
+        names: &[Option<Token>],
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let mut slots: Vec<Option<Value>> = vec![None; param_names.len()];
+        for (position, (value, name)) in arguments.into_iter().zip(names).enumerate() {
+            let index = match name {
+                None if position < slots.len() => position,
+                None => {
+                    return Err(RuntimeError::new(
+                        paren.clone(),
+                        format!(
+                            "Expected {} arguments to {} but got more.",
+                            param_names.len(),
+                            describe_callee(callee)
+                        ),
+                    ));
+                }
+                Some(name) => param_names
+                    .iter()
+                    .position(|param| *param == name.lexeme)
+                    .ok_or_else(|| RuntimeError::new(
+                        name.clone(),
+                        format!("No parameter named '{}'.", name.lexeme),
+                    ))?,
+            };
+            if slots[index].is_some() {
+                return Err(RuntimeError::new(
+                    name.clone().unwrap_or_else(|| paren.clone()),
+                    format!("Argument '{}' was given more than once.", param_names[index]),
+                ));
+            }
+            slots[index] = Some(value);
+        }
+
+        slots
+            .into_iter()
+            .zip(param_names)
+            .map(|(slot, param)| slot.ok_or_else(|| RuntimeError::new(
+                paren.clone(),
+                format!("Missing argument for parameter '{}'.", param),
+            )))
+            .collect()
+    }
+
+    /// Reseed the generator behind `random` and `randomInt`, making their sequence repeatable.
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.rng.seed(seed);
+    }
+
+    /*
+    A runtime error as a Lox value: an instance of the built-in `Error` class, with the error's
+    message in its `message` field and the line it was reported at in `line`, for handing to code
+    that recovers from it. `None` for the signals that travel as errors but aren't any, like
+    `Return` and `Break`, and before the class has been defined.
+    */
+    pub fn error_value(&self, err: &RuntimeError) -> Option<Value> {
+        let (RuntimeError::Error { token, message, .. }, Some(class)) = (err, &self.error_class) else {
+            return None;
+        };
+        let mut instance = LoxInstance::new(class.clone());
+        let field = |name: &str| Token::new(TokenType::Identifier, name.to_string(), Literal::Nil, token.line);
+        // a new instance is never frozen, so setting its fields can't fail
+        instance.set(&field("message"), &Value::String(message.as_str().into())).ok()?;
+        instance.set(&field("line"), &Value::Int(token.line as i64)).ok()?;
+        Some(Value::LoxInstance(Rc::new(RefCell::new(instance))))
+    }
+
+    /// Send the output of `eprint` to `error_output` instead of stderr.
+    pub fn set_error_output(&mut self, error_output: Box<dyn Write>) {
+        self.error_output = error_output;
+    }
+
+    /// Write one line to the error sink, for natives that report diagnostics rather than output.
+    /// A sink that fails to take it is a runtime error at the native's call.
+    pub fn write_error_line(&mut self, text: &str) -> Result<(), RuntimeError> {
+        writeln!(self.error_output, "{}", text)
+            .map_err(|err| self.native_error(format!("Can't write to error output: {}.", err)))
+    }
+
+    /// How many calls may be in progress at once before a further one is a stack overflow.
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /*
+    Every Lox call is a handful of nested Rust calls in the evaluator, so a Lox program that
+    recurses without end would eventually overflow the Rust stack, which aborts the whole process
+    with no chance to report anything. To keep that from happening we count the calls in
+    progress and refuse to start one more past `max_call_depth`, with an ordinary runtime error
+    at the call. It unwinds like any other error, so the REPL or the embedding program carries on.
+    The caller is responsible for decrementing `call_depth` once the call is over.
+    */
+    fn enter_call(&mut self, paren: &Token) -> Result<(), RuntimeError> {
+        self.check_budget(paren)?;
+        if self.call_depth >= self.max_call_depth {
+            return Err(RuntimeError::new(
+                paren.clone(),
+                format!("Stack overflow: exceeded {} nested calls.", self.max_call_depth),
+            ));
+        }
+        self.call_depth += 1;
+        Ok(())
+    }
+
+    // called by `LoxFunction::call` on entering a function, with the frame to push
+    pub(crate) fn push_frame(&mut self, frame: CallFrame) {
+        self.frames.push(frame);
+    }
+
+    pub(crate) fn pop_frame(&mut self) {
+        self.frames.pop();
+    }
+
+    // a tail call: the function running in the innermost frame is now `function`
+    pub(crate) fn replace_frame_function(&mut self, function: String, is_method: bool) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.function = function;
+            frame.is_method = is_method;
+        }
+    }
+
+    // give `error` a copy of the stack, unless it already has one from a function deeper down
+    pub(crate) fn attach_trace(&self, error: &mut RuntimeError) {
+        if let RuntimeError::Error { trace, .. } = error
+            && trace.is_empty()
+        {
+            trace.clone_from(&self.frames);
+        }
+    }
+
+    /// An error raised by a native function, reported at the call that invoked it.
+    pub fn native_error(&self, message: String) -> RuntimeError {
+        RuntimeError::new(self.call_site(), message)
+    }
+
+    /// The token of the call being made, where errors raised by a native are reported.
+    pub(crate) fn call_site(&self) -> Token {
+        self.call_site
+            .clone()
+            .unwrap_or_else(|| Token::new(TokenType::Eof, String::new(), Literal::Nil, 0))
+    }
+
+    pub fn strict_returns(&self) -> bool {
+        self.strict_returns
+    }
+
+    /// Raise an error when a function that returns a value on some paths reaches the end of its
+    /// body instead, rather than quietly returning nil.
+    pub fn set_strict_returns(&mut self, enabled: bool) {
+        self.strict_returns = enabled;
+    }
+
+    pub fn max_loop_iterations(&self) -> Option<usize> {
+        self.max_loop_iterations
+    }
+
+    /// Stop a loop, of any kind, with "Loop iteration limit exceeded." once its body is about to
+    /// run more than `limit` times in one go, to catch an accidental infinite loop while
+    /// developing. The count starts over each time the loop is entered, so a loop nested in
+    /// another gets the full limit on every pass of the outer one. `None`, the default, is no
+    /// limit.
+    pub fn set_max_loop_iterations(&mut self, limit: Option<usize>) {
+        self.max_loop_iterations = limit;
+    }
+
+    /// How many statements have been executed since the evaluator was created.
+    pub fn statements_executed(&self) -> u64 {
+        self.statements_executed
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Bound the work each run may do, see `Limits`. Takes effect from the next `start_budget`.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Start counting against the limits afresh, at the start of a run.
+    pub fn start_budget(&mut self) {
+        self.budget = (!self.limits.is_unlimited()).then(|| Budget::start(self.limits));
+    }
+
+    // Called before each pass of a loop's body, with the count of passes so far in this run of
+    // the loop, which it bumps.
+    fn count_iteration(&self, keyword: &Token, iterations: &mut usize) -> Result<(), RuntimeError> {
+        *iterations += 1;
+        if let Some(limit) = self.max_loop_iterations
+            && *iterations > limit
+        {
+            return Err(RuntimeError::new(keyword.clone(), "Loop iteration limit exceeded.".to_string()));
+        }
+        Ok(())
+    }
+
+    fn check_budget(&mut self, token: &Token) -> Result<(), RuntimeError> {
+        if let Some(budget) = &mut self.budget
+            && budget.exceeded()
+        {
+            return Err(RuntimeError::new(token.clone(), "Execution budget exceeded.".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Record that the function declaration with this `id` may fall off its end without returning
+    /// a value. Keyed by the declaration rather than its name, as two methods of different classes,
+    /// or a function declared again on a later REPL line, can share a name and even a line.
+    pub fn mark_implicit_return(&mut self, id: ExprId) {
+        self.implicit_returns.insert(id);
+    }
+
+    // Called when the function declared as `name`, with this `id`, finished without a `return`.
+    pub(crate) fn check_implicit_return(&self, name: &Token, id: ExprId) -> Result<(), RuntimeError> {
+        if self.strict_returns && self.implicit_returns.contains(&id) {
+            return Err(RuntimeError::new(
+                name.clone(),
+                format!("Function '{}' reached the end of its body without returning a value.", name.lexeme),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn ieee_division(&self) -> bool {
+        self.ieee_division
+    }
+
+    /// Let `x / 0` evaluate to an infinity or NaN instead of raising "Division by zero.".
+    pub fn set_ieee_division(&mut self, enabled: bool) {
+        self.ieee_division = enabled;
+    }
+
+    /*
+    Dividing by zero does not fail in floating point, it produces an infinity (or NaN for 0 / 0).
+    Those then flow silently into every calculation that touches them, far away from the division
+    that went wrong. So by default a zero divisor, either sign, is a runtime error reported at the
+    operator. Embedders that want IEEE behaviour back can turn on `ieee_division`.
+    */
+    fn check_divisor(&self, operator: &Token, divisor: f64) -> Result<(), RuntimeError> {
+        if divisor == 0.0 && !self.ieee_division {
+            return Err(RuntimeError::new(
+                operator.clone(),
+                "Division by zero.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn book_messages(&self) -> bool {
+        self.book_messages
+    }
+
+    /// Word operand type errors exactly as the book does ("Operands must be numbers.")
+    /// instead of naming the operator and the offending values.
+    pub fn set_book_messages(&mut self, enabled: bool) {
+        self.book_messages = enabled;
+    }
+
+    pub fn allocation_limit(&self) -> usize {
+        self.allocation_limit
+    }
+
+    pub fn set_allocation_limit(&mut self, limit: usize) {
+        self.allocation_limit = limit;
+    }
+
+    /*
+    Repetition and concatenation let a tiny program ask for an enormous amount of memory, e.g.
+    `"a" * 1000000000`. Before allocating anything we work out how big the result would be, using
+    checked arithmetic so that the size computation itself cannot overflow, and refuse anything
+    over the allocation limit. `None` means the size did not even fit in a usize.
+    */
+    pub(crate) fn check_allocation(&self, operator: &Token, size: Option<usize>) -> Result<(), RuntimeError> {
+        match size {
+            Some(size) if size <= self.allocation_limit => Ok(()),
+            _ => Err(RuntimeError::new(
+                operator.clone(),
+                "Allocation limit exceeded.".to_string(),
+            )),
+        }
+    }
+
+    fn repeat_string(&self, operator: &Token, s: &str, count: &Value) -> Result<Value, RuntimeError> {
+        // `as` saturates for counts beyond usize::MAX, which the checked multiply then rejects
+        let count = match count {
+            Value::Int(n) if *n >= 0 => *n as usize,
+            // this also rejects NaN and the infinities, whose fractional part is NaN
+            Value::Float(n) if *n >= 0.0 && n.fract() == 0.0 => *n as usize,
+            _ => {
+                return Err(RuntimeError::new(
+                    operator.clone(),
+                    "Repetition count must be a non-negative whole number.".to_string(),
+                ));
+            }
+        };
+        self.check_allocation(operator, s.len().checked_mul(count))?;
+        Ok(Value::String(s.repeat(count).into()))
     }
 
     pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
         expr.accept(self)
     }
 
-    pub fn look_up_variable(&mut self, name: &Token, expr: &Expr) -> Result<Value, RuntimeError> {
+    /// Record that the reference `id` resolves to a binding `depth` scopes out from where it is used.
+    pub fn resolve(&mut self, id: ExprId, depth: usize) {
+        self.locals.insert(id, depth);
+    }
+
+    pub fn look_up_variable(&mut self, name: &Token, id: ExprId) -> Result<Value, RuntimeError> {
         // Check if this is a local variable or a global variable
-        if let Some(distance) = self.locals.get(expr) {
+        if let Some(distance) = self.locals.get(&id) {
             // Access the variable in the appropriate scope
-            self.environment.get_at(*distance, &name.lexeme)
+            self.environment.borrow().get_at(*distance, &name.lexeme)
         } else {
             // Fallback to global environment if not found in local scope
-            self.globals.get(name)
+            self.globals.borrow().get(name)
         }
     }
 
     pub fn execute(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
+        self.statements_executed += 1;
+        if let Some(budget) = &mut self.budget {
+            budget.steps += 1;
+        }
         stmt.accept(self)
     }
 
@@ -749,8 +2407,22 @@ impl Evaluator {
         statements: &[Stmt],
         new_env: Environment,
     ) -> Result<(), RuntimeError> {
+        self.evaluate_block(statements, None, new_env).map(|_| ())
+    }
+
+    // `execute_block`, then evaluate `value` in the block's scope, for a block expression
+    fn evaluate_block(
+        &mut self,
+        statements: &[Stmt],
+        value: Option<&Expr>,
+        new_env: Environment,
+    ) -> Result<Value, RuntimeError> {
+        // `new_env` is a fresh, empty child that only points at its parent, so entering
+        // the block copies none of the bindings in the enclosing scopes.
         // Swap current and new environments.
-        // `old_env` now owns the previous scope, so we can restore it later.
+        // `old_env` now holds the previous scope, so we can restore it later.
+        let new_env = Rc::new(RefCell::new(new_env));
+        self.heap.register(&new_env);
         let old_env = std::mem::replace(&mut self.environment, new_env);
 
         // Ensure the previous environment is restored even on early return or error.
@@ -758,7 +2430,10 @@ impl Evaluator {
             for stmt in statements {
                 self.execute(stmt)?;
             }
-            Ok(())
+            match value {
+                Some(value) => self.evaluate(value),
+                None => Ok(Value::Nil),
+            }
         })();
 
         // put the original environment back
@@ -766,45 +2441,70 @@ impl Evaluator {
         result
     }
 
+    /*
+    A type error names the operator and shows what each operand actually was, e.g.
+
+        Operator '<' expects numbers, got string ("abc") and number (3).
+
+    The operator token is kept on the error so that the reported line is the operator's.
+    With `book_messages` set, the wording is the book's instead, for matching its test suite.
+    */
     pub fn check_number_operand(
-        &self,
+        &mut self,
         operator: Token,
         operand: &Value,
     ) -> Result<(), RuntimeError> {
-        // this mechanism keeps track of the token which causes the runtime error
-        // so we can print the token and line that triggered the runtime error
         match operand {
-            Value::Number(_) => Ok(()),
-            _ => Err(RuntimeError::new(
-                operator,
-                "Operand must be a number".parse().unwrap(),
-            )),
+            Value::Int(_) | Value::Float(_) => Ok(()),
+            _ => {
+                let message = if self.book_messages {
+                    "Operand must be a number.".to_string()
+                } else {
+                    format!(
+                        "Operator '{}' expects a number, got {}.",
+                        operator.lexeme,
+                        self.describe(operand, &operator)
+                    )
+                };
+                Err(RuntimeError::new(operator, message))
+            }
         }
     }
 
     pub fn check_number_operands(
-        &self,
+        &mut self,
         operator: Token,
         left: &Value,
         right: &Value,
     ) -> Result<(), RuntimeError> {
-        // this mechanism keeps track of the token which causes the runtime error
-        // so we can print the token and line that triggered the runtime error
-        match left {
-            Value::Number(_) => match right {
-                Value::Number(_) => Ok(()),
-                _ => Err(RuntimeError::new(
-                    operator,
-                    "Right operand must be a number".parse().unwrap(),
-                )),
-            },
-            _ => Err(RuntimeError::new(
-                operator,
-                "Left operand must be a number".parse().unwrap(),
-            )),
+        match (left, right) {
+            (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => Ok(()),
+            _ => Err(self.operands_error(operator, "numbers", "Operands must be numbers.", left, right)),
         }
     }
 
+    fn operands_error(
+        &mut self,
+        operator: Token,
+        expected: &str,
+        book_message: &str,
+        left: &Value,
+        right: &Value,
+    ) -> RuntimeError {
+        let message = if self.book_messages {
+            book_message.to_string()
+        } else {
+            format!(
+                "Operator '{}' expects {}, got {} and {}.",
+                operator.lexeme,
+                expected,
+                self.describe(left, &operator),
+                self.describe(right, &operator)
+            )
+        };
+        RuntimeError::new(operator, message)
+    }
+
     pub fn is_truthy(&self, value: &Value) -> bool {
         match value {
             Value::Nil => false,
@@ -812,14 +2512,12 @@ impl Evaluator {
             _ => true,
         }
     }
+}
 
-    pub fn is_equal(&self, v1: &Value, v2: &Value) -> bool {
-        match (v1, v2) {
-            (Value::Nil, Value::Nil) => true,
-            (Value::Bool(b1), Value::Bool(b2)) => b1 == b2,
-            (Value::Number(n1), Value::Number(n2)) => n1 == n2,
-            (Value::String(s1), Value::String(s2)) => s1 == s2,
-            _ => false,
-        }
+impl Drop for Evaluator {
+    // once the evaluator is gone nothing can run again, so clear every environment it created.
+    // Whatever was only kept alive by a cycle is freed instead of leaking until the process exits.
+    fn drop(&mut self) {
+        self.heap.release_all();
     }
 }
@@ -0,0 +1,333 @@
+use std::collections::HashSet;
+use crate::lexer::{Literal, Token};
+use crate::parser::{Expr, ExprId, ParseError, Visitor};
+use crate::{RuntimeError, Stmt, StmtVisitor, Value};
+
+/*
+The free variables of a piece of code are the names it uses without binding them itself: the
+variables a closure captures, or the globals it relies on. An editor can use them to highlight
+what a function reaches out for.
+
+This is another visitor over the tree, like the resolver and the AST printer, and it follows the
+resolver's idea of scope. A block, a function body and the loop variable of `for (x in ...)` each
+open a scope, and a name is bound from its declaration on: in
+
+    { print a; var a = 1; print a; }
+
+the first `a` is free and the second is not. A function's name is bound before its body, so it
+can call itself, and its parameters are bound inside it; a `var` is only bound after its
+initializer. A method also has `this`, and `super` when its class has a superclass. A property
+name, as in `point.x`, is never a variable.
+
+There is no scope around the expression itself, so a name that only a statement of the enclosing
+program declares is still free in it.
+*/
+pub fn free_variables(expr: &Expr) -> HashSet<String> {
+    let mut analysis = FreeVariables::default();
+    analysis.expr(expr);
+    analysis.free
+}
+
+/// `free_variables` for a sequence of statements, such as the body of a function. They share a
+/// scope, so a name one of them declares is bound in the ones after it.
+pub fn free_variables_in(statements: &[Stmt]) -> HashSet<String> {
+    let mut analysis = FreeVariables::default();
+    analysis.scoped(&[], |analysis| {
+        for stmt in statements {
+            analysis.stmt(stmt);
+        }
+    });
+    analysis.free
+}
+
+#[derive(Default)]
+struct FreeVariables {
+    // the names bound so far in each scope we are inside, innermost last
+    scopes: Vec<HashSet<String>>,
+    free: HashSet<String>,
+}
+
+impl FreeVariables {
+    fn expr(&mut self, expr: &Expr) {
+        // nothing here can fail, the visitor only returns a Result because its trait says so
+        let _ = expr.accept(self);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        stmt.accept(self)
+    }
+
+    fn bind(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string());
+        }
+    }
+
+    fn reference(&mut self, name: &str) {
+        if !self.scopes.iter().any(|scope| scope.contains(name)) {
+            self.free.insert(name.to_string());
+        }
+    }
+
+    // run `body` in a new scope that starts out with `names` bound
+    fn scoped(&mut self, names: &[&str], body: impl FnOnce(&mut Self)) {
+        self.scopes.push(names.iter().map(|name| name.to_string()).collect());
+        body(self);
+        self.scopes.pop();
+    }
+
+    fn function(&mut self, params: &[Token], body: &[Stmt]) {
+        let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+        self.scoped(&params, |analysis| {
+            for stmt in body {
+                analysis.stmt(stmt);
+            }
+        });
+    }
+}
+
+impl Visitor for FreeVariables {
+    fn visit_literal_expr(&mut self, _value: &Literal) -> Result<Value, RuntimeError> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        self.expr(expr);
+        Ok(Value::Nil)
+    }
+
+    fn visit_unary_expr(&mut self, _operator: &Token, right: &Expr) -> Result<Value, RuntimeError> {
+        self.expr(right);
+        Ok(Value::Nil)
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, _operator: &Token, right: &Expr) -> Result<Value, RuntimeError> {
+        self.expr(left);
+        self.expr(right);
+        Ok(Value::Nil)
+    }
+
+    fn visit_variable_expr(&mut self, token: &Token, _id: ExprId) -> Result<Value, RuntimeError> {
+        self.reference(&token.lexeme);
+        Ok(Value::Nil)
+    }
+
+    fn visit_assign_expr(&mut self, token: &Token, value: &Expr, _id: ExprId) -> Result<Value, RuntimeError> {
+        self.expr(value);
+        self.reference(&token.lexeme);
+        Ok(Value::Nil)
+    }
+
+    fn visit_logical_expr(&mut self, left: &Expr, _operator: &Token, right: &Expr) -> Result<Value, RuntimeError> {
+        self.expr(left);
+        self.expr(right);
+        Ok(Value::Nil)
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        _open_paren: &Token,
+        _paren: &Token,
+        arguments: &[Expr],
+        _names: &[Option<Token>],
+    ) -> Result<Value, RuntimeError> {
+        self.expr(callee);
+        for argument in arguments {
+            self.expr(argument);
+        }
+        Ok(Value::Nil)
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, _name: &Token, _optional: bool, _id: ExprId) -> Result<Value, RuntimeError> {
+        self.expr(object);
+        Ok(Value::Nil)
+    }
+
+    fn visit_set_expr(&mut self, object: &Expr, _name: &Token, value: &Expr) -> Result<Value, RuntimeError> {
+        self.expr(value);
+        self.expr(object);
+        Ok(Value::Nil)
+    }
+
+    fn visit_this_expr(&mut self, this: &Token, _id: ExprId) -> Result<Value, RuntimeError> {
+        self.reference(&this.lexeme);
+        Ok(Value::Nil)
+    }
+
+    fn visit_super_expr(&mut self, keyword: &Token, _method: &Token, _id: ExprId) -> Result<Value, RuntimeError> {
+        self.reference(&keyword.lexeme);
+        Ok(Value::Nil)
+    }
+
+    fn visit_array_expr(&mut self, elements: &[Expr]) -> Result<Value, RuntimeError> {
+        for element in elements {
+            self.expr(element);
+        }
+        Ok(Value::Nil)
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> Result<Value, RuntimeError> {
+        for (key, value) in entries {
+            self.expr(key);
+            self.expr(value);
+        }
+        Ok(Value::Nil)
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> Result<Value, RuntimeError> {
+        self.expr(object);
+        self.expr(index);
+        Ok(Value::Nil)
+    }
+
+    fn visit_slice_expr(&mut self, object: &Expr, _bracket: &Token, start: &Expr, end: &Expr) -> Result<Value, RuntimeError> {
+        self.expr(object);
+        self.expr(start);
+        self.expr(end);
+        Ok(Value::Nil)
+    }
+
+    fn visit_index_set_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr, value: &Expr) -> Result<Value, RuntimeError> {
+        self.expr(object);
+        self.expr(index);
+        self.expr(value);
+        Ok(Value::Nil)
+    }
+
+    fn visit_block_expr(&mut self, statements: &[Stmt], value: Option<&Expr>) -> Result<Value, RuntimeError> {
+        self.scoped(&[], |analysis| {
+            for stmt in statements {
+                analysis.stmt(stmt);
+            }
+            if let Some(value) = value {
+                analysis.expr(value);
+            }
+        });
+        Ok(Value::Nil)
+    }
+}
+
+impl StmtVisitor<()> for FreeVariables {
+    fn visit_expression_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Expression { expression } = stmt {
+            self.expr(expression);
+        }
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Print { expression, .. } = stmt {
+            self.expr(expression);
+        }
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Var { name, initializer, .. } = stmt {
+            if let Some(initializer) = initializer {
+                self.expr(initializer);
+            }
+            self.bind(&name.lexeme);
+        }
+    }
+
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) {
+        self.scoped(&[], |analysis| {
+            for stmt in statements {
+                analysis.stmt(stmt);
+            }
+        });
+    }
+
+    fn visit_if_stmt(&mut self, condition: &Expr, then_branch: &Stmt, else_branch: &Option<Box<Stmt>>) {
+        self.expr(condition);
+        self.stmt(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.stmt(else_branch);
+        }
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        _keyword: &Token,
+        _label: &Option<Token>,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Box<Expr>>,
+        else_branch: &Option<Box<Stmt>>,
+    ) {
+        self.expr(condition);
+        self.stmt(body);
+        if let Some(increment) = increment {
+            self.expr(increment);
+        }
+        if let Some(else_branch) = else_branch {
+            self.stmt(else_branch);
+        }
+    }
+
+    fn visit_continue_stmt(&mut self, _keyword: &Token, _label: &Option<Token>) {}
+
+    fn visit_break_stmt(&mut self, _keyword: &Token, _label: &Option<Token>) {}
+
+    fn visit_repeat_stmt(&mut self, _keyword: &Token, _label: &Option<Token>, count: &Expr, body: &Stmt) {
+        self.expr(count);
+        self.stmt(body);
+    }
+
+    fn visit_do_while_stmt(&mut self, _keyword: &Token, _label: &Option<Token>, body: &Stmt, condition: &Expr) {
+        self.stmt(body);
+        self.expr(condition);
+    }
+
+    fn visit_try_catch_stmt(&mut self, _keyword: &Token, body: &[Stmt], error_name: &Token, handler: &[Stmt]) {
+        self.scoped(&[], |analysis| body.iter().for_each(|stmt| analysis.stmt(stmt)));
+        self.scoped(&[&error_name.lexeme], |analysis| handler.iter().for_each(|stmt| analysis.stmt(stmt)));
+    }
+
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) {
+        self.expr(value);
+    }
+
+    // an import only defines globals, and reads nothing from the scope it is in
+    fn visit_import_stmt(&mut self, _keyword: &Token, _path: &Token, _alias: &Option<Token>) {}
+
+    fn visit_for_in_stmt(&mut self, _keyword: &Token, _label: &Option<Token>, index: &Option<Token>, name: &Token, iterable: &Expr, body: &Stmt) {
+        self.expr(iterable);
+        let names: Vec<&str> = index.iter().chain([name]).map(|name| name.lexeme.as_str()).collect();
+        self.scoped(&names, |analysis| analysis.stmt(body));
+    }
+
+    fn visit_fun_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        _param_types: &[Option<Token>],
+        body: &[Stmt],
+        _return_type: &Option<Token>,
+        _id: ExprId,
+    ) {
+        self.bind(&name.lexeme);
+        self.function(params, body);
+    }
+
+    fn visit_return_stmt(&mut self, _keyword: &Token, value: &Option<Box<Expr>>) {
+        if let Some(value) = value {
+            self.expr(value);
+        }
+    }
+
+    fn visit_class_stmt(&mut self, name: &Token, methods: &[Result<Stmt, ParseError>], superclass: &Option<Box<Expr>>) {
+        self.bind(&name.lexeme);
+        if let Some(superclass) = superclass {
+            self.expr(superclass);
+        }
+        let receiver: &[&str] = if superclass.is_some() { &["this", "super"] } else { &["this"] };
+        self.scoped(receiver, |analysis| {
+            for method in methods.iter().flatten() {
+                if let Stmt::Function { params, body, .. } = method {
+                    analysis.function(params, body);
+                }
+            }
+        });
+    }
+}
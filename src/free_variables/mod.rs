@@ -0,0 +1,2 @@
+pub mod free_variables;
+pub use free_variables::*;
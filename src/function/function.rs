@@ -1,12 +1,16 @@
+use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
 
 use crate::environment::Environment;
-use crate::evaluator::{Evaluator, RuntimeError};
+use crate::evaluator::{CallFrame, Evaluator, RuntimeError};
 use crate::evaluator::{Value, LoxCallable};
-use crate::LoxInstance;
+use crate::{GcRef, LoxInstance, Token};
 use crate::parser::Stmt;
 
+// when less stack than this is left, a call runs its body on a new segment, see `LoxFunction::call`
+const STACK_RED_ZONE: usize = 256 * 1024;
+const STACK_SEGMENT_SIZE: usize = 8 * 1024 * 1024;
 
 /*
 Parameters are core to functions, especially the fact that a function
@@ -43,32 +47,88 @@ This is safe because `visit_call_expr()` checks the arityu before calling `call(
 on the function reporting its arity to do that. 
 */
 
+/*
+A function can declare the types of its parameters and of the value it returns,
+`fun area(w: number, h: number) -> number { ... }`, each one optional. A parameter's type is
+checked as the argument is bound to it, before the body runs, and the error names the parameter.
+The return type is checked when the function returns, whichever way it does so: a `return` with a
+value of the wrong type, or falling off the end of the body (which returns nil) in a function
+that did not declare `nil`, is a runtime error. Without declared types, anything goes, exactly as
+before.
+
+The type names are deliberately few: `number`, `string`, `bool` and `nil` stand for values of
+that kind, and `any` accepts everything, for when the annotation is only there as documentation.
+*/
+pub const TYPE_NAMES: [&str; 5] = ["number", "string", "bool", "nil", "any"];
+
+// whether `value` is of the type called `type_name`, one of `TYPE_NAMES`
+pub fn has_type(value: &Value, type_name: &str) -> bool {
+    match type_name {
+        "number" => matches!(value, Value::Int(_) | Value::Float(_)),
+        "string" => matches!(value, Value::String(_)),
+        "bool" => matches!(value, Value::Bool(_)),
+        "nil" => matches!(value, Value::Nil),
+        _ => true,
+    }
+}
+
 /// A user-defined function object.
 #[derive(Debug, Clone)]
 pub struct LoxFunction {
     // keep an Rc so multiple closures can share the same declaration
     declaration: Rc<Stmt>,        // must be Stmt::Function
-    closure:     Rc<Environment>,
+    closure:     Rc<RefCell<Environment>>,
+    // the global scope the function was declared in, where it looks up the names the resolver
+    // left as globals. That is the program's own, unless it comes from a module imported with
+    // `as`, see namespace.rs
+    globals:     Rc<RefCell<Environment>>,
     is_initializer: bool,
 }
 
 impl LoxFunction {
-    pub fn new(decl: Stmt, closure: Rc<Environment>, is_initializer: bool) -> Self {
+    pub fn new(
+        decl: Stmt,
+        closure: Rc<RefCell<Environment>>,
+        globals: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    ) -> Self {
         Self {
             declaration: Rc::new(decl),
             closure,
+            globals,
             is_initializer
         }
     }
-    pub fn bind(&self, instance: LoxInstance) -> LoxFunction {
-        let mut env = Environment::new_enclosed((*self.closure).clone());
+
+    /// Whether `self` and `other` are the same function: the same declaration closing over the
+    /// same environment. A method bound twice to the same instance gives two different functions.
+    pub fn same_object(&self, other: &LoxFunction) -> bool {
+        Rc::ptr_eq(&self.declaration, &other.declaration) && Rc::ptr_eq(&self.closure, &other.closure)
+    }
+    /*
+    Binding a method gives it a closure of its own, holding nothing but `this`, wrapped around the
+    closure the method was declared in. `this` is the shared handle to the instance, not a copy of
+    it, so a method detached with `var m = point.norm;` and called much later sees the fields as
+    they are then, and any fields it sets are set on the instance everyone else sees too.
+    */
+    pub fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> LoxFunction {
+        let mut env = Environment::new_enclosed(Rc::clone(&self.closure));
         env.define("this".to_string(), Value::LoxInstance(instance));
 
-        LoxFunction::new((*self.declaration).clone(), Rc::new(env), self.is_initializer)
+        LoxFunction {
+            declaration: Rc::clone(&self.declaration),
+            closure: Rc::new(RefCell::new(env)),
+            globals: Rc::clone(&self.globals),
+            is_initializer: self.is_initializer,
+        }
     }
 }
 
 impl LoxCallable for LoxFunction {
+    fn is_native(&self) -> bool {
+        false
+    }
+
     fn arity(&self) -> usize {
         match &*self.declaration {
             Stmt::Function { params, .. } => params.len(),
@@ -76,29 +136,182 @@ impl LoxCallable for LoxFunction {
         }
     }
 
+    fn name(&self) -> &str {
+        match &*self.declaration {
+            Stmt::Function { name, .. } => &name.lexeme,
+            _ => unreachable!("LoxFunction without Function declaration"),
+        }
+    }
+
+    fn param_names(&self) -> Option<Vec<String>> {
+        match &*self.declaration {
+            Stmt::Function { params, .. } => Some(params.iter().map(|param| param.lexeme.clone()).collect()),
+            _ => None,
+        }
+    }
+
+    fn as_lox_function(&self) -> Option<&LoxFunction> {
+        Some(self)
+    }
+
+    /*
+    A call whose value is returned straight away, `return f(args);`, is a tail call: once `f`
+    returns, there is nothing left for the caller to do. Making it from inside the caller's body
+    would nest one more `call` on the Rust stack for every such call, so a loop written as tail
+    recursion, or two functions that hand off to each other the way `isEven` and `isOdd` do, would
+    run out of call depth after a thousand rounds.
+
+    Instead, `visit_return_stmt` evaluates the callee and the arguments and raises
+    `RuntimeError::TailCall` with them. That unwinds the caller's body like any return, and lands
+    here, where we run the new function's body in place of the old one, in a fresh environment of
+    its own. However long the chain, it takes one `call` on the Rust stack and one unit of call
+    depth. Only functions declared in Lox are called this way; `return clock();` or
+    `return Point(1, 2);` is an ordinary call followed by a return.
+
+    What the last function in the chain returns is what every function before it returns too, so
+    it has to satisfy all of their return types. We collect them as we go, each declaration only
+    once, so a loop written as tail recursion keeps checking a single type however long it runs.
+
+    Each call that isn't a tail call still nests the walk of the body one level deeper on the Rust
+    stack, some 20 KB in a debug build. Whatever thread the interpreter was started on, a test's
+    2 MiB or a host's main thread, that runs out long before `max_call_depth` would stop it, and the
+    process aborts. So when less than `STACK_RED_ZONE` is left, the body runs on a fresh segment of
+    stack instead, and the call depth limit is what ends a runaway recursion, with a runtime error.
+    */
     fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        interpreter.push_frame(CallFrame {
+            function: self.name().to_string(),
+            is_method: self.is_method(),
+            call_line: interpreter.call_site().line,
+        });
+        let globals = std::mem::replace(&mut interpreter.globals, Rc::clone(&self.globals));
+        // a `try` around the call is not one inside the body
+        let try_depth = std::mem::take(&mut interpreter.try_depth);
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+            self.clone().run_tail_calls(interpreter, arguments)
+        });
+        interpreter.try_depth = try_depth;
+        interpreter.globals = globals;
+        interpreter.pop_frame();
+        result
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        visit(GcRef::Environment(Rc::clone(&self.closure)));
+        visit(GcRef::Environment(Rc::clone(&self.globals)));
+    }
+}
+
+impl LoxFunction {
+    // a method is bound to an instance, whose `this` is the one thing its closure defines
+    fn is_method(&self) -> bool {
+        self.closure.borrow().values.contains_key("this")
+    }
+
+    // run `self`, then every function it tail calls, in the frame pushed by `call`
+    fn run_tail_calls(self, interpreter: &mut Evaluator, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let mut function = self;
+        let mut arguments = arguments;
+        // (declaration, function name, whether it is a method, declared type) for every function
+        // with a declared return type met along the way; the error for one that isn't met is
+        // raised in that function's frame
+        let mut return_types: Vec<(Rc<Stmt>, Token, bool, Token)> = Vec::new();
+        loop {
+            if let Stmt::Function { name, return_type: Some(return_type), .. } = &*function.declaration
+                && !return_types.iter().any(|(seen, ..)| Rc::ptr_eq(seen, &function.declaration))
+            {
+                return_types.push((
+                    Rc::clone(&function.declaration),
+                    name.clone(),
+                    function.is_method(),
+                    return_type.clone(),
+                ));
+            }
+            match function.run(interpreter, arguments) {
+                Err(RuntimeError::TailCall(callee, callee_arguments)) => {
+                    interpreter.replace_frame_function(callee.name().to_string(), callee.is_method());
+                    interpreter.globals = Rc::clone(&callee.globals);
+                    function = callee;
+                    arguments = callee_arguments;
+                }
+                Err(mut error) => {
+                    interpreter.attach_trace(&mut error);
+                    return Err(error);
+                }
+                Ok(value) => {
+                    // the innermost function first, the one whose `return` produced the value,
+                    // as it would be without tail calls
+                    for (_, name, is_method, return_type) in return_types.iter().rev() {
+                        if !has_type(&value, &return_type.lexeme) {
+                            interpreter.replace_frame_function(name.lexeme.clone(), *is_method);
+                            let mut error = RuntimeError::new(
+                                return_type.clone(),
+                                format!(
+                                    "'{}' must return {}, got {}.",
+                                    name.lexeme,
+                                    return_type.lexeme,
+                                    value.type_name()
+                                ),
+                            );
+                            interpreter.attach_trace(&mut error);
+                            return Err(error);
+                        }
+                    }
+                    return Ok(value);
+                }
+            }
+        }
+    }
+
+}
+
+impl LoxFunction {
+    // run the body once, with `arguments` bound to the parameters
+    fn run(
         &self,
         interpreter: &mut Evaluator,
         mut arguments: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
+        // the call environment points at the closure rather than copying it, so every
+        // function that captured the same scope sees the same variables
+        let mut env = Environment::new_enclosed(Rc::clone(&self.closure));
 
-        let closure = (*self.closure).clone();
-        let mut env = Environment::new_enclosed(closure);
-        
-        if let Stmt::Function { params, .. } = &*self.declaration {
-            for (tok, arg) in params.iter().zip(arguments.drain(..)) {
+        if let Stmt::Function { name, params, param_types, .. } = &*self.declaration {
+            for ((tok, param_type), arg) in params.iter().zip(param_types).zip(arguments.drain(..)) {
+                if let Some(param_type) = param_type
+                    && !has_type(&arg, &param_type.lexeme)
+                {
+                    return Err(RuntimeError::new(
+                        tok.clone(),
+                        format!(
+                            "Parameter '{}' of '{}' must be {}, got {}.",
+                            tok.lexeme,
+                            name.lexeme,
+                            param_type.lexeme,
+                            arg.type_name()
+                        ),
+                    ));
+                }
                 env.define(tok.lexeme.clone(), arg);
             }
         }
 
-        if let Stmt::Function { body, .. } = &*self.declaration {
+        if let Stmt::Function { name, body, id, .. } = &*self.declaration {
             match interpreter.execute_block(body, env) {
+                // an initializer hands back `this` however it ends, from a bare `return;` too (the
+                // resolver makes sure that is the only kind it has), so calling `init` directly
+                // on an instance re-initializes it and returns that same instance
+                Ok(()) | Err(RuntimeError::Return(_)) if self.is_initializer => {
+                    self.closure.borrow().get_at(0, "this")
+                }
                 // If it completes normally, return nil (no explicit return)
                 Ok(()) => {
-                    // If it's an initializer, return `this` instead of `nil`
-                    if self.is_initializer {
-                        return self.closure.get_at(0, "this");
-                    }
+                    // in strict mode, a function that was meant to return a value may not end here
+                    interpreter.check_implicit_return(name, *id)?;
                     Ok(Value::Nil)
                 }
                 Err(RuntimeError::Return(v)) => Ok(v.unwrap_or(Value::Nil)),
@@ -112,9 +325,13 @@ impl LoxCallable for LoxFunction {
 
 /* ───────────────────────── Display helper (optional) ───────────────────── */
 
+// a bound method also says what it is bound to: `<fn area of Point instance>`
 impl fmt::Display for LoxFunction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Stmt::Function { name, .. } = &*self.declaration {
+            if let Some(Value::LoxInstance(instance)) = self.closure.borrow().values.get("this") {
+                return write!(f, "<fn {} of {}>", name.lexeme, instance.borrow());
+            }
             write!(f, "<fn {}>", name.lexeme)
         } else {
             write!(f, "<fn>")
@@ -0,0 +1,373 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::{Rc, Weak};
+use crate::{Environment, Evaluator, LoxCallable, LoxInstance, LoxMap, RuntimeError, Value};
+
+/*
+Environments, instances, arrays and maps are reference counted, and reference counting cannot free
+cycles. A Lox program makes cycles all the time: declaring a function stores it in the very
+environment it closes over, an instance field can hold a closure that captured the environment
+holding the instance, and two instances can simply point at each other. Once the last outside
+reference to such a cycle goes away, every object in it is still keeping the others alive, so
+they leak.
+
+We deal with this with a small cycle collector in the spirit of CPython's:
+
+1. Every environment the evaluator creates for a block or a call, and every instance, array and
+   map a program creates, is recorded in a registry of weak handles. The registry itself keeps
+   nothing alive.
+2. When a collection runs, we start from the registered objects and walk everything they
+   reference - parent environments, fields, elements, and the callables and environments
+   reachable through the values bound in them. These are the nodes of the object graph.
+3. For every node we take its strong count and subtract each reference that comes from another
+   node. Whatever is left over must be held from *outside* the graph: the evaluator's current and
+   global scopes, a value sitting in a Rust local in the middle of evaluating an expression, or
+   a handle an embedder kept. Those nodes are the roots.
+4. Everything reachable from a root is alive. The registered objects that are not are garbage,
+   and we clear them - the bindings of an environment, the fields of an instance, the elements of
+   an array or map - which breaks the cycles and lets reference counting free the rest.
+
+Because roots are inferred from the reference counts rather than listed by hand, a collection
+never frees something that is still in use, no matter where in the evaluation it is triggered.
+Objects we never registered (the small scope `bind` creates to hold `this`) are still walked when
+they are reachable from registered ones; they just do not start a walk themselves.
+
+A collection only happens when the program asks for one with `collectGarbage()`, and when the
+evaluator is dropped, at which point every registered object is cleared unconditionally.
+`gcStats()` reports how many registered objects are still alive, so a long-running REPL session
+can be watched for leaks.
+*/
+/// A strong reference from one heap object to another, as reported by `trace`.
+pub enum GcRef {
+    Environment(Rc<RefCell<Environment>>),
+    Callable(Rc<dyn LoxCallable>),
+    Array(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<LoxMap>>),
+    Instance(Rc<RefCell<LoxInstance>>),
+}
+
+impl GcRef {
+    fn address(&self) -> usize {
+        match self {
+            GcRef::Environment(env) => Rc::as_ptr(env) as *const () as usize,
+            GcRef::Callable(callable) => Rc::as_ptr(callable) as *const () as usize,
+            GcRef::Array(array) => Rc::as_ptr(array) as *const () as usize,
+            GcRef::Map(map) => Rc::as_ptr(map) as *const () as usize,
+            GcRef::Instance(instance) => Rc::as_ptr(instance) as *const () as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            GcRef::Environment(env) => Rc::strong_count(env),
+            GcRef::Callable(callable) => Rc::strong_count(callable),
+            GcRef::Array(array) => Rc::strong_count(array),
+            GcRef::Map(map) => Rc::strong_count(map),
+            GcRef::Instance(instance) => Rc::strong_count(instance),
+        }
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        match self {
+            GcRef::Environment(env) => env.borrow().trace(visit),
+            GcRef::Callable(callable) => callable.trace(visit),
+            GcRef::Array(array) => {
+                for element in array.borrow().iter() {
+                    element.trace(visit);
+                }
+            }
+            // keys are plain literals, only the values can refer to anything
+            GcRef::Map(map) => {
+                for (_, value) in map.borrow().iter() {
+                    value.trace(visit);
+                }
+            }
+            GcRef::Instance(instance) => instance.borrow().trace(visit),
+        }
+    }
+}
+
+impl Value {
+    /// Report every heap object this value keeps alive.
+    pub(crate) fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        match self {
+            Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::String(_) | Value::Nil | Value::Range { .. } => {}
+            Value::Callable(callable) => visit(GcRef::Callable(Rc::clone(callable))),
+            Value::LoxClass(klass) => klass.trace(visit),
+            Value::LoxInstance(instance) => visit(GcRef::Instance(Rc::clone(instance))),
+            Value::LoxFunction(function) => function.trace(visit),
+            Value::Array(array) => visit(GcRef::Array(Rc::clone(array))),
+            Value::Map(map) => visit(GcRef::Map(Rc::clone(map))),
+            Value::Namespace(namespace) => namespace.trace(visit),
+        }
+    }
+}
+
+impl Environment {
+    pub(crate) fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        if let Some(enclosing) = &self.enclosing {
+            visit(GcRef::Environment(Rc::clone(enclosing)));
+        }
+        for value in self.values.values() {
+            value.trace(visit);
+        }
+    }
+}
+
+// a registered object, held weakly so that the registry keeps nothing alive
+enum WeakRef {
+    Environment(Weak<RefCell<Environment>>),
+    Instance(Weak<RefCell<LoxInstance>>),
+    Array(Weak<RefCell<Vec<Value>>>),
+    Map(Weak<RefCell<LoxMap>>),
+}
+
+impl WeakRef {
+    fn upgrade(&self) -> Option<GcRef> {
+        match self {
+            WeakRef::Environment(env) => env.upgrade().map(GcRef::Environment),
+            WeakRef::Instance(instance) => instance.upgrade().map(GcRef::Instance),
+            WeakRef::Array(array) => array.upgrade().map(GcRef::Array),
+            WeakRef::Map(map) => map.upgrade().map(GcRef::Map),
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        match self {
+            WeakRef::Environment(env) => env.strong_count() > 0,
+            WeakRef::Instance(instance) => instance.strong_count() > 0,
+            WeakRef::Array(array) => array.strong_count() > 0,
+            WeakRef::Map(map) => map.strong_count() > 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Heap {
+    objects: Vec<WeakRef>,
+    // the registry is compacted once it grows past this many entries
+    next_prune: usize,
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            next_prune: 1024,
+        }
+    }
+
+    pub fn register(&mut self, env: &Rc<RefCell<Environment>>) {
+        self.track(WeakRef::Environment(Rc::downgrade(env)));
+    }
+
+    /// Register `value` if it is an instance, array or map; anything else is left alone.
+    pub(crate) fn register_value(&mut self, value: &Value) {
+        match value {
+            Value::LoxInstance(instance) => self.track(WeakRef::Instance(Rc::downgrade(instance))),
+            Value::Array(array) => self.track(WeakRef::Array(Rc::downgrade(array))),
+            Value::Map(map) => self.track(WeakRef::Map(Rc::downgrade(map))),
+            _ => {}
+        }
+    }
+
+    /// Register `value` and every instance, array and map it holds, for a structure built all at
+    /// once outside the evaluator, like the result of `from_json` or `clone`.
+    pub(crate) fn register_tree(&mut self, value: &Value) {
+        let mut seen: HashSet<usize> = HashSet::new();
+        let mut pending = vec![value.clone()];
+        while let Some(value) = pending.pop() {
+            let children: Vec<Value> = match &value {
+                Value::LoxInstance(instance) if seen.insert(Rc::as_ptr(instance) as *const () as usize) => {
+                    instance.borrow().field_values()
+                }
+                Value::Array(array) if seen.insert(Rc::as_ptr(array) as *const () as usize) => array.borrow().clone(),
+                Value::Map(map) if seen.insert(Rc::as_ptr(map) as *const () as usize) => map.borrow().values(),
+                _ => continue,
+            };
+            self.register_value(&value);
+            pending.extend(children);
+        }
+    }
+
+    /// A new, registered array of `elements`.
+    pub(crate) fn array(&mut self, elements: Vec<Value>) -> Value {
+        let array = Value::Array(Rc::new(RefCell::new(elements)));
+        self.register_value(&array);
+        array
+    }
+
+    /// A new, registered map of `map`'s entries.
+    pub(crate) fn map(&mut self, map: LoxMap) -> Value {
+        let map = Value::Map(Rc::new(RefCell::new(map)));
+        self.register_value(&map);
+        map
+    }
+
+    /// `instance`, shared and registered.
+    pub(crate) fn instance(&mut self, instance: LoxInstance) -> Rc<RefCell<LoxInstance>> {
+        let instance = Rc::new(RefCell::new(instance));
+        self.track(WeakRef::Instance(Rc::downgrade(&instance)));
+        instance
+    }
+
+    fn track(&mut self, object: WeakRef) {
+        self.objects.push(object);
+
+        // every block and call registers an environment, so drop the dead entries now and then
+        // to keep a long-running loop from growing the registry without bound
+        if self.objects.len() >= self.next_prune {
+            self.objects.retain(WeakRef::is_alive);
+            self.next_prune = (self.objects.len() * 2).max(1024);
+        }
+    }
+
+    /// The number of registered environments, instances, arrays and maps that are still alive.
+    pub fn live_count(&self) -> usize {
+        self.objects.iter().filter(|object| object.is_alive()).count()
+    }
+
+    /// Free every object that is only kept alive by cycles. Returns how many were freed.
+    pub fn collect(&mut self) -> usize {
+        // 1. discover the graph, starting from the registered objects
+        let mut nodes: HashMap<usize, GcRef> = HashMap::new();
+        let mut pending: Vec<GcRef> = self.objects.iter().filter_map(WeakRef::upgrade).collect();
+        while let Some(node) = pending.pop() {
+            let address = node.address();
+            if nodes.contains_key(&address) {
+                continue;
+            }
+            node.trace(&mut |child| {
+                if !nodes.contains_key(&child.address()) {
+                    pending.push(child);
+                }
+            });
+            nodes.insert(address, node);
+        }
+
+        // 2. count the references each node gets from outside the graph. The `nodes` map holds
+        // one reference of its own to every node, which is not part of the program's graph
+        let mut external: HashMap<usize, usize> = nodes
+            .iter()
+            .map(|(address, node)| (*address, node.strong_count() - 1))
+            .collect();
+        for node in nodes.values() {
+            node.trace(&mut |child| {
+                if let Some(count) = external.get_mut(&child.address()) {
+                    *count -= 1;
+                }
+            });
+        }
+
+        // 3. mark everything reachable from a node that is referenced from outside
+        let mut marked: HashSet<usize> = HashSet::new();
+        let mut pending: Vec<usize> = external
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(address, _)| *address)
+            .collect();
+        while let Some(address) = pending.pop() {
+            if !marked.insert(address) {
+                continue;
+            }
+            nodes[&address].trace(&mut |child| pending.push(child.address()));
+        }
+
+        // 4. sweep: clear the unreachable objects to break their cycles. A callable holds nothing
+        // of its own to clear; it goes once what it refers to has been cleared
+        let mut freed = 0;
+        for (address, node) in &nodes {
+            if !marked.contains(address) && Self::clear(node) {
+                freed += 1;
+            }
+        }
+        freed
+    }
+
+    /// Clear every registered object, reachable or not. Only safe once nothing will run again.
+    pub fn release_all(&mut self) {
+        for object in self.objects.drain(..) {
+            if let Some(object) = object.upgrade() {
+                Self::clear(&object);
+            }
+        }
+    }
+
+    // empty `node` of the references it holds, if it is an object that holds any; returns whether it was
+    fn clear(node: &GcRef) -> bool {
+        // move the contents out before dropping them: dropping a value can drop other objects,
+        // and none of them may still be borrowed when that happens
+        match node {
+            GcRef::Environment(env) => {
+                let (values, enclosing) = {
+                    let mut env = env.borrow_mut();
+                    (std::mem::take(&mut env.values), env.enclosing.take())
+                };
+                drop(values);
+                drop(enclosing);
+            }
+            GcRef::Instance(instance) => {
+                let fields = instance.borrow_mut().take_fields();
+                drop(fields);
+            }
+            GcRef::Array(array) => {
+                let elements = std::mem::take(&mut *array.borrow_mut());
+                drop(elements);
+            }
+            GcRef::Map(map) => {
+                let entries = std::mem::take(&mut *map.borrow_mut());
+                drop(entries);
+            }
+            GcRef::Callable(_) => return false,
+        }
+        true
+    }
+}
+
+#[derive(Debug)]
+pub struct CollectGarbageFn;
+
+impl LoxCallable for CollectGarbageFn {
+    fn arity(&self) -> usize { 0 }
+
+    fn name(&self) -> &str { "collectGarbage" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        _arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let freed = interpreter.heap.collect();
+        Ok(Value::Int(freed as i64))
+    }
+}
+
+impl fmt::Display for CollectGarbageFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+#[derive(Debug)]
+pub struct GcStatsFn;
+
+impl LoxCallable for GcStatsFn {
+    fn arity(&self) -> usize { 0 }
+
+    fn name(&self) -> &str { "gcStats" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        _arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        Ok(Value::Int(interpreter.heap.live_count() as i64))
+    }
+}
+
+impl fmt::Display for GcStatsFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
@@ -0,0 +1,2 @@
+pub mod gc;
+pub use gc::*;
\ No newline at end of file
@@ -1,11 +1,29 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use crate::evaluator::{Evaluator};
-use crate::{runtime_error, ClockFn, Environment, Expr, Resolver, RuntimeError, Stmt, Token, Value};
+use crate::resolver::scan_assignments;
+use crate::{ContainsFn, KeysFn, SortFn, ValuesFn, ClassNameFn, FieldsFn, GlobalsFn, HasFieldFn, RemoveFieldFn, StrFn, ClockFn, CloneFn, CollectGarbageFn, FreezeFn, EPrintFn, ExitFn, FormatFn, FormatNumberFn, FromJsonFn, LenFn, MathFn, MathOp, RandomFn, RandomIntFn, RandomSeedFn, SleepMsFn, ToJsonFn, TypeFn, Diagnostics, Environment, ExprId, Limits, GcStatsFn, Literal, Namespace, Parser, Resolver, RuntimeError, Scanner, Stmt, Token, Value};
 pub struct Interpreter {
-    globals: Environment,
-    env:     Environment,   // current (can start equal to globals)
-    locals: HashMap<Expr, usize>,
+    globals: Rc<RefCell<Environment>>,
+    // one evaluator for the interpreter's whole life, so the environments it tracks survive between runs
+    evaluator: Evaluator,
+    // resolve and runtime errors from the current run
+    pub(crate) diagnostics: Diagnostics,
+    // whether the natives that reach outside the interpreter were left out, see `sandboxed`
+    sandboxed: bool,
+    // every file imported so far, and the script itself, so that each runs only once, see `import`
+    imported: HashSet<PathBuf>,
+    // the file whose statements are running, which relative import paths start from
+    current_file: Option<PathBuf>,
+    // every file imported with `as` so far, and the namespace it made
+    namespaces: HashMap<PathBuf, Namespace>,
+    // every name assigned to in the code resolved so far, and whether any of it imports a file,
+    // which rule out knowing a class statically, see `resolver::scan_assignments`
+    pub(crate) assigned_names: HashSet<String>,
+    pub(crate) may_import: bool,
 }
 
 /*
@@ -18,58 +36,570 @@ will never have a type error. The more type checks you defer to runtime, the mor
 confidence erodes.
 */
 
+/*
+The classes every program starts out with, written in Lox itself, like any other class would be.
+There is one so far:
+
+`Error` is what runtime errors look like to a program that handles them: an instance with the
+error's `message` and the `line` it was reported at, made by `Evaluator::error_value`. A program
+can make its own with `Error("what went wrong")`, whose line is nil until it is raised, and
+subclass it for errors of its own kinds. Printing one shows its message.
+*/
+const PRELUDE: &str = "
+class Error {
+  init(message) {
+    this.message = message;
+    this.line = nil;
+  }
+
+  toString() {
+    return \"\" + this.message;
+  }
+}
+";
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interpreter {
     pub fn new() -> Self {
+        Self::with_output(Box::new(std::io::stdout()))
+    }
+
+    /// Create an interpreter whose `print` statements write to `output` instead of stdout,
+    /// e.g. a buffer an embedder reads the program's output back from.
+    pub fn with_output(output: Box<dyn std::io::Write>) -> Self {
+        Self::build(output, false)
+    }
+
+    /*
+    An interpreter for scripts that can't be trusted. It leaves out every native that reaches
+    outside the interpreter: `eprint`, which writes to the host's stderr, and `sleepMs`, which
+    holds up the host's thread. What is left only computes, like `len`, `clock` and the math
+    functions. `import` is refused too, since it reads files. A native that isn't there is not
+    defined at all, so a script calling one fails with "Undefined variable" like for any other
+    name, and can't get at it some other way.
+    `print` is a statement rather than a native, and still writes to stdout. Pair this with
+    `set_limits` to also bound how long a script may run.
+    */
+    pub fn sandboxed() -> Self {
+        Self::build(Box::new(std::io::stdout()), true)
+    }
+
+    /// Whether this interpreter was made by `sandboxed`.
+    pub fn is_sandboxed(&self) -> bool {
+        self.sandboxed
+    }
+
+    fn build(output: Box<dyn std::io::Write>, sandboxed: bool) -> Self {
         let mut globals = Environment::new_global();
 
-        // clock() is available everywhere
+        // clock() is available everywhere, and sleepMs(n) to pause
         globals.define(
             "clock".to_string(),
             Value::Callable(Rc::new(ClockFn)),
         );
+        if !sandboxed {
+            globals.define(
+                "sleepMs".to_string(),
+                Value::Callable(Rc::new(SleepMsFn)),
+            );
+        }
+
+        // len(value) is the length of a string, array or map
+        globals.define(
+            "len".to_string(),
+            Value::Callable(Rc::new(LenFn)),
+        );
+
+        // contains(collection, item) for strings, arrays and maps
+        globals.define(
+            "contains".to_string(),
+            Value::Callable(Rc::new(ContainsFn)),
+        );
+
+        // sort(array) sorts it in place, as array.sort() does, see array.rs
+        globals.define(
+            "sort".to_string(),
+            Value::Callable(Rc::new(SortFn)),
+        );
+
+        // eprint(value) is print, but to stderr
+        if !sandboxed {
+            globals.define(
+                "eprint".to_string(),
+                Value::Callable(Rc::new(EPrintFn)),
+            );
+        }
+
+        // exit(code) stops the program, see ExitFn
+        globals.define(
+            "exit".to_string(),
+            Value::Callable(Rc::new(ExitFn)),
+        );
+
+        // str(value) is value as print would show it
+        globals.define(
+            "str".to_string(),
+            Value::Callable(Rc::new(StrFn)),
+        );
+
+        // format(template, ...) fills in placeholders, see natives.rs
+        globals.define(
+            "format".to_string(),
+            Value::Callable(Rc::new(FormatFn)),
+        );
+
+        // format_number(x, decimals) is x with a fixed number of decimal places
+        globals.define(
+            "format_number".to_string(),
+            Value::Callable(Rc::new(FormatNumberFn)),
+        );
+
+        // to_json(value) and from_json(text), see json.rs
+        globals.define(
+            "to_json".to_string(),
+            Value::Callable(Rc::new(ToJsonFn)),
+        );
+        globals.define(
+            "from_json".to_string(),
+            Value::Callable(Rc::new(FromJsonFn)),
+        );
+
+        // random(), randomInt(lo, hi) and randomSeed(n), see random.rs
+        globals.define(
+            "random".to_string(),
+            Value::Callable(Rc::new(RandomFn)),
+        );
+        globals.define(
+            "randomInt".to_string(),
+            Value::Callable(Rc::new(RandomIntFn)),
+        );
+        globals.define(
+            "randomSeed".to_string(),
+            Value::Callable(Rc::new(RandomSeedFn)),
+        );
+
+        // sqrt, abs, floor, ceil, round, pow, min, max and divInt, see natives.rs
+        for op in MathOp::ALL {
+            globals.define(
+                op.name().to_string(),
+                Value::Callable(Rc::new(MathFn(op))),
+            );
+        }
+
+        // type(value), className(instance) and globals() for introspection
+        globals.define(
+            "type".to_string(),
+            Value::Callable(Rc::new(TypeFn)),
+        );
+        globals.define(
+            "className".to_string(),
+            Value::Callable(Rc::new(ClassNameFn)),
+        );
+        globals.define(
+            "globals".to_string(),
+            Value::Callable(Rc::new(GlobalsFn)),
+        );
+
+        // freeze(instance) makes an instance read-only
+        globals.define(
+            "freeze".to_string(),
+            Value::Callable(Rc::new(FreezeFn)),
+        );
+
+        // clone(value) is a deep copy of the objects value refers to, see class.rs
+        globals.define(
+            "clone".to_string(),
+            Value::Callable(Rc::new(CloneFn)),
+        );
+
+        // hasField(instance, name), removeField(instance, name) and fields(instance), see class.rs
+        globals.define(
+            "hasField".to_string(),
+            Value::Callable(Rc::new(HasFieldFn)),
+        );
+        globals.define(
+            "removeField".to_string(),
+            Value::Callable(Rc::new(RemoveFieldFn)),
+        );
+        globals.define(
+            "fields".to_string(),
+            Value::Callable(Rc::new(FieldsFn)),
+        );
+
+        // keys(map) and values(map), see map.rs
+        globals.define(
+            "keys".to_string(),
+            Value::Callable(Rc::new(KeysFn)),
+        );
+        globals.define(
+            "values".to_string(),
+            Value::Callable(Rc::new(ValuesFn)),
+        );
+
+        // memory introspection for long-running sessions, see gc.rs
+        globals.define(
+            "collectGarbage".to_string(),
+            Value::Callable(Rc::new(CollectGarbageFn)),
+        );
+        globals.define(
+            "gcStats".to_string(),
+            Value::Callable(Rc::new(GcStatsFn)),
+        );
 
         // start with the global env as “current”
-        Self {
-            env: globals.clone(),
+        let globals = Rc::new(RefCell::new(globals));
+        let mut interpreter = Self {
+            evaluator: Evaluator::with_output(Rc::clone(&globals), output),
             globals,
-            locals: HashMap::new(),
+            diagnostics: Diagnostics::new(),
+            sandboxed,
+            imported: HashSet::new(),
+            current_file: None,
+            namespaces: HashMap::new(),
+            assigned_names: HashSet::new(),
+            may_import: false,
+        };
+        interpreter.define_prelude();
+        interpreter
+    }
+
+    // the built-in classes, which are declared in Lox, see `PRELUDE`
+    fn define_prelude(&mut self) {
+        let tokens = Scanner::new(PRELUDE.to_string()).scan_tokens().clone();
+        let statements = Parser::new(tokens).parse();
+        self.resolve_program(&statements);
+        // straight to the visitor rather than through `execute`, so the prelude is not counted as
+        // part of the program in `statements_executed`
+        for statement in &statements {
+            statement.accept(&mut self.evaluator).expect("the prelude runs without errors");
         }
+        if let Ok(Value::LoxClass(class)) = self.globals.borrow().get_at(0, "Error") {
+            self.evaluator.error_class = Some(class);
+        }
+    }
+
+    /// The largest string (in bytes) or collection (in elements) a program may build in one step.
+    pub fn allocation_limit(&self) -> usize {
+        self.evaluator.allocation_limit()
+    }
+
+    pub fn set_allocation_limit(&mut self, limit: usize) {
+        self.evaluator.set_allocation_limit(limit);
+    }
+
+    /// Seed the generator behind `random` and `randomInt`, as `randomSeed(n)` would.
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.evaluator.set_random_seed(seed);
+    }
+
+    /// How many calls may be nested before a program is stopped with a stack overflow error.
+    ///
+    /// Each nested call takes a few KB of Rust stack in a release build and around 20 KB in a
+    /// debug one. Calls grow the stack onto the heap as they need to, so any limit can be reached
+    /// on whatever thread runs the interpreter, a test's 2 MiB one included.
+    pub fn max_call_depth(&self) -> usize {
+        self.evaluator.max_call_depth()
+    }
+
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.evaluator.set_max_call_depth(depth);
+    }
+
+    /// Send what the program writes with `eprint` to `error_output` instead of stderr.
+    pub fn set_error_output(&mut self, error_output: Box<dyn std::io::Write>) {
+        self.evaluator.set_error_output(error_output);
+    }
+
+    pub fn book_messages(&self) -> bool {
+        self.evaluator.book_messages()
+    }
+
+    /// Report operand type errors with the book's exact wording, see `Evaluator::set_book_messages`.
+    pub fn set_book_messages(&mut self, enabled: bool) {
+        self.evaluator.set_book_messages(enabled);
+    }
+
+    pub fn ieee_division(&self) -> bool {
+        self.evaluator.ieee_division()
+    }
+
+    /// Let division by zero produce inf or NaN, see `Evaluator::set_ieee_division`.
+    pub fn set_ieee_division(&mut self, enabled: bool) {
+        self.evaluator.set_ieee_division(enabled);
+    }
+
+    pub fn strict_returns(&self) -> bool {
+        self.evaluator.strict_returns()
+    }
+
+    /// Make functions that fall off their end without a return an error, see `Evaluator::set_strict_returns`.
+    pub fn set_strict_returns(&mut self, enabled: bool) {
+        self.evaluator.set_strict_returns(enabled);
+    }
+
+    pub fn max_loop_iterations(&self) -> Option<usize> {
+        self.evaluator.max_loop_iterations()
     }
 
-    pub fn interpret(&mut self, statements: Vec<Stmt>) {
+    /// Cap how many times one run of a loop may iterate, see `Evaluator::set_max_loop_iterations`.
+    pub fn set_max_loop_iterations(&mut self, limit: Option<usize>) {
+        self.evaluator.set_max_loop_iterations(limit);
+    }
+
+    /// How many statements have been executed over the interpreter's life, every run included.
+    pub fn statements_executed(&self) -> u64 {
+        self.evaluator.statements_executed()
+    }
+
+    /// Create an interpreter that stops any run going over `limits`, see `Limits`.
+    pub fn with_limits(limits: Limits) -> Self {
+        let mut interpreter = Self::new();
+        interpreter.set_limits(limits);
+        interpreter
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.evaluator.limits()
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.evaluator.set_limits(limits);
+    }
+
+    pub fn mark_implicit_return(&mut self, id: ExprId) {
+        self.evaluator.mark_implicit_return(id);
+    }
+
+    /// Hand over the errors reported while resolving and running.
+    pub fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /*
+    Resolve and run `statements`, handing the runtime error that stopped them back to the caller
+    instead of printing it, so an embedder can decide what to do with it. `exit(code)` comes back
+    as `RuntimeError::Exit`. Resolution errors are static, they stop the program before it starts
+    and are reported through `take_diagnostics` like the scanner's and parser's.
+
+    It can be called any number of times, as the REPL does once per line, and each call carries on
+    from where the last one left off: the globals defined so far are still there, and so is what
+    the resolver worked out about the earlier statements. A function declared in one call keeps the
+    scope distances of the variables in its body, which are recorded by expression id and never
+    discarded, so calling it, or a closure it returned, from a later call finds the same variables.
+    */
+    pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<(), RuntimeError> {
+        // a resolution error means the variable bindings cannot be trusted, so run nothing
+        if self.resolve_program(&statements) {
+            self.execute_program(statements)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resolve every variable in `statements`, reporting any errors. Returns whether it is safe
+    /// to go on and execute them. `interpret` does both; they are separate so each can be timed.
+    pub fn resolve_program(&mut self, statements: &[Stmt]) -> bool {
+        // only errors in these statements count; an earlier run's that nobody took don't stop them
+        let mut diagnostics = self.take_diagnostics();
+        // still naming the imported file being resolved, if it is one
+        self.diagnostics.set_file(diagnostics.file().map(str::to_string));
+        // earlier runs count too: a REPL line can call a function an earlier one declared
+        self.may_import |= scan_assignments(statements, &mut self.assigned_names);
         let mut resolver = Resolver::new(self); // Pass `self` as a mutable reference
-        resolver.resolve_stmt(&statements); // resolve the statements (loop internally)
+        resolver.resolve_stmt(statements); // resolve the statements (loop internally)
+        let resolved = !self.diagnostics.had_error();
+        diagnostics.extend(self.take_diagnostics());
+        self.diagnostics = diagnostics;
+        resolved
+    }
 
-        let mut evaluator = Evaluator::new(self.env.clone());
+    /// Execute statements that have already been resolved, stopping at the first runtime error.
+    pub fn execute_program(&mut self, statements: Vec<Stmt>) -> Result<(), RuntimeError> {
+        // every run gets the whole budget, whatever the last one used
+        self.evaluator.start_budget();
+        self.execute_top_level(statements)
+    }
 
+    // the statements of a script, or of a file it imports, which are the only ones that can import
+    fn execute_top_level(&mut self, statements: Vec<Stmt>) -> Result<(), RuntimeError> {
         // Execute each statement
         for stmt in statements {
-            if let Err(err) = evaluator.execute(&stmt) {
-                runtime_error(err);
-                break;
+            let result = match &stmt {
+                Stmt::Import { keyword, path, alias } => self.import(keyword, path, alias.as_ref()),
+                _ => self.evaluator.execute(&stmt),
+            };
+            match result {
+                Ok(()) => {}
+                // `return;` outside any function ends the script early, as if it had run to the end
+                Err(RuntimeError::Return(_)) => break,
+                Err(err) => return Err(err),
             }
         }
+        Ok(())
+    }
+
 
-        // Keep `self.env` in sync in case the program created globals
-        self.env = evaluator.environment;
+    /// Hand the program its command-line arguments, as an array of strings in the global `args`.
+    /// A script run by `jlox` gets whatever followed its path; the REPL gets an empty array.
+    pub fn set_args(&mut self, args: &[String]) {
+        let args = Rc::new(RefCell::new(args.iter().map(|arg| Value::String(arg.as_str().into())).collect()));
+        let array = Value::Array(Rc::clone(&args));
+        self.evaluator.heap.register_value(&array);
+        self.globals.borrow_mut().define("args".to_string(), array);
+        self.evaluator.script_args = Some(args);
     }
 
+    /// Tell the interpreter which file the program comes from, so that its imports are looked up
+    /// next to it, and so that a file importing it back doesn't run it a second time. Without
+    /// this, as in the REPL, relative imports start from the current directory.
+    pub fn set_script_path(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.imported.insert(path.clone());
+        self.current_file = Some(path);
+    }
 
-    pub fn resolve(&mut self, expr: &Expr, depth: usize) {
-        // This will store how deep each variable is in the environment
-        // The depth here represents how many scopes away the variable is from the current one
-        self.locals.insert(expr.clone(), depth);
+    /*
+    `import "lib.lox";` runs another file, at the top level, so everything it declares ends up in
+    the globals, where the rest of the importing file can use it. It is scanned, parsed, resolved
+    and run like a script of its own, in the same interpreter, at the point where the `import` is.
+    A relative path is taken from the directory of the file doing the importing.
+
+    Each file runs once at most. Importing one that has already been imported, or is being imported
+    right now further up, does nothing, which is what stops two files that import each other from
+    going round forever. A `return` at the top level of an imported file ends that file only.
+
+    The file not being readable is a runtime error at the path. Syntax and resolution errors in it
+    are reported as usual, with the lines of the imported file, and then stop the importing program
+    with an error at the path too. A file that failed to import can be imported again, e.g. in the
+    REPL once it has been fixed.
+
+    `import "lib.lox" as lib;` runs the file in a scope of its own instead, see `import_namespace`.
+    */
+    fn import(&mut self, keyword: &Token, path_token: &Token, alias: Option<&Token>) -> Result<(), RuntimeError> {
+        if self.sandboxed {
+            return Err(RuntimeError::new(
+                keyword.clone(),
+                "Can't import files in a sandboxed interpreter.".to_string(),
+            ));
+        }
+        let Literal::String(relative) = &path_token.literal else {
+            unreachable!() // the parser only accepts a string token as the path
+        };
+        let path = match self.current_file.as_ref().and_then(|file| file.parent()) {
+            Some(directory) => directory.join(relative),
+            None => PathBuf::from(relative),
+        };
+        let source = fs::read_to_string(&path).map_err(|err| {
+            RuntimeError::new(path_token.clone(), format!("Can't import '{}': {}.", relative, err))
+        })?;
+        let path = path.canonicalize().unwrap_or(path);
+        if let Some(alias) = alias {
+            return self.import_namespace(&source, path, relative, path_token, alias);
+        }
+        if !self.imported.insert(path.clone()) {
+            return Ok(());
+        }
+
+        let enclosing_file = self.current_file.replace(path.clone());
+        let result = self.run_import(&source, relative, path_token);
+        self.current_file = enclosing_file;
+        if result.is_err() {
+            self.imported.remove(&path);
+        }
+        result
     }
 
-    pub fn lookup_variable(&mut self, name: Token, expr: Expr) -> Result<Value, RuntimeError> {
-        // Check if the variable is local by looking it up in the `locals` map
-        if let Some(&distance) = self.locals.get(&expr) {
-            // If found in the locals, use `get_at` to access it from the correct environment
-            return self.env.get_at(distance, &name.lexeme);
+    /*
+    A module imported with `as` gets a global scope of its own, enclosed by the importer's, so it
+    can still use the natives and whatever the importer had declared by then, but what it declares
+    stays in its scope, which becomes the namespace bound to `alias` (see namespace.rs). Its
+    functions keep that scope as their globals wherever they are called from.
+
+    Each file is run as a module once. Importing it with `as` again, under any name, binds the same
+    namespace, and a module imported again while it is still running, by a module it imports, gets
+    the namespace as far as it has been filled in. A module keeps its own record of the files it
+    imports without `as`, so those run into its scope even if the importer has run them already.
+    */
+    fn import_namespace(
+        &mut self,
+        source: &str,
+        path: PathBuf,
+        relative: &str,
+        path_token: &Token,
+        alias: &Token,
+    ) -> Result<(), RuntimeError> {
+        let namespace = match self.namespaces.get(&path) {
+            Some(namespace) => namespace.clone(),
+            None => {
+                let scope = Rc::new(RefCell::new(Environment::new_enclosed(Rc::clone(&self.evaluator.globals))));
+                self.evaluator.heap.register(&scope);
+                let namespace = Namespace::new(relative, Rc::clone(&scope));
+                self.namespaces.insert(path.clone(), namespace.clone());
+
+                let enclosing_globals = std::mem::replace(&mut self.evaluator.globals, Rc::clone(&scope));
+                let enclosing_environment = std::mem::replace(&mut self.evaluator.environment, scope);
+                let enclosing_imports = std::mem::replace(&mut self.imported, HashSet::from([path.clone()]));
+                let enclosing_file = self.current_file.replace(path.clone());
+                let result = self.run_import(source, relative, path_token);
+                self.evaluator.globals = enclosing_globals;
+                self.evaluator.environment = enclosing_environment;
+                self.imported = enclosing_imports;
+                self.current_file = enclosing_file;
+
+                if let Err(err) = result {
+                    self.namespaces.remove(&path);
+                    return Err(err);
+                }
+                namespace
+            }
+        };
+        self.evaluator.environment.borrow_mut().declare(alias, Value::Namespace(namespace), false)
+    }
+
+    // the errors and warnings found in an imported file name it, as their lines are the file's own
+    fn run_import(&mut self, source: &str, relative: &str, path_token: &Token) -> Result<(), RuntimeError> {
+        let mut scanner = Scanner::new(source.to_string());
+        scanner.set_file(relative);
+        let tokens = scanner.scan_tokens().clone();
+        let mut parser = Parser::new(tokens);
+        parser.set_file(relative);
+        let statements = parser.parse();
+        let (scan_diagnostics, parse_diagnostics) = (scanner.take_diagnostics(), parser.take_diagnostics());
+        let had_syntax_error = scan_diagnostics.had_error() || parse_diagnostics.had_error();
+        self.diagnostics.extend(scan_diagnostics);
+        self.diagnostics.extend(parse_diagnostics);
+        let enclosing_file = self.diagnostics.set_file(Some(relative.to_string()));
+        let resolved = !had_syntax_error && self.resolve_program(&statements);
+        self.diagnostics.set_file(enclosing_file);
+        if !resolved {
+            return Err(RuntimeError::new(
+                path_token.clone(),
+                format!("Can't import '{}': it has errors.", relative),
+            ));
         }
+        self.execute_top_level(statements)
+    }
+
+    /// `Evaluator::error_value`: the `Error` instance a runtime error stands for, e.g. one that
+    /// `interpret` handed back, for an embedder that passes errors on to Lox code.
+    pub fn error_value(&self, err: &RuntimeError) -> Option<Value> {
+        self.evaluator.error_value(err)
+    }
+
+    pub fn resolve(&mut self, id: ExprId, depth: usize) {
+        // This will store how deep each variable is in the environment
+        // The depth here represents how many scopes away the variable is from the current one.
+        // The evaluator owns the map, since it is the one that reads it
+        self.evaluator.resolve(id, depth);
+    }
 
-        // If not found locally, look for it in the global environment
-        self.globals.get(&name)
+    pub fn lookup_variable(&mut self, name: &Token, id: ExprId) -> Result<Value, RuntimeError> {
+        self.evaluator.look_up_variable(name, id)
     }
 
 }
@@ -0,0 +1,418 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use crate::lexer::Literal;
+use crate::{stringify_number, Evaluator, LoxCallable, LoxMap, RuntimeError, Value};
+
+/*
+`to_json(value)` writes a value out as JSON text, and `from_json(text)` reads JSON text back into
+values, so that a Lox program can exchange data with the rest of the world.
+
+The two sides map onto each other directly:
+
+    JSON        Lox
+    number      number
+    string      string
+    true/false  true/false
+    null        nil
+    array       array
+    object      map, with string keys, in the order the object lists them
+
+`to_json` writes compact JSON, without any spaces, and refuses anything that has no JSON
+equivalent rather than writing something that would not read back as the same value: functions,
+classes and instances, NaN and the infinities, a map with a key that is not a string, and an array
+or map that contains itself. Its output is held to the interpreter's allocation limit as it is
+written, since an array holding the same array twice, a hundred levels deep, is small in memory but
+enormous written out.
+
+`from_json` accepts exactly the JSON grammar (RFC 8259), whitespace included, and nothing more:
+no comments, no trailing commas, no single quotes. An object with a repeated key keeps the first
+position and the last value, the same as a map literal. Nesting is limited to `MAX_DEPTH` levels,
+as each level is a nested Rust call.
+*/
+
+const MAX_DEPTH: usize = 512;
+
+pub fn to_json(value: &Value, limit: usize) -> Result<String, String> {
+    let mut writer = JsonWriter { out: String::new(), open: Vec::new(), limit };
+    writer.write(value)?;
+    Ok(writer.out)
+}
+
+struct JsonWriter {
+    out: String,
+    // the arrays and maps being written, to catch one that contains itself
+    open: Vec<*const ()>,
+    limit: usize,
+}
+
+impl JsonWriter {
+    fn write(&mut self, value: &Value) -> Result<(), String> {
+        match value {
+            Value::Nil => self.out.push_str("null"),
+            Value::Bool(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            Value::Int(n) => self.out.push_str(&n.to_string()),
+            Value::Float(n) if n.is_finite() => self.out.push_str(&stringify_number(*n)),
+            Value::Float(n) => {
+                return Err(format!("Can't convert {} to JSON.", stringify_number(*n)));
+            }
+            Value::String(s) => self.write_string(s),
+            Value::Array(array) => {
+                self.enter(Rc::as_ptr(array) as *const ())?;
+                self.out.push('[');
+                for (i, element) in array.borrow().iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.write(element)?;
+                }
+                self.out.push(']');
+                self.open.pop();
+            }
+            Value::Map(map) => {
+                self.enter(Rc::as_ptr(map) as *const ())?;
+                self.out.push('{');
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    let Literal::String(key) = key else {
+                        return Err("JSON object keys must be strings.".to_string());
+                    };
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.write_string(key);
+                    self.out.push(':');
+                    self.write(value)?;
+                }
+                self.out.push('}');
+                self.open.pop();
+            }
+            other => return Err(format!("Can't convert {} to JSON.", other.type_name())),
+        }
+        if self.out.len() > self.limit {
+            return Err("Allocation limit exceeded.".to_string());
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, address: *const ()) -> Result<(), String> {
+        if self.open.contains(&address) {
+            return Err("Can't convert a collection that contains itself to JSON.".to_string());
+        }
+        self.open.push(address);
+        Ok(())
+    }
+
+    // `"`, `\` and the control characters are escaped; everything else is written as it is
+    fn write_string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                '\u{8}' => self.out.push_str("\\b"),
+                '\u{c}' => self.out.push_str("\\f"),
+                c if (c as u32) < 0x20 => self.out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+}
+
+/// Parse JSON text into a value. An error says what was wrong and at which character.
+pub fn from_json(text: &str) -> Result<Value, String> {
+    let mut parser = JsonParser { chars: text.chars().collect(), current: 0 };
+    parser.skip_whitespace();
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.current < parser.chars.len() {
+        return Err(parser.error("unexpected text after the value"));
+    }
+    Ok(value)
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    current: usize,
+}
+
+impl JsonParser {
+    fn error(&self, message: &str) -> String {
+        format!("Invalid JSON at character {}: {}.", self.current, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.current).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.current += 1;
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.current += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.peek() == Some(expected) {
+            self.current += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth >= MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        match self.peek() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"') => Ok(Value::String(self.string()?.into())),
+            Some('-' | '0'..='9') => self.number(),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('n') => self.keyword("null", Value::Nil),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error("expected a value"));
+            }
+            self.current += 1;
+        }
+        Ok(value)
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut map = LoxMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.current += 1;
+            return Ok(Value::Map(Rc::new(RefCell::new(map))));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            let value = self.value(depth + 1)?;
+            map.insert(Literal::String(key), value);
+            self.skip_whitespace();
+            match self.advance() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => {
+                    self.current -= 1;
+                    return Err(self.error("expected ',' or '}'"));
+                }
+            }
+        }
+        Ok(Value::Map(Rc::new(RefCell::new(map))))
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.current += 1;
+            return Ok(Value::Array(Rc::new(RefCell::new(elements))));
+        }
+        loop {
+            self.skip_whitespace();
+            elements.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.advance() {
+                Some(',') => continue,
+                Some(']') => break,
+                _ => {
+                    self.current -= 1;
+                    return Err(self.error("expected ',' or ']'"));
+                }
+            }
+        }
+        Ok(Value::Array(Rc::new(RefCell::new(elements))))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.advance() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('/') => s.push('/'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('u') => s.push(self.unicode_escape()?),
+                    _ => {
+                        self.current -= 1;
+                        return Err(self.error("invalid escape"));
+                    }
+                },
+                Some(c) if (c as u32) < 0x20 => {
+                    self.current -= 1;
+                    return Err(self.error("control character in string"));
+                }
+                Some(c) => s.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    // the character of a `\uXXXX` escape, whose `\u` has been read. A character outside the Basic
+    // Multilingual Plane is written as two escapes, a surrogate pair, which we put back together
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let first = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&first) {
+            if self.advance() != Some('\\') || self.advance() != Some('u') {
+                return Err(self.error("unpaired surrogate"));
+            }
+            let second = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&second) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+        } else {
+            first
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.peek().and_then(|c| c.to_digit(16));
+            let Some(digit) = digit else {
+                return Err(self.error("expected four hex digits"));
+            };
+            code = code * 16 + digit;
+            self.current += 1;
+        }
+        Ok(code)
+    }
+
+    // -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.current;
+        if self.peek() == Some('-') {
+            self.current += 1;
+        }
+        match self.peek() {
+            Some('0') => self.current += 1,
+            Some('1'..='9') => self.digits(),
+            _ => return Err(self.error("expected a digit")),
+        }
+        if self.peek() == Some('.') {
+            self.current += 1;
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("expected a digit"));
+            }
+            self.digits();
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.current += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.current += 1;
+            }
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("expected a digit"));
+            }
+            self.digits();
+        }
+        let text: String = self.chars[start..self.current].iter().collect();
+        // a number without a fraction or exponent is an int, like a Lox literal, if it fits
+        if let Ok(n) = text.parse::<i64>() {
+            return Ok(Value::Int(n));
+        }
+        // the grammar above is a subset of what Rust parses, so this cannot fail
+        Ok(Value::Float(text.parse().unwrap_or(f64::NAN)))
+    }
+
+    fn digits(&mut self) {
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.current += 1;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ToJsonFn;
+
+impl LoxCallable for ToJsonFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "to_json" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        to_json(&arguments[0], interpreter.allocation_limit())
+            .map(|json| Value::String(json.into()))
+            .map_err(|message| interpreter.native_error(message))
+    }
+}
+
+impl fmt::Display for ToJsonFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+#[derive(Debug)]
+pub struct FromJsonFn;
+
+impl LoxCallable for FromJsonFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "from_json" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match &arguments[0] {
+            Value::String(text) => {
+                let value = from_json(text).map_err(|message| interpreter.native_error(message))?;
+                interpreter.heap.register_tree(&value);
+                Ok(value)
+            }
+            other => Err(interpreter.native_error(format!(
+                "from_json() expects a string, got {}.",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for FromJsonFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
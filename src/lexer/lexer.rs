@@ -3,24 +3,36 @@ use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
 use std::vec::Vec;
-use crate::utils::{error};
+use crate::utils::{error, Diagnostics, Severity};
 
 pub static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     let mut m = HashMap::new();
     m.insert("and", TokenType::And);
+    m.insert("as", TokenType::As);
+    m.insert("break", TokenType::Break);
+    m.insert("catch", TokenType::Catch);
     m.insert("class", TokenType::Class);
+    m.insert("const", TokenType::Const);
+    m.insert("continue", TokenType::Continue);
+    m.insert("do", TokenType::Do);
     m.insert("else", TokenType::Else);
     m.insert("false", TokenType::False);
     m.insert("for", TokenType::For);
     m.insert("fun", TokenType::Fun);
     m.insert("if", TokenType::If);
+    m.insert("import", TokenType::Import);
+    m.insert("in", TokenType::In);
+    m.insert("is", TokenType::Is);
     m.insert("nil", TokenType::Nil);
     m.insert("or", TokenType::Or);
     m.insert("print", TokenType::Print);
+    m.insert("repeat", TokenType::Repeat);
     m.insert("return", TokenType::Return);
     m.insert("super", TokenType::Super);
     m.insert("this", TokenType::This);
+    m.insert("throw", TokenType::Throw);
     m.insert("true", TokenType::True);
+    m.insert("try", TokenType::Try);
     m.insert("var", TokenType::Var);
     m.insert("while", TokenType::While);
     m
@@ -42,14 +54,23 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
     Minus,
+    Arrow,
     Plus,
     SemiColon,
     Slash,
     Star,
 
+    // two character tokens
+    QuestionQuestion,
+    QuestionDot,
+    DotDot,
+
     // one or two character tokens
     Bang,
     BangEqual,
@@ -71,28 +92,48 @@ pub enum TokenType {
 
     // keywords
     And,
+    As,
+    Break,
+    Catch,
     Class,
+    Const,
+    Continue,
+    Do,
     Else,
     False,
     Fun,
     For,
     If,
+    Import,
+    In,
+    Is,
     Nil,
     Or,
     Print,
+    Repeat,
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
 
     Eof,
 }
 
+// in the book's spelling, `LEFT_PAREN` for `LeftParen`
 impl fmt::Display for TokenType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        let name = format!("{:?}", self);
+        for (i, ch) in name.chars().enumerate() {
+            if i > 0 && ch.is_ascii_uppercase() {
+                write!(f, "_")?;
+            }
+            write!(f, "{}", ch.to_ascii_uppercase())?;
+        }
+        Ok(())
     }
 }
 
@@ -121,22 +162,87 @@ impl Token {
             line,
         }
     }
+
+    /// Whether the two tokens are the same apart from where they are: the same type, lexeme and
+    /// literal, on any line. `==` also compares the line, as tokens are used to tell apart the
+    /// declarations they come from.
+    pub fn same_as(&self, other: &Token) -> bool {
+        self.token_type == other.token_type && self.lexeme == other.lexeme && self.literal == other.literal
+    }
 }
 
+/// `Token::same_as` for two lists of tokens, or of optional ones, such as parameter types.
+pub(crate) fn same_tokens<'a>(
+    a: impl ExactSizeIterator<Item = Option<&'a Token>>,
+    b: impl ExactSizeIterator<Item = Option<&'a Token>>,
+) -> bool {
+    a.len() == b.len()
+        && a.zip(b).all(|pair| match pair {
+            (Some(a), Some(b)) => a.same_as(b),
+            (None, None) => true,
+            _ => false,
+        })
+}
+
+/*
+A token prints the way the book's scanner prints it, type, lexeme and literal, with the line it is
+on appended, one token to a line for `jlox --tokens`:
+
+    VAR var null [line 1]
+    IDENTIFIER answer null [line 1]
+    EQUAL = null [line 1]
+    NUMBER 42 42 [line 1]
+*/
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{} {} {:?}", self.token_type, self.lexeme, self.literal)
+        write!(f, "{} {} {} [line {}]", self.token_type, self.lexeme, self.literal, self.line)
     }
 }
 
-#[derive(Debug, Clone, PartialEq)] // PartialEq is fine for deriving
+#[derive(Debug, Clone)]
 pub enum Literal {
     String(String),
-    Number(f64),
+    Int(i64),
+    Float(f64),
     Bool(bool),
     Nil,
 }
 
+/*
+Literals are hashed, as the keys of maps, so their equality has to be a proper equivalence that
+agrees with the hash. For floats that is not quite f64's `==`: NaN is equal to itself here, and
+0 and -0, which `==` already calls equal, hash the same. An int is never equal to a float here;
+maps store a whole float under the int with its value (see `map_key`), so `1` and `1.0` still find
+the same entry. This is sameness of keys, not Lox's `==`, which stays IEEE 754 (see
+`Value::equals`); maps refuse NaN as a key before it ever gets here.
+*/
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Literal::String(a), Literal::String(b)) => a == b,
+            (Literal::Int(a), Literal::Int(b)) => a == b,
+            (Literal::Float(a), Literal::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Literal::Bool(a), Literal::Bool(b)) => a == b,
+            (Literal::Nil, Literal::Nil) => true,
+            _ => false,
+        }
+    }
+}
+
+// a string is shown without its quotes, a float always with its point, and a token that has no
+// literal shows `null`, as in Java
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Literal::String(s) => write!(f, "{}", s),
+            Literal::Int(n) => write!(f, "{}", n),
+            Literal::Float(n) => write!(f, "{:?}", n),
+            Literal::Bool(b) => write!(f, "{}", b),
+            Literal::Nil => write!(f, "null"),
+        }
+    }
+}
+
 // Implementing Eq for Literal enum
 impl Eq for Literal {}
 
@@ -145,8 +251,11 @@ impl std::hash::Hash for Literal {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
             Literal::String(s) => s.hash(state),
-            Literal::Number(n) => {
-                // Convert f64 to bits for hashing
+            Literal::Int(n) => n.hash(state),
+            Literal::Float(n) => {
+                // Convert f64 to bits for hashing, with one pattern for every NaN and `+ 0.0`
+                // turning -0 into 0, so that numbers equal above hash alike
+                let n = if n.is_nan() { f64::NAN } else { n + 0.0 };
                 n.to_bits().hash(state)
             },
             Literal::Bool(b) => b.hash(state),
@@ -175,6 +284,9 @@ pub struct Scanner {
     start: usize,   // points to the first position in the lexeme
     current: usize, // points to the current position of the lexeme
     line: usize, // keeps track which source line `current` is on so we can print out the location of the tokens
+    diagnostics: Diagnostics,
+    // whether the source ended inside a string
+    unterminated_string: bool,
 }
 
 impl Scanner {
@@ -185,13 +297,55 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            diagnostics: Diagnostics::new(),
+            unterminated_string: false,
         }
     }
 
+    /*
+    Whether `source` stops in the middle of something: inside a string, or with more `(`, `[` or
+    `{` opened than closed. The REPL keeps reading lines until it doesn't. This goes by the tokens,
+    so a brace in a string or a comment doesn't count. More closers than openers is not unfinished,
+    but a mistake the parser will report. Nothing is reported here, as the source is scanned again
+    when it is run.
+    */
+    pub fn is_unfinished(source: &str) -> bool {
+        let mut scanner = Scanner::new(source.to_string());
+        scanner.diagnostics = Diagnostics::silent();
+        let depth = scanner.scan_tokens().iter().fold(0i64, |depth, token| match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth + 1,
+            TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => depth - 1,
+            _ => depth,
+        });
+        depth > 0 || scanner.unterminated_string
+    }
+
+    /// Name `file` in the errors reported while scanning, for the source of an imported file.
+    pub(crate) fn set_file(&mut self, file: &str) {
+        self.diagnostics.set_file(Some(file.to_string()));
+    }
+
+    /// Hand over the errors reported while scanning.
+    pub fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
+    }
+
     pub fn scan_tokens(&mut self) -> &Vec<Token> {
+        self.scan_tokens_with(|_| {})
+    }
+
+    /*
+    `scan_tokens`, handing every token to `on_token` as soon as it is scanned. An error is reported
+    the moment the scanner runs into it, so whatever `on_token` prints comes out in between the
+    errors, in the order they appear in the source.
+    */
+    pub fn scan_tokens_with(&mut self, mut on_token: impl FnMut(&Token)) -> &Vec<Token> {
+        self.skip_shebang();
         while !self.is_at_end() {
             self.start = self.current;
+            let scanned = self.tokens.len();
             self.scan_token();
+            self.tokens[scanned..].iter().for_each(&mut on_token);
         }
         self.tokens.push(Token::new(
             TokenType::Eof,
@@ -199,9 +353,25 @@ impl Scanner {
             Literal::Nil,
             self.line,
         ));
+        on_token(&self.tokens[self.tokens.len() - 1]);
         &self.tokens
     }
 
+    /*
+    A script made executable on Unix starts with a line like `#!/usr/bin/env jlox`, naming the
+    program to run it with. That line is for the operating system, not for us, so it is skipped
+    the way a comment is. Only a `#!` at the very start of the source counts; anywhere else `#`
+    is still an unexpected character. The newline that ends it is left to be scanned as usual,
+    so line numbers stay right.
+    */
+    fn skip_shebang(&mut self) {
+        if self.current == 0 && self.source.starts_with("#!") {
+            while self.peek() != '\n' && !self.is_at_end() {
+                self.advance();
+            }
+        }
+    }
+
     // to consume input
     fn advance(&mut self) -> char {
         let ch = self.source[self.current..].chars().next().unwrap();
@@ -219,12 +389,20 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
+            ':' => self.add_token(TokenType::Colon),
+            '.' if self.match_char('.') => self.add_token(TokenType::DotDot),
             '.' => self.add_token(TokenType::Dot),
+            '-' if self.match_char('>') => self.add_token(TokenType::Arrow),
             '-' => self.add_token(TokenType::Minus),
             '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::SemiColon),
             '*' => self.add_token(TokenType::Star),
+            // a lone '?' is not a token, so it falls through to the error below
+            '?' if self.match_char('?') => self.add_token(TokenType::QuestionQuestion),
+            '?' if self.match_char('.') => self.add_token(TokenType::QuestionDot),
             '!' => {
                 let token = if self.match_char('=') {
                     TokenType::BangEqual
@@ -286,16 +464,16 @@ impl Scanner {
                     // if an unexpected character is consumed, throw an error
                     // note that the erroneous character is still consumed by `advance()`.
                     // This is important to avoid an infinite loop.
-                    // Since HAD_ERROR will be set to true, we never execute the code,
+                    // Since the diagnostics will record an error, we never execute the code,
                     // but we keep scanning through the source code to catch all the errors at once
-                    error(self.line, "Unexpected character.");
+                    error(&mut self.diagnostics, Severity::Error, self.line, "Unexpected character.");
                 }
             }
         }
     }
 
     fn is_alpha(&self, c: char) -> bool {
-        (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_'
+        c.is_ascii_alphabetic() || c == '_'
     }
 
     fn is_alphanumeric(&self, c: char) -> bool {
@@ -303,7 +481,7 @@ impl Scanner {
     }
 
     fn is_digit(&self, ch: char) -> bool {
-        ch >= '0' && ch <= '9'
+        ch.is_ascii_digit()
     }
 
     fn is_at_end(&self) -> bool {
@@ -311,10 +489,10 @@ impl Scanner {
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            return '\0';
-        }
-        self.source[self.current..].chars().next().unwrap()
+        // skip the character under `current` and look at the one after it
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
 
     fn peek(&self) -> char {
@@ -327,18 +505,18 @@ impl Scanner {
 
     fn match_char(&mut self, expected: char) -> bool {
         if self.is_at_end() {
-            false;
+            return false;
         }
 
         let next_char = self.source[self.current..].chars().next().unwrap();
         if next_char != expected {
-            false;
+            return false;
         }
         self.current += next_char.len_utf8();
         true
     }
 
-    fn string(&mut self) -> () {
+    fn string(&mut self) {
         while self.peek() != '"' && !self.is_at_end() {
             if self.peek() == '\n' {
                 self.line += 1;
@@ -347,7 +525,8 @@ impl Scanner {
         }
 
         if self.is_at_end() {
-            error(self.line, "Unterminated string.");
+            error(&mut self.diagnostics, Severity::Error, self.line, "Unterminated string.");
+            self.unterminated_string = true;
             return;
         }
 
@@ -357,7 +536,7 @@ impl Scanner {
         self.add_token_with_literal(TokenType::String, Literal::String(value.to_string()));
     }
 
-    fn number(&mut self) -> () {
+    fn number(&mut self) {
         while self.is_digit(self.peek()) {
             self.advance();
         }
@@ -369,9 +548,13 @@ impl Scanner {
             }
         }
 
+        // digits alone make an int, unless there are too many of them to fit in one
         let text = &self.source[self.start..self.current];
-        let value: f64 = text.parse().unwrap();
-        self.add_token_with_literal(TokenType::Number, Literal::Number(value));
+        let literal = match text.parse::<i64>() {
+            Ok(value) => Literal::Int(value),
+            Err(_) => Literal::Float(text.parse().unwrap()),
+        };
+        self.add_token_with_literal(TokenType::Number, literal);
     }
 
     fn identifier(&mut self) {
@@ -385,11 +568,11 @@ impl Scanner {
     }
 
     // to produce output
-    fn add_token(&mut self, token_type: TokenType) -> () {
+    fn add_token(&mut self, token_type: TokenType) {
         self.add_token_with_literal(token_type, Literal::Nil);
     }
 
-    fn add_token_with_literal(&mut self, token_type: TokenType, literal: Literal) -> () {
+    fn add_token_with_literal(&mut self, token_type: TokenType, literal: Literal) {
         let text = self.source[self.start..self.current].to_string();
         let token = Token::new(token_type, text, literal, self.line);
         self.tokens.push(token);
@@ -1,3 +1,6 @@
+// each module lives in `<name>/mod.rs`, which re-exports the `<name>.rs` beside it
+#![allow(clippy::module_inception)]
+
 pub mod lexer;
 pub mod parser;
 pub mod utils;
@@ -21,4 +24,31 @@ pub mod resolver;
 pub use resolver::*;
 
 pub mod class;
-pub use class::*;
\ No newline at end of file
+pub use class::*;
+
+pub mod gc;
+pub use gc::*;
+
+pub mod ast_printer;
+pub use ast_printer::*;
+
+pub mod natives;
+pub use natives::*;
+
+pub mod random;
+pub use random::*;
+
+pub mod array;
+pub use array::*;
+
+pub mod map;
+pub use map::*;
+
+pub mod namespace;
+pub use namespace::*;
+
+pub mod json;
+pub use json::*;
+
+pub mod free_variables;
+pub use free_variables::*;
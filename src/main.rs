@@ -1,20 +1,61 @@
 use std::env;
-use crafting_interpreters::runner::{run_file, run_prompt};
+use std::thread;
+use crafting_interpreters::runner::{run_file, run_prompt, DumpMode};
+
+/*
+The parser and evaluator walk the tree with ordinary Rust recursion, so deeply nested source, like
+a few thousand parentheses inside each other, uses a deep slice of the Rust stack, a debug build
+far more than a release one. Nested Lox calls grow the stack for themselves (see
+`LoxFunction::call`), but nothing else does, and the process would abort instead of reporting an
+error. So the interpreter runs on a thread of its own with a much larger stack. Only the pages that
+are actually touched are ever committed, so a large reservation costs nothing for a program that
+doesn't nest deeply.
+*/
+const INTERPRETER_STACK_SIZE: usize = 256 * 1024 * 1024;
 
 pub fn main() -> std::io::Result<()> {
-    let args: Vec<String> = env::args().collect();
-    // args always includes the program name in args[0]
-    match args.len() {
-        1 => {
-            run_prompt();
-        }
-        2 => {
-            run_file(&args[1]);
-        }
-        _ => {
-            println!("Usage: jlox [script]");
-            std::process::exit(64);
+    /*
+    args always includes the program name in args[0]. Flags have to come before the script: what
+    follows its path is the script's own arguments, handed to it as they are, so that
+    `jlox --time tool.lox --verbose a b` times `tool.lox` and gives it `["--verbose", "a", "b"]`.
+    */
+    let mut time = false;
+    let mut dump: Option<DumpMode> = None;
+    let mut script: Option<String> = None;
+    let mut args = env::args().skip(1);
+    for arg in args.by_ref() {
+        match arg.as_str() {
+            "--time" => time = true,
+            "--tokens" => dump = Some(DumpMode::Tokens),
+            "--ast" => dump = Some(DumpMode::Ast),
+            _ if arg.starts_with("--") => usage(),
+            _ => {
+                script = Some(arg);
+                break;
+            }
         }
     }
+    let script_args: Vec<String> = args.collect();
+
+    let interpreter = thread::Builder::new()
+        .stack_size(INTERPRETER_STACK_SIZE)
+        .spawn(move || {
+            match script {
+                // the timings and dumps are per script, there is nothing to report for a REPL session
+                None if !time && dump.is_none() => {
+                    run_prompt();
+                }
+                Some(script) => {
+                    run_file(&script, &script_args, time, dump);
+                }
+                None => usage(),
+            }
+        })?;
+    interpreter.join().expect("the interpreter thread panicked");
     Ok(())
 }
+
+fn usage() -> ! {
+    println!("Usage: jlox [--time] [--tokens | --ast] [script [args...]]");
+    std::process::exit(64);
+}
@@ -0,0 +1,274 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use crate::lexer::Literal;
+use crate::{float_to_int, Evaluator, GcRef, LoxCallable, RuntimeError, Value};
+
+/*
+Maps associate keys with values, written `{"a": 1, "b": 2}` (and `{}` for an empty one) and
+looked up with `m[key]`, which gives nil for a key that isn't there. Assigning `m[key] = value`
+adds the key or replaces its value. Like arrays, a map is an object: every reference to it sees
+the same entries. Unlike arrays, two maps are only equal (`==`) when they are the same map, the
+same as instances.
+
+Keys are restricted to the values that can be compared by value: strings, numbers, booleans and
+nil. These are exactly the kinds of literal the scanner produces, so a key is stored as a
+`Literal`, which already knows how to be hashed. An object can't be a key, since two objects that
+are equal today may not be tomorrow. NaN can't be one either, as it is not even equal to itself,
+and -0 is stored as 0, which it is equal to.
+
+A map remembers the order its keys were first inserted in. Printing it, asking for its keys or
+values, and looping over it with `for (key in map)`, always go in that order, so a script behaves
+the same on every run; a `HashMap` alone would give a different order from one run to the next. Replacing the value
+of a key keeps its place; removing a key and adding it back moves it to the end.
+
+The other operations are methods, looked up on the map like the methods of arrays:
+
+    get(key)            the value for key, or nil
+    set(key, value)     add or replace the value for key
+    has(key)            whether key is in the map
+    remove(key)         remove key, returning its value, or nil if it wasn't there
+    keys()              an array of the keys
+    values()            an array of the values
+    size()              the number of entries
+*/
+
+#[derive(Debug, Clone, Default)]
+pub struct LoxMap {
+    // the entries in insertion order
+    entries: Vec<(Literal, Value)>,
+    // where each key's entry sits in `entries`
+    index: HashMap<Literal, usize>,
+}
+
+impl LoxMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &Literal) -> Option<&Value> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &Literal) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn insert(&mut self, key: Literal, value: Value) {
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &Literal) -> Option<Value> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        // everything after the removed entry moved down by one
+        for position in self.index.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    /// The entries, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Literal, &Value)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// The keys, in insertion order, as the values they were made from.
+    pub fn keys(&self) -> Vec<Value> {
+        self.entries.iter().map(|(key, _)| key_value(key)).collect()
+    }
+
+    /// The values, in the order of their keys.
+    pub fn values(&self) -> Vec<Value> {
+        self.entries.iter().map(|(_, value)| value.clone()).collect()
+    }
+}
+
+/// The key a value is stored under, or why it can't be one.
+pub fn map_key(value: &Value) -> Result<Literal, String> {
+    match value {
+        Value::String(s) => Ok(Literal::String(s.to_string())),
+        Value::Int(n) => Ok(Literal::Int(*n)),
+        Value::Float(n) if n.is_nan() => Err("NaN can't be used as a map key.".to_string()),
+        // 1.0 == 1, so a whole float is stored as the int with its value, and -0 == 0 too, so
+        // `+ 0.0` turns -0 into 0 and leaves the rest
+        Value::Float(n) => Ok(float_to_int(*n).map_or(Literal::Float(n + 0.0), Literal::Int)),
+        Value::Bool(b) => Ok(Literal::Bool(*b)),
+        Value::Nil => Ok(Literal::Nil),
+        other => Err(format!(
+            "Map keys must be strings, numbers, booleans or nil, got {}.",
+            other.type_name()
+        )),
+    }
+}
+
+/// A stored key, as the value it was made from.
+pub fn key_value(key: &Literal) -> Value {
+    match key {
+        Literal::String(s) => Value::String(s.as_str().into()),
+        Literal::Int(n) => Value::Int(*n),
+        Literal::Float(n) => Value::Float(*n),
+        Literal::Bool(b) => Value::Bool(*b),
+        Literal::Nil => Value::Nil,
+    }
+}
+
+#[derive(Debug)]
+pub struct MapMethod {
+    map: Rc<RefCell<LoxMap>>,
+    name: &'static str,
+    arity: usize,
+}
+
+impl MapMethod {
+    /// The method called `name`, bound to `map`, if maps have one by that name.
+    pub fn lookup(map: &Rc<RefCell<LoxMap>>, name: &str) -> Option<MapMethod> {
+        let (name, arity) = match name {
+            "get" => ("get", 1),
+            "set" => ("set", 2),
+            "has" => ("has", 1),
+            "remove" => ("remove", 1),
+            "keys" => ("keys", 0),
+            "values" => ("values", 0),
+            "size" => ("size", 0),
+            _ => return None,
+        };
+        Some(MapMethod {
+            map: Rc::clone(map),
+            name,
+            arity,
+        })
+    }
+}
+
+impl LoxCallable for MapMethod {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        // every method but keys, values and size starts with a key
+        let key = match arguments.first() {
+            Some(key) => Some(map_key(key).map_err(|message| interpreter.native_error(message))?),
+            None => None,
+        };
+        let key = || key.clone().expect("the arity check guarantees a key argument");
+
+        match self.name {
+            "get" => Ok(self.map.borrow().get(&key()).cloned().unwrap_or(Value::Nil)),
+            "set" => {
+                let len = self.map.borrow().len();
+                interpreter.check_allocation(&interpreter.call_site(), len.checked_add(1))?;
+                self.map.borrow_mut().insert(key(), arguments[1].clone());
+                Ok(Value::Nil)
+            }
+            "has" => Ok(Value::Bool(self.map.borrow().contains_key(&key()))),
+            "remove" => Ok(self.map.borrow_mut().remove(&key()).unwrap_or(Value::Nil)),
+            "keys" => Ok(interpreter.heap.array(self.map.borrow().keys())),
+            "values" => Ok(interpreter.heap.array(self.map.borrow().values())),
+            "size" => Ok(Value::Int(self.map.borrow().len() as i64)),
+            _ => unreachable!("MapMethod::lookup only makes known methods"),
+        }
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        visit(GcRef::Map(Rc::clone(&self.map)));
+    }
+}
+
+impl fmt::Display for MapMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`keys(m)` and `values(m)` are the `m.keys()` and `m.values()` methods as plain functions, for code
+that reads better passing a map around than calling on it. Each gives a new array, in the map's
+insertion order, so changing the array leaves the map alone. Anything but a map is an error.
+*/
+fn expect_map(interpreter: &Evaluator, native: &str, value: &Value) -> Result<Rc<RefCell<LoxMap>>, RuntimeError> {
+    match value {
+        Value::Map(map) => Ok(Rc::clone(map)),
+        other => Err(interpreter.native_error(format!(
+            "{}() expects a map, got {}.",
+            native,
+            other.type_name()
+        ))),
+    }
+}
+
+#[derive(Debug)]
+pub struct KeysFn;
+
+impl LoxCallable for KeysFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "keys" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let map = expect_map(interpreter, "keys", &arguments[0])?;
+        let keys = map.borrow().keys();
+        Ok(interpreter.heap.array(keys))
+    }
+}
+
+impl fmt::Display for KeysFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+#[derive(Debug)]
+pub struct ValuesFn;
+
+impl LoxCallable for ValuesFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "values" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let map = expect_map(interpreter, "values", &arguments[0])?;
+        let values = map.borrow().values();
+        Ok(interpreter.heap.array(values))
+    }
+}
+
+impl fmt::Display for ValuesFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
@@ -0,0 +1,2 @@
+pub mod namespace;
+pub use namespace::*;
@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use crate::{Environment, GcRef, RuntimeError, Token, Value};
+
+/*
+`import "math.lox" as m;` runs the file in a global scope of its own, and binds `m` to a namespace
+holding whatever the file declared there. `m.add(1, 2)` looks `add` up in that scope, the way
+`point.x` looks up a field, so nothing the module declares ends up in the importer's globals.
+
+The namespace shares the module's scope rather than copying it: the module's functions go on
+reading and writing their globals there, and `m.count` shows the value as it is now. Only what the
+module declared itself is found through the namespace, not the natives or the importer's globals
+that the module can see around it. Bindings can't be assigned from outside, `m.x = 1` is an error.
+
+A namespace is cloned along with the handle to its scope, so, like an environment holding a
+variable, every copy counts as a reference to the scope for the cycle collector.
+*/
+#[derive(Debug, Clone)]
+pub struct Namespace {
+    // the path as the import wrote it, to tell namespaces apart when one is printed
+    path: Rc<str>,
+    bindings: Rc<RefCell<Environment>>,
+}
+
+impl Namespace {
+    pub fn new(path: &str, bindings: Rc<RefCell<Environment>>) -> Self {
+        Self { path: Rc::from(path), bindings }
+    }
+
+    /// The value the module bound to `name`, or an error at `name` if it declared no such thing.
+    pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
+        match self.bindings.borrow().values.get(&name.lexeme) {
+            Some(value) => Ok(value.clone()),
+            None => Err(RuntimeError::new(
+                name.clone(),
+                format!("Module '{}' has no '{}'.", self.path, name.lexeme),
+            )),
+        }
+    }
+
+    /// Whether `self` and `other` are the namespace of the same import.
+    pub fn same_object(&self, other: &Namespace) -> bool {
+        Rc::ptr_eq(&self.bindings, &other.bindings)
+    }
+
+    pub(crate) fn trace(&self, visit: &mut dyn FnMut(GcRef)) {
+        visit(GcRef::Environment(Rc::clone(&self.bindings)));
+    }
+}
+
+impl fmt::Display for Namespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<module {}>", self.path)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod natives;
+pub use natives::*;
@@ -0,0 +1,795 @@
+use std::fmt;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+use crate::{float_to_int, map_key, stringify_number, Arity, Evaluator, Literal, LoxCallable, LoxMap, RuntimeError, Value};
+
+/*
+Native functions are the functions the interpreter provides itself, written in Rust rather
+than in Lox. To a Lox program they look like any other function: they live in the global
+environment, have an arity, and are called the same way. Each one is a unit struct that
+implements `LoxCallable`, and is registered in `Interpreter::new`.
+
+A native gets its arguments already evaluated, and the visit for the call has already checked
+that there are as many as its arity asks for. What it cannot assume is their types. A native that
+is handed something it cannot work with raises a runtime error with `Evaluator::native_error`,
+which reports it at the call site.
+*/
+
+/*
+`len(value)` is the length of a string, counted in Unicode scalar values (Rust `char`s), not in
+bytes: `len("héllo")` is 5, although its UTF-8 encoding takes 6 bytes. Characters are what a
+person counting the letters would expect, and bytes are an encoding detail a Lox program never
+otherwise sees. Note that a scalar value is not always a whole visible character: an accent
+written as a separate combining mark counts on its own.
+
+`len(array)` is the number of elements in the array, and `len(map)` the number of entries.
+*/
+#[derive(Debug)]
+pub struct LenFn;
+
+impl LoxCallable for LenFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "len" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match &arguments[0] {
+            Value::String(s) => Ok(Value::Int(s.chars().count() as i64)),
+            Value::Array(array) => Ok(Value::Int(array.borrow().len() as i64)),
+            Value::Map(map) => Ok(Value::Int(map.borrow().len() as i64)),
+            other => Err(interpreter.native_error(format!(
+                "Object has no length. Got {}.",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for LenFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`contains(collection, item)` asks the question each collection answers most naturally: whether a
+string has `item` as a substring, whether an array has an element equal (`==`) to it, and whether
+a map has it as a key. Only a string can be found in a string. A value that could never be a map
+key, like an array, is simply not in the map.
+*/
+#[derive(Debug)]
+pub struct ContainsFn;
+
+impl LoxCallable for ContainsFn {
+    fn arity(&self) -> usize { 2 }
+
+    fn name(&self) -> &str { "contains" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let item = &arguments[1];
+        match &arguments[0] {
+            Value::String(s) => match item {
+                Value::String(part) => Ok(Value::Bool(s.contains(&**part))),
+                other => Err(interpreter.native_error(format!(
+                    "contains() can only look for a string in a string, got {}.",
+                    other.type_name()
+                ))),
+            },
+            Value::Array(array) => Ok(Value::Bool(array.borrow().iter().any(|element| element.equals(item)))),
+            Value::Map(map) => Ok(Value::Bool(
+                map_key(item).is_ok_and(|key| map.borrow().contains_key(&key)),
+            )),
+            other => Err(interpreter.native_error(format!(
+                "contains() expects a string, array or map, got {}.",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ContainsFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`eprint(value)` writes a value followed by a newline, exactly as the `print` statement would
+format it, but to the interpreter's error sink (stderr by default) rather than to its output. That
+keeps diagnostics and progress messages out of a program's real output, so the latter can still
+be piped somewhere. Being a function rather than a statement, it has to return something: `nil`.
+*/
+#[derive(Debug)]
+pub struct EPrintFn;
+
+impl LoxCallable for EPrintFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "eprint" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let call_site = interpreter.call_site();
+        let text = interpreter.stringify(&arguments[0], &call_site)?;
+        interpreter.write_error_line(&text)?;
+        Ok(Value::Nil)
+    }
+}
+
+impl fmt::Display for EPrintFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+The math natives: `sqrt`, `abs`, `floor`, `ceil`, `round`, `pow`, `min`, `max` and `divInt`. They
+are all thin wrappers around the `f64` methods of the same name, so their results follow IEEE 754
+double precision exactly like Lox's own arithmetic does. `round` rounds half-way cases away from
+zero (`round(2.5)` is 3, `round(-2.5)` is -3), and `min`/`max` of two equal numbers is simply that
+number.
+
+Like the arithmetic operators, they keep ints exact: `abs`, `pow` (with an exponent that is not
+negative), `min` and `max` of ints are ints, as long as the result fits. `floor`, `ceil` and
+`round` always produce whole numbers, so they return ints, unless the float is too big for one.
+
+`divInt(a, b)` is division rounded down, towards negative infinity, the same as `floor(a / b)`:
+`divInt(7, 2)` is 3 and `divInt(-7, 2)` is -4. On two ints it is exact, which `floor(a / b)` is
+not past 2^53, and the result is an int. A zero divisor is an error whatever `ieee_division` says,
+since there is no whole number to give back.
+
+The one place we part ways with `f64` is `sqrt` of a negative number. Rust gives back NaN, which
+then flows silently through every calculation that touches it and shows up far from the cause.
+Asking for the square root of a negative number is almost always a bug, so we raise a runtime
+error at the call instead.
+
+Rather than nine near-identical structs, there is a single `MathFn` that carries which operation
+it performs. Every argument must be a number; anything else is reported with the function's name,
+so that `sqrt("4")` says "sqrt" rather than leaving the user to guess which call failed.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathOp {
+    Sqrt,
+    Abs,
+    Floor,
+    Ceil,
+    Round,
+    Pow,
+    Min,
+    Max,
+    DivInt,
+}
+
+impl MathOp {
+    pub const ALL: [MathOp; 9] = [
+        MathOp::Sqrt,
+        MathOp::Abs,
+        MathOp::Floor,
+        MathOp::Ceil,
+        MathOp::Round,
+        MathOp::Pow,
+        MathOp::Min,
+        MathOp::Max,
+        MathOp::DivInt,
+    ];
+
+    /// The global name the operation is defined under.
+    pub fn name(self) -> &'static str {
+        match self {
+            MathOp::Sqrt => "sqrt",
+            MathOp::Abs => "abs",
+            MathOp::Floor => "floor",
+            MathOp::Ceil => "ceil",
+            MathOp::Round => "round",
+            MathOp::Pow => "pow",
+            MathOp::Min => "min",
+            MathOp::Max => "max",
+            MathOp::DivInt => "divInt",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MathFn(pub MathOp);
+
+impl LoxCallable for MathFn {
+    fn arity(&self) -> usize {
+        match self.0 {
+            MathOp::Pow | MathOp::Min | MathOp::Max | MathOp::DivInt => 2,
+            _ => 1,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let name = self.0.name();
+        let mut numbers = Vec::with_capacity(arguments.len());
+        for argument in &arguments {
+            match argument.as_f64() {
+                Some(n) => numbers.push(n),
+                None => {
+                    return Err(interpreter.native_error(format!(
+                        "{}() expects numbers, got {}.",
+                        name,
+                        argument.type_name()
+                    )));
+                }
+            }
+        }
+
+        let result = match self.0 {
+            MathOp::Sqrt => {
+                if numbers[0] < 0.0 {
+                    return Err(interpreter.native_error(format!(
+                        "Can't take sqrt() of a negative number ({}).",
+                        stringify_number(numbers[0])
+                    )));
+                }
+                numbers[0].sqrt()
+            }
+            MathOp::Abs => {
+                if let Value::Int(n) = arguments[0] {
+                    return Ok(n.checked_abs().map_or(Value::Float((n as f64).abs()), Value::Int));
+                }
+                numbers[0].abs()
+            }
+            MathOp::Floor | MathOp::Ceil | MathOp::Round => {
+                let whole = match self.0 {
+                    MathOp::Floor => numbers[0].floor(),
+                    MathOp::Ceil => numbers[0].ceil(),
+                    _ => numbers[0].round(),
+                };
+                return Ok(match arguments[0] {
+                    Value::Int(n) => Value::Int(n),
+                    _ => float_to_int(whole).map_or(Value::Float(whole), Value::Int),
+                });
+            }
+            MathOp::Pow => {
+                if let (Value::Int(base), Value::Int(exponent)) = (&arguments[0], &arguments[1])
+                    && let Ok(exponent) = u32::try_from(*exponent)
+                    && let Some(n) = base.checked_pow(exponent)
+                {
+                    return Ok(Value::Int(n));
+                }
+                numbers[0].powf(numbers[1])
+            }
+            MathOp::Min | MathOp::Max => {
+                if let (Value::Int(a), Value::Int(b)) = (&arguments[0], &arguments[1]) {
+                    return Ok(Value::Int(if self.0 == MathOp::Min { *a.min(b) } else { *a.max(b) }));
+                }
+                if self.0 == MathOp::Min { numbers[0].min(numbers[1]) } else { numbers[0].max(numbers[1]) }
+            }
+            MathOp::DivInt => {
+                if numbers[1] == 0.0 {
+                    return Err(interpreter.native_error("Division by zero.".to_string()));
+                }
+                if let (Value::Int(a), Value::Int(b)) = (&arguments[0], &arguments[1]) {
+                    return Ok(floor_div(*a, *b).map_or(Value::Float((numbers[0] / numbers[1]).floor()), Value::Int));
+                }
+                let quotient = (numbers[0] / numbers[1]).floor();
+                return Ok(float_to_int(quotient).map_or(Value::Float(quotient), Value::Int));
+            }
+        };
+        Ok(Value::Float(result))
+    }
+}
+
+// `a / b` rounded towards negative infinity, where Rust's `/` rounds towards zero. `None` only
+// for i64::MIN / -1, whose result is one more than i64::MAX
+fn floor_div(a: i64, b: i64) -> Option<i64> {
+    let quotient = a.checked_div(b)?;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        Some(quotient - 1)
+    } else {
+        Some(quotient)
+    }
+}
+
+impl fmt::Display for MathFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`random()` is a float in [0, 1), `randomInt(lo, hi)` a whole number between `lo` and `hi`
+inclusive, and `randomSeed(n)` reseeds the generator so the numbers that follow are the same on
+every run. The generator itself lives on the evaluator, see random.rs.
+
+`randomInt` insists on whole-number bounds with `lo <= hi`. Quietly rounding `randomInt(0.5, 3)`
+or swapping reversed bounds would hide a mistake in the caller. Any number is a valid seed: its
+bit pattern is what seeds the generator, so `randomSeed(1)` and `randomSeed(1.5)` differ.
+*/
+#[derive(Debug)]
+pub struct RandomFn;
+
+impl LoxCallable for RandomFn {
+    fn arity(&self) -> usize { 0 }
+
+    fn name(&self) -> &str { "random" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        _arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        Ok(Value::Float(interpreter.rng.next_f64()))
+    }
+}
+
+impl fmt::Display for RandomFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+#[derive(Debug)]
+pub struct RandomIntFn;
+
+impl LoxCallable for RandomIntFn {
+    fn arity(&self) -> usize { 2 }
+
+    fn name(&self) -> &str { "randomInt" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let (lo, hi) = match (arguments[0].as_integer(), arguments[1].as_integer()) {
+            (Some(lo), Some(hi)) => (lo, hi),
+            _ => {
+                return Err(interpreter.native_error(
+                    "randomInt() expects two whole numbers.".to_string(),
+                ));
+            }
+        };
+        if lo > hi {
+            return Err(interpreter.native_error(format!(
+                "randomInt() lower bound {} is greater than upper bound {}.",
+                lo, hi
+            )));
+        }
+        Ok(Value::Int(interpreter.rng.next_in_range(lo, hi)))
+    }
+}
+
+impl fmt::Display for RandomIntFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+#[derive(Debug)]
+pub struct RandomSeedFn;
+
+impl LoxCallable for RandomSeedFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "randomSeed" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match &arguments[0] {
+            // seeded from the float's bits whichever way the number is held, so 1 and 1.0 agree
+            number @ (Value::Int(_) | Value::Float(_)) => {
+                interpreter.set_random_seed(number.as_f64().unwrap_or_default().to_bits());
+                Ok(Value::Nil)
+            }
+            other => Err(interpreter.native_error(format!(
+                "randomSeed() expects a number, got {}.",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for RandomSeedFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`format_number(x, decimals)` is `x` written with exactly `decimals` digits after the decimal
+point, rounded, as a string: `format_number(3.14159, 2)` is "3.14" and `format_number(2, 3)` is
+"2.000". This is Rust's `{:.N}` formatting, so the digits are those of the exact value of the
+double, and a half-way case like 0.125 rounds to "0.12" because 0.125 is stored exactly and rounds
+to even.
+
+`decimals` must be a whole number from 0 up to `MAX_DECIMALS`. A double only carries about 17
+significant digits, so no report needs more than that, and an absurd count like 1e9 would
+otherwise try to build a gigabyte of zeros.
+*/
+#[derive(Debug)]
+pub struct FormatNumberFn;
+
+const MAX_DECIMALS: i64 = 100;
+
+impl LoxCallable for FormatNumberFn {
+    fn arity(&self) -> usize { 2 }
+
+    fn name(&self) -> &str { "format_number" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let x = match &arguments[0] {
+            Value::Int(x) => *x as f64,
+            Value::Float(x) => *x,
+            other => {
+                return Err(interpreter.native_error(format!(
+                    "format_number() expects a number to format, got {}.",
+                    other.type_name()
+                )));
+            }
+        };
+        let decimals = match arguments[1].as_integer() {
+            Some(d) if (0..=MAX_DECIMALS).contains(&d) => d as usize,
+            _ => {
+                return Err(interpreter.native_error(format!(
+                    "format_number() expects a whole number of decimals from 0 to {}.",
+                    MAX_DECIMALS
+                )));
+            }
+        };
+        Ok(Value::String(format!("{:.*}", decimals, x).into()))
+    }
+}
+
+impl fmt::Display for FormatNumberFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`sleepMs(n)` pauses the whole interpreter for `n` milliseconds and returns `nil`. Fractions of a
+millisecond are honoured as far as the operating system's timer allows; like any sleep, it may
+overrun but never returns early. A negative duration is an error rather than a no-op, since it is
+almost certainly a mistake in the caller's arithmetic, and so is one too long to represent.
+*/
+#[derive(Debug)]
+pub struct SleepMsFn;
+
+impl LoxCallable for SleepMsFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "sleepMs" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match arguments[0].as_f64() {
+            Some(ms) if ms >= 0.0 && ms.is_finite() => {
+                // finite, but possibly more seconds than a Duration can hold
+                let duration = Duration::try_from_secs_f64(ms / 1000.0)
+                    .map_err(|_| interpreter.native_error("sleepMs() duration is too long.".to_string()))?;
+                thread::sleep(duration);
+                Ok(Value::Nil)
+            }
+            Some(_) => Err(interpreter.native_error(format!(
+                "sleepMs() expects a finite, non-negative duration, got {}.",
+                arguments[0]
+            ))),
+            None => Err(interpreter.native_error(format!(
+                "sleepMs() expects a number, got {}.",
+                arguments[0].type_name()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SleepMsFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`str(value)` is the text `print` would show for a value, `toString` and all, as a string.
+*/
+#[derive(Debug)]
+pub struct StrFn;
+
+impl LoxCallable for StrFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "str" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let call_site = interpreter.call_site();
+        Ok(Value::String(interpreter.stringify(&arguments[0], &call_site)?.into()))
+    }
+}
+
+impl fmt::Display for StrFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`format(template, ...)` fills the placeholders in `template` with the arguments that follow it,
+each turned into text the way `print` would show it, `toString` included:
+
+    format("{} + {} = {}", 1, 2, 3)        "1 + 2 = 3"
+    format("{1}, {0}", "world", "hello")   "hello, world"
+    format("{{{}}}", "x")                  "{x}"
+
+`{}` takes the next argument in turn, counting only the other `{}`s, and `{n}` the argument at
+index `n`, from 0, so the two can be mixed. `{{` and `}}` are a literal brace. Any other use of a
+brace is an error, as is a placeholder with no argument to fill it, which names the missing index.
+
+An argument that no placeholder uses is an error too. It is almost always a placeholder that was
+forgotten, and silently dropping it would print something other than what the caller meant.
+*/
+#[derive(Debug)]
+pub struct FormatFn;
+
+impl LoxCallable for FormatFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn expected_arity(&self) -> Arity {
+        Arity::AtLeast(1)
+    }
+
+    fn name(&self) -> &str { "format" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let Value::String(template) = &arguments[0] else {
+            return Err(interpreter.native_error(format!(
+                "format() expects a string to format, got {}.",
+                arguments[0].type_name()
+            )));
+        };
+        let values = &arguments[1..];
+        let call_site = interpreter.call_site();
+        let mut used = vec![false; values.len()];
+        let mut next = 0;
+        let mut out = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => out.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => out.push('}'),
+                '{' => {
+                    let mut digits = String::new();
+                    while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+                        digits.push(digit);
+                    }
+                    if chars.next() != Some('}') {
+                        return Err(interpreter.native_error(
+                            "format() placeholders must be {} or {index}; write {{ for a '{'.".to_string(),
+                        ));
+                    }
+                    let index = if digits.is_empty() {
+                        next += 1;
+                        next - 1
+                    } else {
+                        // too many digits to fit is as missing as any other index past the end
+                        digits.parse().unwrap_or(usize::MAX)
+                    };
+                    let Some(value) = values.get(index) else {
+                        // the index as written, which may not have fit in a usize
+                        let shown = if digits.is_empty() { index.to_string() } else { digits };
+                        return Err(interpreter.native_error(format!(
+                            "format() has no argument {} for its placeholder, got {} arguments.",
+                            shown,
+                            values.len()
+                        )));
+                    };
+                    used[index] = true;
+                    out.push_str(&interpreter.stringify(value, &call_site)?);
+                    interpreter.check_allocation(&call_site, Some(out.len()))?;
+                }
+                '}' => {
+                    return Err(interpreter.native_error(
+                        "format() found a '}' outside a placeholder; write }} for a '}'.".to_string(),
+                    ));
+                }
+                c => out.push(c),
+            }
+        }
+        if let Some(unused) = used.iter().position(|used| !used) {
+            return Err(interpreter.native_error(format!(
+                "format() argument {} is not used by any placeholder.",
+                unused
+            )));
+        }
+        Ok(Value::String(out.into()))
+    }
+}
+
+impl fmt::Display for FormatFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`type(value)` names the runtime type of a value as a string, one of "nil", "boolean", "number",
+"string", "array", "map", "function", "native", "class" or "instance". These are the names the interpreter's own
+error messages use, so `type(x)` tells a program what "got string" in an error meant. Functions
+declared in Lox, bound methods included, are "function"; the ones built into the interpreter, like
+`clock`, are "native".
+
+Every instance is just "instance"; `className(instance)` gives the name of the class it was made
+from, which for an instance of a subclass is the subclass.
+*/
+#[derive(Debug)]
+pub struct TypeFn;
+
+impl LoxCallable for TypeFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "type" }
+
+    fn call(
+        &self,
+        _interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        Ok(Value::String(arguments[0].type_name().to_string().into()))
+    }
+}
+
+impl fmt::Display for TypeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+#[derive(Debug)]
+pub struct ClassNameFn;
+
+impl LoxCallable for ClassNameFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "className" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match &arguments[0] {
+            Value::LoxInstance(instance) => Ok(Value::String(instance.borrow().class_name().to_string().into())),
+            other => Err(interpreter.native_error(format!(
+                "className() expects an instance, got {}.",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ClassNameFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`globals()` is a map from the name of every global variable to its current value, for tools like
+a REPL that want to show what has been defined. The natives and built-in classes like `Error` are
+left out: they are there in every program, so they would only bury what the program defined
+itself. That goes for a global the program bound to a native, too (`var root = sqrt;`), since its
+value is a native all the same. `args`, the script's arguments, is left out as well, for as long
+as it holds the array it started with.
+
+The map is a snapshot, sorted by name. Changing it does not change the variables, but the values
+in it are the values themselves, so an array or instance in it is the one the global refers to.
+*/
+#[derive(Debug)]
+pub struct GlobalsFn;
+
+impl LoxCallable for GlobalsFn {
+    fn arity(&self) -> usize { 0 }
+
+    fn name(&self) -> &str { "globals" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        _arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let globals = interpreter.globals.borrow();
+        let mut bindings: Vec<(&String, &Value)> = globals
+            .values
+            .iter()
+            .filter(|(_, value)| match value {
+                Value::Callable(callable) => !callable.is_native(),
+                Value::LoxClass(class) => !interpreter.error_class.as_ref().is_some_and(|error| error.same_object(class)),
+                Value::Array(array) => !interpreter.script_args.as_ref().is_some_and(|args| Rc::ptr_eq(args, array)),
+                _ => true,
+            })
+            .collect();
+        bindings.sort_by_key(|(name, _)| *name);
+
+        let mut map = LoxMap::new();
+        for (name, value) in bindings {
+            map.insert(Literal::String(name.clone()), value.clone());
+        }
+        Ok(interpreter.heap.map(map))
+    }
+}
+
+impl fmt::Display for GlobalsFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/*
+`exit(code)` stops the program with the given status. It does not end the process itself: that
+would skip every destructor and take down whatever program is embedding the interpreter. Instead
+it raises `RuntimeError::Exit`, which unwinds through every function and loop like a return that
+nothing catches, out of `Interpreter::interpret`. The code then ends up in the diagnostics, and it
+is up to the caller what to do with it; `run_file` exits the process with it, while the REPL just
+reports it and carries on.
+
+The code must be a whole number from 0 to 255, the range of statuses a process can report on
+Unix; anything else would be silently truncated by the operating system.
+*/
+#[derive(Debug)]
+pub struct ExitFn;
+
+impl LoxCallable for ExitFn {
+    fn arity(&self) -> usize { 1 }
+
+    fn name(&self) -> &str { "exit" }
+
+    fn call(
+        &self,
+        interpreter: &mut Evaluator,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match arguments[0].as_integer() {
+            Some(code) if (0..=255).contains(&code) => Err(RuntimeError::Exit(code as i32)),
+            _ => Err(interpreter.native_error(format!(
+                "exit() expects a whole number from 0 to 255, got {}.",
+                arguments[0].repr()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ExitFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
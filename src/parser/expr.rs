@@ -1,6 +1,23 @@
 // This file is generated by generate_ast.rs
-use crate::lexer::{Token, Literal};
-use crate::{RuntimeError, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::lexer::{same_tokens, Token, Literal};
+use crate::{RuntimeError, Stmt, Value};
+
+/*
+The resolver records, for every variable reference, how many scopes away its binding lives.
+It needs a key that tells two references to the same name apart, even when they sit on the same
+line, and that survives the tree being cloned (function bodies are). So every expression that can
+be resolved carries an id handed out at parse time. Ids are unique for the whole process, which
+keeps them from clashing when a REPL resolves each line separately into the same map. Function
+declarations take one from the same counter, for what the resolver finds out about them.
+*/
+pub type ExprId = usize;
+
+static NEXT_EXPR_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub fn next_expr_id() -> ExprId {
+    NEXT_EXPR_ID.fetch_add(1, Ordering::Relaxed)
+}
 
 pub trait Visitor {
     fn visit_literal_expr(&mut self, value: &Literal) -> Result<Value, RuntimeError>;
@@ -12,8 +29,8 @@ pub trait Visitor {
         operator: &Token,
         right: &Expr,
     ) -> Result<Value, RuntimeError>;
-    fn visit_variable_expr(&mut self, token: &Token, initializer: &Option<Box<Expr>>) -> Result<Value, RuntimeError>;
-    fn visit_assign_expr(&mut self, token: &Token, value: &Expr) -> Result<Value, RuntimeError>;
+    fn visit_variable_expr(&mut self, token: &Token, id: ExprId) -> Result<Value, RuntimeError>;
+    fn visit_assign_expr(&mut self, token: &Token, value: &Expr, id: ExprId) -> Result<Value, RuntimeError>;
 
     fn visit_logical_expr(
         &mut self,
@@ -24,25 +41,38 @@ pub trait Visitor {
     fn visit_call_expr(
         &mut self,
         callee: &Expr,
+        open_paren: &Token,
         paren:  &Token,
         arguments: &[Expr],
+        names: &[Option<Token>],
     ) -> Result<Value, RuntimeError>;
     fn visit_get_expr(
-        &mut self, object: &Expr, name: &Token
+        &mut self, object: &Expr, name: &Token, optional: bool, id: ExprId
     ) -> Result<Value, RuntimeError>;
     fn visit_set_expr(
         &mut self, object: &Expr, name: &Token, value: &Expr
     ) -> Result<Value, RuntimeError>;
     fn visit_this_expr(
-        &mut self, this: &Token
+        &mut self, this: &Token, id: ExprId
     ) -> Result<Value, RuntimeError>;
     fn visit_super_expr(
-        &mut self, keyword: &Token, method: &Token
+        &mut self, keyword: &Token, method: &Token, id: ExprId
+    ) -> Result<Value, RuntimeError>;
+    fn visit_array_expr(&mut self, elements: &[Expr]) -> Result<Value, RuntimeError>;
+    fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<Value, RuntimeError>;
+    fn visit_index_expr(
+        &mut self, object: &Expr, bracket: &Token, index: &Expr
+    ) -> Result<Value, RuntimeError>;
+    fn visit_slice_expr(
+        &mut self, object: &Expr, bracket: &Token, start: &Expr, end: &Expr
     ) -> Result<Value, RuntimeError>;
+    fn visit_index_set_expr(
+        &mut self, object: &Expr, bracket: &Token, index: &Expr, value: &Expr
+    ) -> Result<Value, RuntimeError>;
+    fn visit_block_expr(&mut self, statements: &[Stmt], value: Option<&Expr>) -> Result<Value, RuntimeError>;
 }
 
 #[derive(Debug, Clone)]
-#[derive(Eq, Hash, PartialEq)]
 pub enum Expr {
     Binary {
         left: Box<Expr>,
@@ -61,11 +91,12 @@ pub enum Expr {
     },
     Variable {
         name: Token,
-        initializer: Option<Box<Expr>>,
+        id: ExprId,
     },
     Assign {
         name: Token,
         value: Box<Expr>,
+        id: ExprId,
     },
     Logical {
         left: Box<Expr>,
@@ -74,12 +105,20 @@ pub enum Expr {
     },
     Call {
         callee: Box<Expr>,
+        // the `(` after the callee, where an error about the callee itself is reported
+        open_paren: Token,
         paren: Token, // for error reporting
         arguments: Vec<Expr>, // can be zero or more
+        // one entry per argument: the parameter name for `name: value`, None for a positional one
+        names: Vec<Option<Token>>,
     },
     Get {
         object: Box<Expr>,
         name: Token,
+        // `object?.name`, which gives nil instead of an error when the object is nil
+        optional: bool,
+        // keys the evaluator's cache of the method this site last found, see `Evaluator::get_property`
+        id: ExprId,
     },
     Set {
         object: Box<Expr>,
@@ -87,48 +126,164 @@ pub enum Expr {
         value: Box<Expr>,
     },
     This {
-        keyword: Token
+        keyword: Token, id: ExprId
     },
     Super {
-        keyword: Token, method: Token
+        keyword: Token, method: Token, id: ExprId
+    },
+    // an array literal, `[a, b, c]`
+    Array {
+        elements: Vec<Expr>,
+    },
+    // a map literal, `{key: value, ...}`
+    Map {
+        brace: Token, // the opening '{', where a bad key is reported
+        entries: Vec<(Expr, Expr)>,
+    },
+    // `object[index]`
+    Index {
+        object: Box<Expr>,
+        bracket: Token, // the closing ']', for error reporting
+        index: Box<Expr>,
+    },
+    // `object[start..end]`, the elements from `start` up to but not including `end`
+    Slice {
+        object: Box<Expr>,
+        bracket: Token,
+        start: Box<Expr>,
+        end: Box<Expr>,
+    },
+    // `object[index] = value`
+    IndexSet {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    // `{ statements... value }`, a block in its own scope whose value is its last expression,
+    // nil if it ends with a statement instead
+    Block {
+        statements: Vec<Stmt>,
+        value: Option<Box<Expr>>,
+    },
+}
+
+/*
+Two trees are equal when they have the same shape and the same tokens, wherever the tokens are:
+parsing `1 + 2` and `1 +\n 2` gives equal trees. The ids that key the resolver's results are
+ignored too, since every parse hands out new ones. This is for comparing a parse with the tree a
+test expects; the interpreter itself never compares trees.
+*/
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Expr::Binary { left: l1, operator: o1, right: r1 }, Expr::Binary { left: l2, operator: o2, right: r2 })
+            | (Expr::Logical { left: l1, operator: o1, right: r1 }, Expr::Logical { left: l2, operator: o2, right: r2 }) => {
+                o1.same_as(o2) && l1 == l2 && r1 == r2
+            }
+            (Expr::Grouping { expression: e1 }, Expr::Grouping { expression: e2 }) => e1 == e2,
+            (Expr::Literal { value: v1 }, Expr::Literal { value: v2 }) => v1 == v2,
+            (Expr::Unary { operator: o1, right: r1 }, Expr::Unary { operator: o2, right: r2 }) => {
+                o1.same_as(o2) && r1 == r2
+            }
+            (Expr::Variable { name: n1, .. }, Expr::Variable { name: n2, .. }) => n1.same_as(n2),
+            (Expr::Assign { name: n1, value: v1, .. }, Expr::Assign { name: n2, value: v2, .. }) => {
+                n1.same_as(n2) && v1 == v2
+            }
+            (
+                Expr::Call { callee: c1, open_paren: op1, paren: p1, arguments: a1, names: n1 },
+                Expr::Call { callee: c2, open_paren: op2, paren: p2, arguments: a2, names: n2 },
+            ) => {
+                c1 == c2
+                    && op1.same_as(op2)
+                    && p1.same_as(p2)
+                    && a1 == a2
+                    && same_tokens(n1.iter().map(Option::as_ref), n2.iter().map(Option::as_ref))
+            }
+            (
+                Expr::Get { object: o1, name: n1, optional: q1, .. },
+                Expr::Get { object: o2, name: n2, optional: q2, .. },
+            ) => o1 == o2 && n1.same_as(n2) && q1 == q2,
+            (Expr::Set { object: o1, name: n1, value: v1 }, Expr::Set { object: o2, name: n2, value: v2 }) => {
+                o1 == o2 && n1.same_as(n2) && v1 == v2
+            }
+            (Expr::This { keyword: k1, .. }, Expr::This { keyword: k2, .. }) => k1.same_as(k2),
+            (Expr::Super { keyword: k1, method: m1, .. }, Expr::Super { keyword: k2, method: m2, .. }) => {
+                k1.same_as(k2) && m1.same_as(m2)
+            }
+            (Expr::Array { elements: e1 }, Expr::Array { elements: e2 }) => e1 == e2,
+            (Expr::Map { brace: b1, entries: e1 }, Expr::Map { brace: b2, entries: e2 }) => {
+                b1.same_as(b2) && e1 == e2
+            }
+            (
+                Expr::Index { object: o1, bracket: b1, index: i1 },
+                Expr::Index { object: o2, bracket: b2, index: i2 },
+            ) => o1 == o2 && b1.same_as(b2) && i1 == i2,
+            (
+                Expr::Slice { object: o1, bracket: b1, start: s1, end: e1 },
+                Expr::Slice { object: o2, bracket: b2, start: s2, end: e2 },
+            ) => o1 == o2 && b1.same_as(b2) && s1 == s2 && e1 == e2,
+            (
+                Expr::IndexSet { object: o1, bracket: b1, index: i1, value: v1 },
+                Expr::IndexSet { object: o2, bracket: b2, index: i2, value: v2 },
+            ) => o1 == o2 && b1.same_as(b2) && i1 == i2 && v1 == v2,
+            (Expr::Block { statements: s1, value: v1 }, Expr::Block { statements: s2, value: v2 }) => {
+                s1 == s2 && v1 == v2
+            }
+            _ => false,
+        }
     }
 }
 
 impl Expr {
     pub fn accept(&self, visitor: &mut impl Visitor) -> Result<Value, RuntimeError> {
         match self {
-            Expr::Literal { value } => visitor.visit_literal_expr(&value),
-            Expr::Grouping { expression } => visitor.visit_grouping_expr(&expression),
-            Expr::Unary { operator, right } => visitor.visit_unary_expr(&operator, &right),
+            Expr::Literal { value } => visitor.visit_literal_expr(value),
+            Expr::Grouping { expression } => visitor.visit_grouping_expr(expression),
+            Expr::Unary { operator, right } => visitor.visit_unary_expr(operator, right),
             Expr::Binary {
                 left,
                 operator,
                 right,
-            } => visitor.visit_binary_expr(&left, &operator, &right),
-            Expr::Variable { name, initializer } => visitor.visit_variable_expr(name, initializer),
-            Expr::Assign { name, value } => visitor.visit_assign_expr(name, value),
+            } => visitor.visit_binary_expr(left, operator, right),
+            Expr::Variable { name, id } => visitor.visit_variable_expr(name, *id),
+            Expr::Assign { name, value, id } => visitor.visit_assign_expr(name, value, *id),
             Expr::Logical {
                 left,
                 operator,
                 right,
-            } => visitor.visit_logical_expr(&left, &operator, &right),
+            } => visitor.visit_logical_expr(left, operator, right),
             Expr::Call {
                 callee,
+                open_paren,
                 paren,
-                arguments
-            } => visitor.visit_call_expr(callee, paren, arguments),
+                arguments,
+                names
+            } => visitor.visit_call_expr(callee, open_paren, paren, arguments, names),
             Expr::Get {
-                object, name
-            } => visitor.visit_get_expr(object, name),
+                object, name, optional, id
+            } => visitor.visit_get_expr(object, name, *optional, *id),
             Expr::Set {
                 object, name, value
             } => visitor.visit_set_expr(object, name, value),
             Expr::This {
-                keyword
-            } => visitor.visit_this_expr(keyword),
+                keyword, id
+            } => visitor.visit_this_expr(keyword, *id),
             Expr::Super {
-                keyword, method
-            } => visitor.visit_super_expr(keyword, method),
+                keyword, method, id
+            } => visitor.visit_super_expr(keyword, method, *id),
+            Expr::Array { elements } => visitor.visit_array_expr(elements),
+            Expr::Map { brace, entries } => visitor.visit_map_expr(brace, entries),
+            Expr::Index {
+                object, bracket, index
+            } => visitor.visit_index_expr(object, bracket, index),
+            Expr::Slice {
+                object, bracket, start, end
+            } => visitor.visit_slice_expr(object, bracket, start, end),
+            Expr::IndexSet {
+                object, bracket, index, value
+            } => visitor.visit_index_set_expr(object, bracket, index, value),
+            Expr::Block { statements, value } => visitor.visit_block_expr(statements, value.as_deref()),
         }
     }
 }
@@ -6,3 +6,5 @@ pub use expr::*;
 
 pub mod stmt;
 pub use stmt::*;
+
+mod teardown;
@@ -1,8 +1,8 @@
-use log::error;
-use crate::expr::Expr;
+use crate::expr::{next_expr_id, Expr};
+use crate::parser::teardown::take;
 use crate::lexer::Token;
-use crate::{report, Literal, Stmt, TokenType};
-use crate::TokenType::{Dot, Identifier, LeftParen, Less, RightParen};
+use crate::{report, Diagnostics, Severity, Literal, Stmt, TokenType, TYPE_NAMES};
+use crate::TokenType::LeftParen;
 /*
 The parser takes the tokens as input and produces an abstract syntax tree, a more information-rich
 data structure, as output. As a reminder, tokens are the output of the lexer, which takes raw
@@ -107,8 +107,8 @@ it will discard tokens that would have caused cascading errors, so the parser ca
 the tokens at the next statement.
 */
 #[derive(Debug)]
-#[derive(Clone)]
-pub(crate) struct ParseError;
+#[derive(Clone, PartialEq)]
+pub struct ParseError;
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -121,6 +121,7 @@ impl std::error::Error for ParseError {}
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    diagnostics: Diagnostics,
 }
 
 impl Parser {
@@ -156,7 +157,17 @@ impl Parser {
     These are called error productions.
     */
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self { tokens, current: 0, diagnostics: Diagnostics::new() }
+    }
+
+    /// Name `file` in the errors reported while parsing, for the tokens of an imported file.
+    pub(crate) fn set_file(&mut self, file: &str) {
+        self.diagnostics.set_file(Some(file.to_string()));
+    }
+
+    /// Hand over the errors reported while parsing.
+    pub fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
     }
 
     pub fn parse(&mut self) -> Vec<Stmt> {
@@ -167,41 +178,83 @@ impl Parser {
                 // since the `self.declaration` function is repeatedly called to process
                 // a sequence of statements, it is the perfect place to synchronize
                 Ok(stmt) => statements.push(stmt),
-                Err(error) => self.synchronize(),
+                Err(_) => self.synchronize(),
             }
         }
         statements
     }
 
+    /*
+    Parse the tokens as a single expression rather than a program: `1 + 2 * 3`, with no `;` after
+    it. The expression has to use up every token, so anything left over is a syntax error, reported
+    like any other. This is for embedders and tools that evaluate expressions on their own, and for
+    the REPL, which echoes the value of a line that is just an expression.
+    */
+    pub fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.expression()?;
+        if !self.is_at_end() {
+            return Err(self.error(self.peek().clone(), "Expect end of expression."));
+        }
+        Ok(expr)
+    }
+
+    /*
+    Whether the tokens look like a bare expression, so that `parse_expression` is the way to read
+    them: they don't start with a keyword that only begins a statement, and don't end the way a
+    statement does, with a `;` or a `}`. Nothing is reported; `1 +` still counts, and it is
+    `parse_expression` that says what is wrong with it.
+    */
+    pub(crate) fn is_bare_expression(&self) -> bool {
+        let last = self.tokens.iter().rev().find(|token| token.token_type != TokenType::Eof);
+        match last {
+            Some(last) => {
+                !self.starts_statement()
+                    && !matches!(last.token_type, TokenType::SemiColon | TokenType::RightBrace)
+            }
+            None => false,
+        }
+    }
+
     fn declaration(&mut self) -> Result<Stmt, ParseError> {
+        // errors are reported where they are detected, so we only hand them up to `parse`,
+        // which synchronizes and carries on with the next statement
         if self.match_tokens(&[TokenType::Var]) {
-            match self.var_declaration() {
-                Ok(stmt) => Ok(stmt),
-                Err(error) => panic!("Error in processing a variable declaration.")
-            }
+            self.var_declaration(false)
+        } else if self.match_tokens(&[TokenType::Const]) {
+            self.var_declaration(true)
         } else if self.match_tokens(&[TokenType::Fun]) {
-            match self.function() {
-                Ok(stmt) => Ok(stmt),
-                Err(error) => panic!("Error in processing a function.")
-            }
+            self.function()
         } else if self.match_tokens(&[TokenType::Class]) {
-            match self.class_declaration() {
-                Ok(stmt) => Ok(stmt),
-                Err(error) => panic!("Error in processing a Class.")
-            }
+            self.class_declaration()
+        } else if self.match_tokens(&[TokenType::Import]) {
+            self.import_declaration()
         } else {
             self.statement()
         }
     }
+
+    // `import "path";` or `import "path" as name;`. It is a declaration, so it can't be the body of
+    // an `if` or a loop; the resolver makes sure it isn't inside a block or function either
+    fn import_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let path = self.consume(TokenType::String, "Expect a file path string after 'import'.")?;
+        let alias = if self.match_tokens(&[TokenType::As]) {
+            Some(self.consume(TokenType::Identifier, "Expect a module name after 'as'.")?)
+        } else {
+            None
+        };
+        self.consume(TokenType::SemiColon, "Expect ';' after import.")?;
+        Ok(Stmt::Import { keyword, path, alias })
+    }
     
     fn class_declaration(&mut self) -> Result<Stmt, ParseError> {
         let name = self.consume(TokenType::Identifier, "Expect class name.")?;
 
         let mut superclass = None;
         if self.match_tokens(&[TokenType::Less]) {
-            self.consume(TokenType::Identifier, "Expect superclass name.").expect("TODO: panic message");
+            self.consume(TokenType::Identifier, "Expect superclass name.")?;
             let superclass_token = self.previous().clone();
-            superclass = Some(Box::new(Expr::Variable { name: superclass_token, initializer: None }));
+            superclass = Some(Box::new(Expr::Variable { name: superclass_token, id: next_expr_id() }));
 
         }
 
@@ -236,6 +289,7 @@ impl Parser {
                      "Expect '(' after function name.")?;
 
         let mut params = Vec::new();
+        let mut param_types = Vec::new();
         // the first if statement checks for the zero-parameter case
         if !self.check(&TokenType::RightParen) {
             loop {
@@ -243,16 +297,22 @@ impl Parser {
                 // arguments separated by a comma
                 if params.len() >= 255 {
                     // same error style as the book
-                    return Err(Parser::error(self.peek(), "Can't have more than 255 parameters."));
+                    return Err(self.error(self.peek().clone(), "Can't have more than 255 parameters."));
                 }
 
                 params.push(
                     self.consume(TokenType::Identifier,
                                  "Expect parameter name.")?
                 );
+                // an optional type, `w: number`
+                param_types.push(if self.match_tokens(&[TokenType::Colon]) {
+                    Some(self.type_annotation()?)
+                } else {
+                    None
+                });
 
-                // no more parameters?
-                if !self.match_tokens(&[TokenType::Comma]) {
+                // no more parameters? A comma right before the ')' is allowed and ends the list too
+                if !self.match_tokens(&[TokenType::Comma]) || self.check(&TokenType::RightParen) {
                     break;
                 }
             }
@@ -261,6 +321,13 @@ impl Parser {
         self.consume(TokenType::RightParen,
                      "Expect ')' after parameters.")?;
 
+        // an optional return type, `fun f() -> number`
+        let return_type = if self.match_tokens(&[TokenType::Arrow]) {
+            Some(self.type_annotation()?)
+        } else {
+            None
+        };
+
         // 3. Body
         // consuming for a left brace here gives a more precise error message
         // because we expect a left brace since we are expecting a body from a function declaration
@@ -268,19 +335,39 @@ impl Parser {
                      "Expect '{' before function body.")?;
 
         // self.block() parses the braced statement list
-        let body = self.block();
+        let body = self.block()?;
 
         Ok(Stmt::Function {
             name,
             params,
+            param_types,
             body,
+            return_type,
+            id: next_expr_id(),
         })
     }
-    fn var_declaration(&mut self) -> Result<Stmt, ParseError> {
+
+    // the name of a type, one of `TYPE_NAMES`, after a parameter's `:` or a function's `->`;
+    // `nil` is a keyword, so it is let in separately
+    fn type_annotation(&mut self) -> Result<Token, ParseError> {
+        if self.match_tokens(&[TokenType::Nil]) {
+            return Ok(self.previous().clone());
+        }
+        let name = self.consume(TokenType::Identifier, "Expect type name.")?;
+        if !TYPE_NAMES.contains(&name.lexeme.as_str()) {
+            return Err(self.error(name.clone(), &format!("Unknown type '{}'.", name.lexeme)));
+        }
+        Ok(name)
+    }
+    // `var` and `const` declarations, `constant` says which
+    fn var_declaration(&mut self, constant: bool) -> Result<Stmt, ParseError> {
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
 
         let initializer = if self.match_tokens(&[TokenType::Equal]) {
             Some(Box::new(self.expression()?))
+        } else if constant {
+            // a constant could never be given a value later, so it has to get one here
+            return Err(self.error(name.clone(), &format!("Constant '{}' must be initialized.", name.lexeme)));
         } else {
             // If no initializer, default to `nil`
             None
@@ -291,6 +378,7 @@ impl Parser {
         Ok(Stmt::Var {
             name,
             initializer,
+            constant,
         })
     }
 
@@ -311,19 +399,73 @@ impl Parser {
         if self.match_stmt(TokenType::Print) {
             self.print_stmt()
         } else if self.match_stmt(TokenType::LeftBrace) {
-            Ok(Stmt::Block {statements: self.block()})
+            Ok(Stmt::Block {statements: self.block()?})
         } else if self.match_stmt(TokenType::If) {
           self.if_stmt()
         } else if self.match_stmt(TokenType::While) {
             self.while_stmt()
         } else if self.match_stmt(TokenType::For) {
             self.for_stmt()
+        } else if self.match_stmt(TokenType::Repeat) {
+            self.repeat_stmt()
+        } else if self.match_stmt(TokenType::Do) {
+            self.do_while_stmt()
         } else if self.match_stmt(TokenType::Return) {
             self.return_statement()
+        } else if self.match_stmt(TokenType::Continue) {
+            self.continue_statement()
+        } else if self.match_stmt(TokenType::Break) {
+            self.break_statement()
+        } else if self.match_stmt(TokenType::Try) {
+            self.try_statement()
+        } else if self.match_stmt(TokenType::Throw) {
+            self.throw_statement()
+        } else if self.check(&TokenType::Identifier) && self.check_next(&TokenType::Colon) {
+            self.labeled_loop()
         } else {
             self.expr_stmt()
         }
     }
+
+    // `name: loop`. Nothing else can start with a name and a colon, so it can't be mistaken for
+    // an expression statement
+    fn labeled_loop(&mut self) -> Result<Stmt, ParseError> {
+        let label = self.advance();
+        self.advance(); // the `:`
+        let mut statement = if self.match_stmt(TokenType::While) {
+            self.while_stmt()?
+        } else if self.match_stmt(TokenType::For) {
+            self.for_stmt()?
+        } else if self.match_stmt(TokenType::Repeat) {
+            self.repeat_stmt()?
+        } else if self.match_stmt(TokenType::Do) {
+            self.do_while_stmt()?
+        } else {
+            return Err(self.error(self.peek().clone(), "Expect a loop after a label."));
+        };
+        // a `for` with an initializer is desugared into a block around its loop
+        let target = match &mut statement {
+            Stmt::Block { statements } => statements.last_mut().expect("the desugared loop"),
+            loop_statement => loop_statement,
+        };
+        match target {
+            Stmt::While { label: slot, .. }
+            | Stmt::Repeat { label: slot, .. }
+            | Stmt::DoWhile { label: slot, .. }
+            | Stmt::ForIn { label: slot, .. } => *slot = Some(label),
+            _ => unreachable!("only loops are parsed above"),
+        }
+        Ok(statement)
+    }
+
+    // the label a `break` or `continue` names, if it names one
+    fn loop_label(&mut self) -> Option<Token> {
+        if self.match_tokens(&[TokenType::Identifier]) {
+            Some(self.previous().clone())
+        } else {
+            None
+        }
+    }
     
     fn match_stmt(&mut self, expected: TokenType) -> bool {
         if self.check(&expected) {
@@ -358,22 +500,68 @@ impl Parser {
     }
 
 
+    fn continue_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone(); // keep the token so errors can point at it
+        let label = self.loop_label();
+        self.consume(TokenType::SemiColon, "Expect ';' after 'continue'.")?;
+        Ok(Stmt::Continue { keyword, label })
+    }
+
+    fn break_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let label = self.loop_label();
+        self.consume(TokenType::SemiColon, "Expect ';' after 'break'.")?;
+        Ok(Stmt::Break { keyword, label })
+    }
+
+    // `try { ... } catch (name) { ... }`, where both blocks are required, braces and all
+    fn try_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+        self.consume(TokenType::Catch, "Expect 'catch' after try block.")?;
+        self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+        let error_name = self.consume(TokenType::Identifier, "Expect error variable name.")?;
+        self.consume(TokenType::RightParen, "Expect ')' after error variable.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before catch body.")?;
+        let handler = self.block()?;
+        Ok(Stmt::TryCatch { keyword, body, error_name, handler })
+    }
+
+    // unlike `return`, a `throw` always has a value: there is nothing to raise without one
+    fn throw_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let value = Box::new(self.expression()?);
+        self.consume(TokenType::SemiColon, "Expect ';' after thrown value.")?;
+        Ok(Stmt::Throw { keyword, value })
+    }
+
     fn print_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
         let value = self.expression()?; // Propagate error
         self.consume(TokenType::SemiColon, "Expect ';' after value.")?;
         Ok(Stmt::Print {
+            keyword,
             expression: Box::new(value),
         })
     }
 
     fn for_stmt(&mut self) -> Result<Stmt, ParseError> {
         // "for" has already been consumed by the caller.
+        let keyword = self.previous().clone();
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
 
+        // `for (name in ...)` or `for (index, name in ...)`: a loop over the elements of a collection
+        if self.check(&TokenType::Identifier)
+            && (self.check_next(&TokenType::In) || self.check_next(&TokenType::Comma))
+        {
+            return self.for_in_stmt(keyword);
+        }
+
         let initializer: Option<Stmt> = if self.match_tokens(&[TokenType::SemiColon]) {
             None
         } else if self.match_tokens(&[TokenType::Var]) {
-            Some(self.var_declaration()?)
+            Some(self.var_declaration(false)?)
         } else {
             Some(self.expr_stmt()?)
         };
@@ -400,23 +588,17 @@ impl Parser {
 
         let mut body: Stmt = self.statement()?; // {...} or single stmt
 
-        if let Some(inc_expr) = increment {
-            body = Stmt::Block {
-                statements: vec![
-                    body,
-                    Stmt::Expression {
-                        expression: Box::new(inc_expr),
-                    },
-                ],
-            };
-        }
-
+        // the increment stays a separate loop tail so that `continue` still runs it
         let cond_expr = condition.unwrap_or(Expr::Literal {
             value: Literal::Bool(true), // infinite loop if none
         });
         body = Stmt::While {
+            keyword,
+            label: None,
             condition: Box::new(cond_expr),
             body: Box::new(body),
+            increment: increment.map(Box::new),
+            else_branch: None,
         };
 
         if let Some(init_stmt) = initializer {
@@ -428,16 +610,78 @@ impl Parser {
         Ok(body)
     }
 
+    fn for_in_stmt(&mut self, keyword: Token) -> Result<Stmt, ParseError> {
+        let first = self.consume(TokenType::Identifier, "Expect loop variable name.")?.clone();
+        let (index, name) = if self.match_tokens(&[TokenType::Comma]) {
+            (Some(first), self.consume(TokenType::Identifier, "Expect loop variable name after ','.")?)
+        } else {
+            (None, first)
+        };
+        self.consume(TokenType::In, "Expect 'in' after loop variable.")?;
+        let iterable = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after for-in clause.")?;
+        let body = self.statement()?;
+        Ok(Stmt::ForIn {
+            keyword,
+            label: None,
+            index,
+            name,
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+        })
+    }
+
+    fn repeat_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone(); // the count is checked at runtime, errors point here
+        self.consume(TokenType::LeftParen, "Expect '(' after 'repeat'.")?;
+        let count = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after repeat count.")?;
+        let body = self.statement()?;
+        Ok(Stmt::Repeat {
+            keyword,
+            label: None,
+            count: Box::new(count),
+            body: Box::new(body),
+        })
+    }
+
+    fn do_while_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let body = self.statement()?;
+        let keyword = self.consume(TokenType::While, "Expect 'while' after do loop body.")?.clone();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after while condition.")?;
+        self.consume(TokenType::SemiColon, "Expect ';' after do-while loop.")?;
+        Ok(Stmt::DoWhile {
+            keyword,
+            label: None,
+            body: Box::new(body),
+            condition: Box::new(condition),
+        })
+    }
+
     fn while_stmt(&mut self) -> Result<Stmt, ParseError> {
-        self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
-        self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+        self.consume(TokenType::RightParen, "Expect ')' after while condition.")?;
 
         let body = self.statement()?;
 
+        // like with `if`, an `else` belongs to the nearest loop that can take it
+        let else_branch = if self.match_tokens(&[TokenType::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+
         Ok(Stmt::While {
+            keyword,
+            label: None,
             condition: Box::new(condition),
             body: Box::new(body),
+            increment: None,
+            else_branch,
         })
     }
 
@@ -462,14 +706,13 @@ impl Parser {
     }
 
 
-    fn block(&mut self) -> Vec<Stmt> {
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
         let mut statements = Vec::<Stmt>::new();
-        while (self.check(&TokenType::RightBrace) && !self.is_at_end()) {
-            statements.push(self.declaration().unwrap());
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            statements.push(self.declaration()?);
         }
-        self.consume(TokenType::RightBrace, "Expect '}' after block.")
-            .expect("Expect '}' after block.");
-        statements
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
     }
 
     fn expr_stmt(&mut self) -> Result<Stmt, ParseError> {
@@ -481,7 +724,7 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Result<Expr, ParseError> {
-        self.or_expr()
+        self.assignment()
     }
 
     fn equality(&mut self) -> Result<Expr, ParseError> {
@@ -502,43 +745,75 @@ impl Parser {
 
     fn assignment(&mut self) -> Result<Expr, ParseError> {
         // parse the left side first
-        let expr = self.or_expr()?;
+        let mut expr = self.range()?;
 
         // look for “=”
         if self.match_tokens(&[TokenType::Equal]) {
             let equals = self.previous().clone();  // keep for error reporting
             let value  = self.assignment()?;       // recurse for right side
 
-            // only a variable is a valid assignment target
-            if let Expr::Variable { name, .. } = expr {
-                return Ok(Expr::Assign {
-                    name,
+            return match &mut expr {
+                // only a variable is a valid assignment target
+                Expr::Variable { name, .. } => Ok(Expr::Assign {
+                    name: name.clone(),
                     value: Box::new(value),
-                });
-            }
-
-            if let Expr::Get { object, name } = expr {
-                return Ok(Expr::Set {
-                    object,
-                    name,
+                    id: next_expr_id(),
+                }),
+                // `a?.b = c` is not a target: there would be nothing to assign to when `a` is nil
+                Expr::Get { object, name, optional: false, .. } => Ok(Expr::Set {
+                    object: Box::new(take(object)),
+                    name: name.clone(),
                     value: Box::new(value),
-                });
-            }
-
-            // any other LHS → error
-            return Err(ParseError);
+                }),
+                Expr::Index { object, bracket, index } => Ok(Expr::IndexSet {
+                    object: Box::new(take(object)),
+                    bracket: bracket.clone(),
+                    index: Box::new(take(index)),
+                    value: Box::new(value),
+                }),
+                // `this` looks like a variable but is bound by the method call; it can't be rebound
+                Expr::This { keyword, .. } => {
+                    self.error(keyword.clone(), "Cannot assign to 'this'.");
+                    Ok(expr)
+                }
+                // any other LHS → report it, but the parser is not confused, so no need to synchronize
+                _ => {
+                    self.error(equals, "Invalid assignment target.");
+                    Ok(expr)
+                }
+            };
         }
 
         // no “=”: just return the original expression
         Ok(expr)
     }
 
+    /*
+    `start..end` binds looser than any other operator but assignment, so `0..n + 1` is
+    `0..(n + 1)` and `0..a or b` is `0..(a or b)`. It doesn't chain: after `a..b`, a second `..`
+    is left for the caller, which has no use for it and reports it.
+    */
+    fn range(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.or_expr()?;
+        if self.match_tokens(&[TokenType::DotDot]) {
+            let operator = self.previous().clone();
+            let right = self.or_expr()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+        Ok(expr)
+    }
+
     fn or_expr(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.and_expr()?;
 
-        // While we see consecutive "or" tokens, fold them left-associatively
-        while self.match_tokens(&[TokenType::Or]) {
-            let operator = self.previous().clone();   // the consumed "or"
+        // While we see consecutive "or" (or "??") tokens, fold them left-associatively.
+        // `a ?? b` gives `a` unless it is nil, with the same precedence and short-circuiting as "or"
+        while self.match_tokens(&[TokenType::Or, TokenType::QuestionQuestion]) {
+            let operator = self.previous().clone();   // the consumed "or" or "??"
             let right = self.and_expr()?;          // parse RHS
             expr = Expr::Logical {
                 left: Box::new(expr),
@@ -573,8 +848,18 @@ impl Parser {
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
+            // `object is Class` binds like a comparison: `a is B == true` is `(a is B) == true`
+            TokenType::Is,
         ]) {
             let operator: Token = self.previous().clone();
+            /*
+            `1 < 2 < 3` parses as `(1 < 2) < 3`, comparing a boolean to a number, which is
+            almost never what was meant. If the left operand is itself a comparison that was not
+            parenthesized, report it. The parser still knows where it is, so we keep going.
+            */
+            if Self::is_comparison(&expr) {
+                self.error(operator.clone(), "Chained comparison is not allowed; use explicit parentheses.");
+            }
             let right: Expr = self.term()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
@@ -585,6 +870,16 @@ impl Parser {
         Ok(expr)
     }
 
+    fn is_comparison(expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::Binary { operator, .. } if matches!(
+                operator.token_type,
+                TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual
+            )
+        )
+    }
+
     // it is possible to write a helper method to generalize the method for each
     // production rule
     fn term(&mut self) -> Result<Expr, ParseError> {
@@ -643,6 +938,35 @@ impl Parser {
                 expr = Ok(Expr::Get {
                     object: Box::new(expr?),  
                     name, 
+                    optional: false,
+                    id: next_expr_id(),
+                });
+            } else if self.match_tokens(&[TokenType::QuestionDot]) {
+                // optional chaining, `a?.b`. Each `?.` in a chain like `a?.b?.c` guards its own step
+                let name = self.consume(TokenType::Identifier, "Expect property name after '?.'.")?;
+                expr = Ok(Expr::Get {
+                    object: Box::new(expr?),
+                    name,
+                    optional: true,
+                    id: next_expr_id(),
+                });
+            } else if self.match_tokens(&[TokenType::LeftBracket]) {
+                // `a[i]`, which can be chained like calls and gets: `grid[y][x]`, or a slice `a[i..j]`,
+                // whose bounds come out of the index parsed as a range
+                let mut index = self.expression()?;
+                let bracket = self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Ok(match &mut index {
+                    Expr::Binary { left, operator, right } if operator.token_type == TokenType::DotDot => Expr::Slice {
+                        object: Box::new(expr?),
+                        bracket,
+                        start: Box::new(take(left)),
+                        end: Box::new(take(right)),
+                    },
+                    _ => Expr::Index {
+                        object: Box::new(expr?),
+                        bracket,
+                        index: Box::new(index),
+                    },
                 });
             } else {
                 break
@@ -650,8 +974,16 @@ impl Parser {
         }
         expr
     }
+    /*
+    An argument can be given by name, `greet(name: "Sam", greeting: "Hi")`, and is then bound to
+    the parameter with that name rather than by its position. Positional arguments may come first,
+    but once one argument has been named all the rest must be too: after a named argument, there is
+    no telling which position a positional one was meant for.
+    */
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let open_paren = self.previous().clone();
         let mut arguments = Vec::new();
+        let mut names: Vec<Option<Token>> = Vec::new();
 
         // if the token immediately following is a right parenthesis, then stop
         // else, parse the arguments as expressions
@@ -660,11 +992,24 @@ impl Parser {
                 if arguments.len() >= 255 {
                     // throwing an error is valid only when the parser does not know what state
                     // it has anymore. However, in this case, the state is still fine
-                    crate::utils::error(self.peek().line, "Can't have more than 255 arguments")
+                    self.error(self.peek().clone(), "Can't have more than 255 arguments.");
+                }
+                // `identifier :` starts a named argument
+                let name = if self.check(&TokenType::Identifier) && self.check_next(&TokenType::Colon) {
+                    let name = self.advance();
+                    self.advance(); // the ':'
+                    Some(name)
+                } else {
+                    None
+                };
+                if name.is_none() && names.iter().any(Option::is_some) {
+                    self.error(self.peek().clone(), "Positional argument can't follow a named argument.");
                 }
                 arguments.push(self.expression()?);
-                // syntax check
-                if !self.match_tokens(&[TokenType::Comma]) {
+                names.push(name);
+                // syntax check. A trailing comma, as in `f(a, b,)`, ends the list as well,
+                // but the list cannot be just a comma, so `f(,)` is still an error
+                if !self.match_tokens(&[TokenType::Comma]) || self.check(&TokenType::RightParen) {
                     break;
                 }
             }
@@ -674,8 +1019,10 @@ impl Parser {
 
         Ok(Expr::Call {
             callee: Box::new(callee),
+            open_paren,
             paren,
             arguments,
+            names,
         })
     }
 
@@ -704,49 +1051,199 @@ impl Parser {
                 })
             }
 
-            TokenType::Number | TokenType::String => {
+            TokenType::Number => {
                 let literal = self.peek().literal.clone();
                 self.advance();
                 Ok(Expr::Literal { value: literal })
             }
 
+            // string literals written next to each other are one string, as in C, so a long one
+            // can be split over several lines: `"Hello, " "world"` is `"Hello, world"`
+            TokenType::String => {
+                let mut text = String::new();
+                while self.check(&TokenType::String) {
+                    if let Literal::String(part) = &self.peek().literal {
+                        text.push_str(part);
+                    }
+                    self.advance();
+                }
+                Ok(Expr::Literal { value: Literal::String(text) })
+            }
+
             TokenType::LeftParen => {
+                self.advance();
                 let expr = self.expression()?;
-                self.consume(TokenType::RightParen, "Expect ')' after expression.")
-                    .expect("TODO: panic message");
+                self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
                 Ok(Expr::Grouping {
                     expression: Box::new(expr),
                 })
             }
 
+            TokenType::LeftBracket => {
+                self.advance();
+                self.array_literal()
+            }
+
+            // a statement starting with '{' is a block statement, so here it is a map or a block
+            // expression, see `brace_expression`
+            TokenType::LeftBrace => {
+                self.advance();
+                self.brace_expression()
+            }
+
             TokenType::Identifier => {
+                self.advance();
                 Ok(Expr::Variable {
                     name: self.previous().clone(),
-                    initializer: None
+                    id: next_expr_id()
                 })
             }
             TokenType::This => {
+                self.advance();
                 Ok(Expr::This {
-                    keyword: self.previous().clone()
+                    keyword: self.previous().clone(),
+                    id: next_expr_id()
                 })
             }
             TokenType::Super => {
+                self.advance();
                 let keyword = self.previous().clone();
-                self.consume(TokenType::Dot, "Expect '.' after 'super'.").expect("TODO: panic message");
+                self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
                 let method = self.consume(TokenType::Identifier, "Expect superclass method name.");
                 Ok(Expr::Super {
-                    keyword: keyword.clone(), method: method?
+                    keyword: keyword.clone(), method: method?, id: next_expr_id()
                 })
             }
-            _ => Err(Parser::error(self.peek(), "Expected an expression.")),
+            _ => Err(self.error(self.peek().clone(), "Expected an expression.")),
+        }
+    }
+
+    // `[a, b, c]`, after the '['. Like an argument list, it may end with a trailing comma
+    fn array_literal(&mut self) -> Result<Expr, ParseError> {
+        let mut elements = Vec::new();
+        while !self.check(&TokenType::RightBracket) {
+            elements.push(self.expression()?);
+            if !self.match_tokens(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBracket, "Expect ']' after array elements.")?;
+        Ok(Expr::Array { elements })
+    }
+
+    // `{key: value, ...}`, after the '{'. Keys are expressions too: `{name: 1}` uses the value of
+    // the variable `name` as the key, and a string key is written as a string, `{"name": 1}`
+    // `entries` are the ones `brace_expression` already parsed to tell the map from a block
+    fn map_literal(&mut self, brace: Token, mut entries: Vec<(Expr, Expr)>) -> Result<Expr, ParseError> {
+        if entries.is_empty() || self.match_tokens(&[TokenType::Comma]) {
+            while !self.check(&TokenType::RightBrace) {
+                let key = self.expression()?;
+                self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                let value = self.expression()?;
+                entries.push((key, value));
+                if !self.match_tokens(&[TokenType::Comma]) {
+                    break;
+                }
+            }
         }
+        self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+        Ok(Expr::Map { brace, entries })
+    }
+
+    /*
+    A '{' where an expression is expected starts either a map literal or a block expression:
+
+        var m = {"a": 1};
+        var x = { var t = compute(); t * 2 };
+
+    A block expression holds statements like any block, and its value is a final expression with
+    no ';' after it, or nil when there is none. The two are told apart by how they start. `{}` is
+    the empty map, as it always was. A declaration or a statement keyword can only start a block.
+    Otherwise both start with an expression, and what follows it decides: a ':' makes it the first
+    key of a map, and a ';' or the closing '}' make it part of a block.
+
+    A '{' inside a block expression starts another expression, so `{ var a = 2; { a * 3 } }` is 6.
+    A nested block that ends without a value and goes on with more statements, with no ';' after
+    it, is an ordinary block statement.
+    */
+    fn brace_expression(&mut self) -> Result<Expr, ParseError> {
+        let brace = self.previous().clone();
+        if self.check(&TokenType::RightBrace) {
+            return self.map_literal(brace, Vec::new());
+        }
+        if self.starts_statement() {
+            return self.block_expression(None);
+        }
+        let first = self.expression()?;
+        if self.match_tokens(&[TokenType::Colon]) {
+            let value = self.expression()?;
+            return self.map_literal(brace, vec![(first, value)]);
+        }
+        self.block_expression(Some(first))
+    }
+
+    // the rest of a block expression, after `first`, its leading expression if it had to be parsed
+    fn block_expression(&mut self, first: Option<Expr>) -> Result<Expr, ParseError> {
+        let mut statements = Vec::new();
+        let mut pending = first;
+        loop {
+            let mut expression = match pending.take() {
+                Some(expression) => expression,
+                None if self.check(&TokenType::RightBrace) || self.is_at_end() => break,
+                None if self.starts_statement() => {
+                    statements.push(self.declaration()?);
+                    continue;
+                }
+                None => self.expression()?,
+            };
+            // an expression followed by ';' is an expression statement, one followed by '}' the value
+            if self.match_tokens(&[TokenType::SemiColon]) {
+                statements.push(Stmt::Expression { expression: Box::new(expression) });
+                continue;
+            }
+            if self.match_tokens(&[TokenType::RightBrace]) {
+                return Ok(Expr::Block { statements, value: Some(Box::new(expression)) });
+            }
+            // a nested block with no value of its own, followed by more, is a block statement
+            match &mut expression {
+                Expr::Block { statements: inner, value: None } => {
+                    statements.push(Stmt::Block { statements: std::mem::take(inner) })
+                }
+                _ => return Err(self.error(self.peek().clone(), "Expect ';' or '}' after expression in block.")),
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(Expr::Block { statements, value: None })
+    }
+
+    // whether the next token can only begin a declaration or a statement, not an expression
+    fn starts_statement(&self) -> bool {
+        matches!(
+            self.peek().token_type,
+            TokenType::Var
+                | TokenType::Const
+                | TokenType::Fun
+                | TokenType::Class
+                | TokenType::Import
+                | TokenType::Print
+                | TokenType::If
+                | TokenType::While
+                | TokenType::For
+                | TokenType::Repeat
+                | TokenType::Do
+                | TokenType::Return
+                | TokenType::Break
+                | TokenType::Continue
+                | TokenType::Try
+                | TokenType::Throw
+        )
     }
 
     fn match_tokens(&mut self, types: &[TokenType]) -> bool {
         for token_type in types {
             if self.check(token_type) {
                 self.advance();
-                true;
+                return true;
             }
         }
         false
@@ -763,17 +1260,17 @@ impl Parser {
         if self.check(&token_type) {
             Ok(self.advance())
         } else {
-            Err(Parser::error(self.peek(), message))
+            Err(self.error(self.peek().clone(), message))
         }
     }
 
-    fn error(token: &Token, message: &str) -> ParseError {
+    fn error(&mut self, token: Token, message: &str) -> ParseError {
         match token.token_type {
             TokenType::Eof => {
-                report(token.line, " at end", message);
+                report(&mut self.diagnostics, Severity::Error, token.line, " at end", message);
             }
             _ => {
-                report(token.line, &format!(" at '{}'", token.lexeme), message);
+                report(&mut self.diagnostics, Severity::Error, token.line, &format!(" at '{}'", token.lexeme), message);
             }
         }
 
@@ -787,6 +1284,14 @@ impl Parser {
         self.previous().clone()
     }
 
+    // like `check`, but looks one token further ahead
+    fn check_next(&self, token_type: &TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => token.token_type == *token_type,
+            None => false,
+        }
+    }
+
     fn is_at_end(&self) -> bool {
         self.peek().token_type == TokenType::Eof
     }
@@ -815,12 +1320,17 @@ impl Parser {
 
             match self.peek().token_type {
                 TokenType::Class
+                | TokenType::Import
                 | TokenType::Fun
                 | TokenType::Var
+                | TokenType::Const
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
+                | TokenType::Do
                 | TokenType::Print
+                | TokenType::Try
+                | TokenType::Throw
                 | TokenType::Return => return,
                 _ => {}
             }
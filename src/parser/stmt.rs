@@ -1,13 +1,13 @@
 use crate::parser::parser::ParseError;
 // This file is generated by generate_ast.rs
-use crate::lexer::{Token};
-use crate::parser::{Expr};
+use crate::lexer::{same_tokens, Token};
+use crate::parser::{Expr, ExprId};
 
 pub trait StmtVisitor<R> {
     fn visit_expression_stmt(&mut self, expr: &Stmt) -> R;
     fn visit_print_stmt(&mut self, expr: &Stmt) -> R;
     fn visit_var_stmt(&mut self, expr: &Stmt) -> R;
-    fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> R;
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> R;
     fn visit_if_stmt(
         &mut self,
         condition: &Expr,
@@ -16,17 +16,32 @@ pub trait StmtVisitor<R> {
     ) -> R;
     fn visit_while_stmt(
         &mut self,
+        keyword: &Token,
+        label: &Option<Token>,
         condition: &Expr,
         body: &Stmt,
+        increment: &Option<Box<Expr>>,
+        else_branch: &Option<Box<Stmt>>,
     ) -> R;
+    fn visit_continue_stmt(&mut self, keyword: &Token, label: &Option<Token>) -> R;
+    fn visit_break_stmt(&mut self, keyword: &Token, label: &Option<Token>) -> R;
+    fn visit_repeat_stmt(&mut self, keyword: &Token, label: &Option<Token>, count: &Expr, body: &Stmt) -> R;
+    fn visit_do_while_stmt(&mut self, keyword: &Token, label: &Option<Token>, body: &Stmt, condition: &Expr) -> R;
+    fn visit_for_in_stmt(&mut self, keyword: &Token, label: &Option<Token>, index: &Option<Token>, name: &Token, iterable: &Expr, body: &Stmt) -> R;
     fn visit_fun_stmt(
         &mut self,
         name: &Token,
-        params: &Vec<Token>,
-        body: &Vec<Stmt>
+        params: &[Token],
+        param_types: &[Option<Token>],
+        body: &[Stmt],
+        return_type: &Option<Token>,
+        id: ExprId,
     ) -> R;
     fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Box<Expr>>) -> R;
-    fn visit_class_stmt(&mut self, name: &Token, methods: &Vec<Result<Stmt, ParseError>>, superclass: &Option<Box<Expr>>) -> R;
+    fn visit_class_stmt(&mut self, name: &Token, methods: &[Result<Stmt, ParseError>], superclass: &Option<Box<Expr>>) -> R;
+    fn visit_try_catch_stmt(&mut self, keyword: &Token, body: &[Stmt], error_name: &Token, handler: &[Stmt]) -> R;
+    fn visit_throw_stmt(&mut self, keyword: &Token, value: &Expr) -> R;
+    fn visit_import_stmt(&mut self, keyword: &Token, path: &Token, alias: &Option<Token>) -> R;
 }
 
 #[derive(Debug, Clone)]
@@ -37,7 +52,14 @@ pub enum Stmt {
     Function {
       name: Token,
         params: Vec<Token>,
+        // one entry per parameter: the type name after `name:`, checked against the argument
+        param_types: Vec<Option<Token>>,
         body: Vec<Stmt>,
+        // the type name after `->`, checked against whatever the function returns
+        return_type: Option<Token>,
+        // tells this declaration apart from any other with the same name, for what the resolver
+        // finds out about it, see `Evaluator::mark_implicit_return`. Handed out like an `ExprId`
+        id: ExprId,
     },
     If {
         /*
@@ -59,26 +81,162 @@ pub enum Stmt {
         
     },
     Print {
+        // where an error raised while turning the value into text is reported
+        keyword: Token,
         expression: Box<Expr>,
     },
     Return {
         keyword: Token, value: Option<Box<Expr>>,
     },
+    // `const x = ...;` is a Var with `constant` set, see `Environment::define_constant`
     Var {
         name: Token,
         initializer: Option<Box<Expr>>,
+        constant: bool,
     },
     Block {
         statements: Vec<Stmt>,
     },
+    /*
+    A `for` loop desugars into a `while`. Its increment is kept apart from the body, as a tail that
+    runs after every iteration, rather than being appended to the body block. That way a `continue`,
+    which abandons the rest of the body, still runs the increment instead of looping forever.
+    A plain `while` has no increment.
+
+    A `while` can have an `else` branch, as in Python, which runs instead of the body when the
+    condition is false the very first time it is checked, i.e. when the loop runs zero times.
+
+    Every kind of loop can be labeled, `outer: while (...) ...`, so that a `break outer;` or
+    `continue outer;` in a loop nested inside it is for it rather than the innermost loop. The
+    label is kept on the loop itself, and is the name token before the `:`.
+    */
     While {
-        condition: Box<Expr>, body: Box<Stmt>,
+        keyword: Token, // `while`, or `for` for a loop desugared from one
+        label: Option<Token>,
+        condition: Box<Expr>, body: Box<Stmt>, increment: Option<Box<Expr>>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    // `label` is the loop the `continue` is for, if it names one
+    Continue {
+        keyword: Token, label: Option<Token>,
+    },
+    Break {
+        keyword: Token, label: Option<Token>,
+    },
+    // `repeat (count) body` runs the body a fixed number of times, `count` being evaluated once
+    Repeat {
+        keyword: Token, label: Option<Token>, count: Box<Expr>, body: Box<Stmt>,
+    },
+    // `do body while (condition);` runs the body once before checking the condition the first time
+    DoWhile {
+        keyword: Token, // the `while`, where a loop limit is reported like for any other loop
+        label: Option<Token>,
+        body: Box<Stmt>, condition: Box<Expr>,
+    },
+    /*
+    `for (name in iterable) body` runs the body once for every element of an array, every key of a
+    map, every character of a string or every number of a range, with `name` bound to it in a
+    scope of its own. `for (index, name in iterable)` binds the element's zero-based position to
+    `index` as well, or for a map, the key to `index` and its value to `name`. Unlike the
+    three-clause `for`, it is not desugared: there is no Lox code that steps through a map.
+    */
+    ForIn {
+        keyword: Token, label: Option<Token>, index: Option<Token>, name: Token, iterable: Box<Expr>, body: Box<Stmt>,
     },
     Class {
         name: Token,
         methods: Vec<Result<Stmt, ParseError>>,
         superclass: Option<Box<Expr>>,
     },
+    /*
+    `try { body } catch (error_name) { handler }` runs the body, and if a runtime error escapes
+    it, runs the handler with `error_name` bound to the error. Both are blocks of their own, and
+    `keyword` is the `try`.
+    */
+    TryCatch {
+        keyword: Token, body: Vec<Stmt>, error_name: Token, handler: Vec<Stmt>,
+    },
+    // `throw value;` raises a runtime error carrying the value, for a `catch` to bind as it is
+    Throw {
+        keyword: Token, value: Box<Expr>,
+    },
+    // `import "path";` runs another file into the globals; `path` is the string token. With
+    // `as name`, `alias` is the name its namespace is bound to instead. See `Interpreter::import`
+    Import {
+        keyword: Token, path: Token, alias: Option<Token>,
+    },
+}
+
+// structural, ignoring lines, like the equality of expressions
+impl PartialEq for Stmt {
+    fn eq(&self, other: &Self) -> bool {
+        // a missing token and a present one are never the same
+        let same = |a: &Option<Token>, b: &Option<Token>| same_tokens(std::iter::once(a.as_ref()), std::iter::once(b.as_ref()));
+        match (self, other) {
+            (Stmt::Expression { expression: e1 }, Stmt::Expression { expression: e2 }) => e1 == e2,
+            (
+                Stmt::Function { name: n1, params: p1, param_types: t1, body: b1, return_type: r1, .. },
+                Stmt::Function { name: n2, params: p2, param_types: t2, body: b2, return_type: r2, .. },
+            ) => {
+                n1.same_as(n2)
+                    && same_tokens(p1.iter().map(Some), p2.iter().map(Some))
+                    && same_tokens(t1.iter().map(Option::as_ref), t2.iter().map(Option::as_ref))
+                    && b1 == b2
+                    && same(r1, r2)
+            }
+            (
+                Stmt::If { conditional: c1, consequent: t1, alternative: e1 },
+                Stmt::If { conditional: c2, consequent: t2, alternative: e2 },
+            ) => c1 == c2 && t1 == t2 && e1 == e2,
+            (Stmt::Print { keyword: k1, expression: e1 }, Stmt::Print { keyword: k2, expression: e2 }) => {
+                k1.same_as(k2) && e1 == e2
+            }
+            (Stmt::Return { keyword: k1, value: v1 }, Stmt::Return { keyword: k2, value: v2 }) => {
+                k1.same_as(k2) && v1 == v2
+            }
+            (
+                Stmt::Var { name: n1, initializer: i1, constant: c1 },
+                Stmt::Var { name: n2, initializer: i2, constant: c2 },
+            ) => n1.same_as(n2) && i1 == i2 && c1 == c2,
+            (Stmt::Block { statements: s1 }, Stmt::Block { statements: s2 }) => s1 == s2,
+            (
+                Stmt::While { keyword: k1, label: l1, condition: c1, body: b1, increment: i1, else_branch: e1 },
+                Stmt::While { keyword: k2, label: l2, condition: c2, body: b2, increment: i2, else_branch: e2 },
+            ) => k1.same_as(k2) && same(l1, l2) && c1 == c2 && b1 == b2 && i1 == i2 && e1 == e2,
+            (Stmt::Continue { keyword: k1, label: l1 }, Stmt::Continue { keyword: k2, label: l2 })
+            | (Stmt::Break { keyword: k1, label: l1 }, Stmt::Break { keyword: k2, label: l2 }) => {
+                k1.same_as(k2) && same(l1, l2)
+            }
+            (
+                Stmt::Repeat { keyword: k1, label: l1, count: c1, body: b1 },
+                Stmt::Repeat { keyword: k2, label: l2, count: c2, body: b2 },
+            ) => k1.same_as(k2) && same(l1, l2) && c1 == c2 && b1 == b2,
+            (
+                Stmt::DoWhile { keyword: k1, label: l1, body: b1, condition: c1 },
+                Stmt::DoWhile { keyword: k2, label: l2, body: b2, condition: c2 },
+            ) => k1.same_as(k2) && same(l1, l2) && b1 == b2 && c1 == c2,
+            (
+                Stmt::ForIn { keyword: k1, label: l1, index: x1, name: n1, iterable: i1, body: b1 },
+                Stmt::ForIn { keyword: k2, label: l2, index: x2, name: n2, iterable: i2, body: b2 },
+            ) => k1.same_as(k2) && same(l1, l2) && same(x1, x2) && n1.same_as(n2) && i1 == i2 && b1 == b2,
+            (
+                Stmt::Class { name: n1, methods: m1, superclass: s1 },
+                Stmt::Class { name: n2, methods: m2, superclass: s2 },
+            ) => n1.same_as(n2) && m1 == m2 && s1 == s2,
+            (
+                Stmt::TryCatch { keyword: k1, body: b1, error_name: e1, handler: h1 },
+                Stmt::TryCatch { keyword: k2, body: b2, error_name: e2, handler: h2 },
+            ) => k1.same_as(k2) && b1 == b2 && e1.same_as(e2) && h1 == h2,
+            (Stmt::Throw { keyword: k1, value: v1 }, Stmt::Throw { keyword: k2, value: v2 }) => {
+                k1.same_as(k2) && v1 == v2
+            }
+            (
+                Stmt::Import { keyword: k1, path: p1, alias: a1 },
+                Stmt::Import { keyword: k2, path: p2, alias: a2 },
+            ) => k1.same_as(k2) && p1.same_as(p2) && same(a1, a2),
+            _ => false,
+        }
+    }
 }
 
 impl Stmt {
@@ -89,12 +247,20 @@ impl Stmt {
             Stmt::Var { .. } => visitor.visit_var_stmt(self),
             Stmt::Block { statements } => visitor.visit_block_stmt(statements),
             Stmt::If { conditional, consequent, alternative } => visitor.visit_if_stmt(conditional, consequent, alternative),
-            Stmt::While {condition, body} => visitor.visit_while_stmt(condition, body),
+            Stmt::While {keyword, label, condition, body, increment, else_branch} => visitor.visit_while_stmt(keyword, label, condition, body, increment, else_branch),
+            Stmt::Continue {keyword, label} => visitor.visit_continue_stmt(keyword, label),
+            Stmt::Break {keyword, label} => visitor.visit_break_stmt(keyword, label),
+            Stmt::Repeat {keyword, label, count, body} => visitor.visit_repeat_stmt(keyword, label, count, body),
+            Stmt::DoWhile {keyword, label, body, condition} => visitor.visit_do_while_stmt(keyword, label, body, condition),
+            Stmt::ForIn {keyword, label, index, name, iterable, body} => visitor.visit_for_in_stmt(keyword, label, index, name, iterable, body),
             Stmt::Function {
-                name, params, body
-            } => visitor.visit_fun_stmt(name, params, body),
+                name, params, param_types, body, return_type, id
+            } => visitor.visit_fun_stmt(name, params, param_types, body, return_type, *id),
             Stmt::Return {keyword, value} => visitor.visit_return_stmt(keyword, value),
             Stmt::Class {name, methods, superclass} => visitor.visit_class_stmt(name, methods, superclass),
+            Stmt::TryCatch {keyword, body, error_name, handler} => visitor.visit_try_catch_stmt(keyword, body, error_name, handler),
+            Stmt::Throw {keyword, value} => visitor.visit_throw_stmt(keyword, value),
+            Stmt::Import {keyword, path, alias} => visitor.visit_import_stmt(keyword, path, alias),
         }
     }
 }
@@ -0,0 +1,168 @@
+use std::mem;
+use crate::lexer::Literal;
+use crate::parser::{Expr, Stmt};
+
+/*
+Dropping a tree the way the compiler would, each node dropping its children before returning,
+recurses once per level. A generated `0 + 1 + 1 + ...` of 200000 terms is a left spine that deep,
+which parses and evaluates without recursing, and would then overflow the stack just being thrown
+away. So expressions and statements drop iteratively instead: a node's boxed and listed children
+are moved out onto a work stack, leaving cheap leaves in their place, and taken apart from there
+one at a time. By the time a node itself is dropped it has no children left, so its own drop has
+nothing to do.
+*/
+
+// a subtree waiting on the work stack to be taken apart
+enum Pending {
+    Expr(Expr),
+    Stmt(Stmt),
+}
+
+/*
+Move an expression out of its place in a tree, leaving a leaf behind. A type with a `Drop` can't
+be taken apart with a by-value `match`, so the parser uses this when it rebuilds a node out of the
+parts of another, as `a.b = c` does out of `a.b`.
+*/
+pub(crate) fn take(slot: &mut Expr) -> Expr {
+    mem::replace(slot, Expr::Literal { value: Literal::Nil })
+}
+
+fn take_expr(slot: &mut Expr, pending: &mut Vec<Pending>) {
+    pending.push(Pending::Expr(take(slot)));
+}
+
+fn take_stmt(slot: &mut Stmt, pending: &mut Vec<Pending>) {
+    pending.push(Pending::Stmt(mem::replace(slot, Stmt::Block { statements: Vec::new() })));
+}
+
+fn take_exprs(exprs: &mut Vec<Expr>, pending: &mut Vec<Pending>) {
+    pending.extend(exprs.drain(..).map(Pending::Expr));
+}
+
+fn take_stmts(stmts: &mut Vec<Stmt>, pending: &mut Vec<Pending>) {
+    pending.extend(stmts.drain(..).map(Pending::Stmt));
+}
+
+fn take_expr_children(expr: &mut Expr, pending: &mut Vec<Pending>) {
+    match expr {
+        Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+            take_expr(left, pending);
+            take_expr(right, pending);
+        }
+        Expr::Grouping { expression } => take_expr(expression, pending),
+        Expr::Unary { right, .. } => take_expr(right, pending),
+        Expr::Assign { value, .. } => take_expr(value, pending),
+        Expr::Call { callee, arguments, .. } => {
+            take_expr(callee, pending);
+            take_exprs(arguments, pending);
+        }
+        Expr::Get { object, .. } => take_expr(object, pending),
+        Expr::Set { object, value, .. } => {
+            take_expr(object, pending);
+            take_expr(value, pending);
+        }
+        Expr::Array { elements } => take_exprs(elements, pending),
+        Expr::Map { entries, .. } => {
+            for (key, value) in entries.drain(..) {
+                pending.push(Pending::Expr(key));
+                pending.push(Pending::Expr(value));
+            }
+        }
+        Expr::Index { object, index, .. } => {
+            take_expr(object, pending);
+            take_expr(index, pending);
+        }
+        Expr::Slice { object, start, end, .. } => {
+            take_expr(object, pending);
+            take_expr(start, pending);
+            take_expr(end, pending);
+        }
+        Expr::IndexSet { object, index, value, .. } => {
+            take_expr(object, pending);
+            take_expr(index, pending);
+            take_expr(value, pending);
+        }
+        Expr::Block { statements, value } => {
+            take_stmts(statements, pending);
+            if let Some(value) = value {
+                take_expr(value, pending);
+            }
+        }
+        Expr::Literal { .. } | Expr::Variable { .. } | Expr::This { .. } | Expr::Super { .. } => {}
+    }
+}
+
+fn take_stmt_children(stmt: &mut Stmt, pending: &mut Vec<Pending>) {
+    match stmt {
+        Stmt::Expression { expression } | Stmt::Print { expression, .. } => take_expr(expression, pending),
+        Stmt::Function { body, .. } => take_stmts(body, pending),
+        Stmt::If { conditional, consequent, alternative } => {
+            take_expr(conditional, pending);
+            take_stmt(consequent, pending);
+            if let Some(alternative) = alternative {
+                take_stmt(alternative, pending);
+            }
+        }
+        Stmt::Return { value: Some(value), .. } | Stmt::Var { initializer: Some(value), .. } => {
+            take_expr(value, pending)
+        }
+        Stmt::Block { statements } => take_stmts(statements, pending),
+        Stmt::While { condition, body, increment, else_branch, .. } => {
+            take_expr(condition, pending);
+            take_stmt(body, pending);
+            if let Some(increment) = increment {
+                take_expr(increment, pending);
+            }
+            if let Some(else_branch) = else_branch {
+                take_stmt(else_branch, pending);
+            }
+        }
+        Stmt::Repeat { count: expr, body, .. }
+        | Stmt::DoWhile { condition: expr, body, .. }
+        | Stmt::ForIn { iterable: expr, body, .. } => {
+            take_expr(expr, pending);
+            take_stmt(body, pending);
+        }
+        Stmt::Class { methods, superclass, .. } => {
+            pending.extend(methods.drain(..).flatten().map(Pending::Stmt));
+            if let Some(superclass) = superclass {
+                take_expr(superclass, pending);
+            }
+        }
+        Stmt::TryCatch { body, handler, .. } => {
+            take_stmts(body, pending);
+            take_stmts(handler, pending);
+        }
+        Stmt::Throw { value, .. } => take_expr(value, pending),
+        Stmt::Return { value: None, .. }
+        | Stmt::Var { initializer: None, .. }
+        | Stmt::Continue { .. }
+        | Stmt::Break { .. }
+        | Stmt::Import { .. } => {}
+    }
+}
+
+fn take_apart(mut pending: Vec<Pending>) {
+    while let Some(node) = pending.pop() {
+        match node {
+            Pending::Expr(mut expr) => take_expr_children(&mut expr, &mut pending),
+            Pending::Stmt(mut stmt) => take_stmt_children(&mut stmt, &mut pending),
+        }
+    }
+}
+
+impl Drop for Expr {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        take_expr_children(self, &mut pending);
+        take_apart(pending);
+    }
+}
+
+impl Drop for Stmt {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        take_stmt_children(self, &mut pending);
+        take_apart(pending);
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/*
+A small pseudo-random number generator for the `random`, `randomInt` and `randomSeed` natives.
+
+Lox programs only need randomness for things like simulations, games and shuffling, so this is
+xorshift64* rather than anything cryptographically strong: a single 64-bit word of state, a few
+shifts and a multiply per number, and statistically good enough for the job. Writing it ourselves
+keeps the crate free of dependencies.
+
+Each evaluator owns its own generator, so two interpreters never draw from the same sequence. A
+fresh generator is seeded from the clock; `randomSeed(n)` reseeds it so that a run can be
+repeated exactly. Seeds are first passed through splitmix64, because xorshift cannot leave the
+all-zero state and gives poor first outputs for small seeds like 1, 2 and 3, which are exactly
+the seeds people pick.
+*/
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.seed(seed);
+        rng
+    }
+
+    /// A generator seeded from the current time, for runs that don't ask for a seed.
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(nanos)
+    }
+
+    pub fn seed(&mut self, seed: u64) {
+        // splitmix64 of the seed; it maps no input to zero except one, which we avoid below
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        self.state = if z == 0 { 0x9E37_79B9_7F4A_7C15 } else { z };
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A float in [0, 1), using the top 53 bits so every value is exactly representable.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A whole number in the inclusive range `lo..=hi`. The caller makes sure `lo <= hi`.
+    pub fn next_in_range(&mut self, lo: i64, hi: i64) -> i64 {
+        // the span of the full i64 range is 2^64, one more than a u64 holds, hence the u128s.
+        // Scaling a 64-bit random number by it keeps the top 64 bits, which lie in `0..span`
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        let offset = (self.next_u64() as u128 * span) >> 64;
+        (lo as i128 + offset as i128) as i64
+    }
+}
@@ -1,7 +1,7 @@
 use crate::interpreter::Interpreter; // Assuming Interpreter is the same as Evaluator
-use crate::parser::{parser, Expr, ParseError, Visitor}; // Importing the Expr and Stmt enums
+use crate::parser::{Expr, ExprId, ParseError, Visitor}; // Importing the Expr and Stmt enums
 use crate::lexer::{Literal};
-use crate::{error, Stmt, StmtVisitor, Token, Value};
+use crate::{error, Severity, Stmt, StmtVisitor, Token, Value};
 use crate::RuntimeError;
 /*
 Since the resolver needs to visit every node in the syntax tree, it implements
@@ -46,15 +46,37 @@ We start at the innermost scope and work outwards, looking in each map for a mat
 If we walk through all of the block scopes and never find the variable, we leave it unresolved and assume it is global. We will get to the implementation of that resolve() later.
 */
 
-use std::collections::HashMap;
-use crate::FunctionType::Initializer;
+use std::collections::{HashMap, HashSet};
 use crate::Value::Nil;
 
 pub struct Resolver<'a> {
     interpreter: &'a mut Interpreter,  // Interpreter is passed as a mutable reference
     scopes: Vec<HashMap<String, bool>>, // Stack of scopes
+    // for each scope in `scopes`, the local variables declared there that nothing has read yet
+    unused: Vec<HashMap<String, Token>>,
+    // for each scope in `scopes`, the names declared there with `const`, which can't be declared again
+    constants: Vec<HashSet<String>>,
     current_function: FunctionType,
     current_class: ClassType,
+    // how many loops enclose the code being resolved, within the current function
+    loop_depth: usize,
+    // the labels of those loops that have one, innermost last
+    loop_labels: Vec<String>,
+    // whether the function being resolved has a `return` with a value in it
+    returns_value: bool,
+    // for each scope in `scopes`, the classes declared there whose methods are all known, see
+    // `visit_class_stmt`. The global scope is not in `scopes`, so its classes get a map of their own
+    class_methods: Vec<HashMap<String, KnownClass>>,
+    global_class_methods: HashMap<String, KnownClass>,
+    // the methods of the superclass of the class being resolved, when they are known
+    superclass_methods: Option<HashSet<String>>,
+}
+
+// a class whose every method the resolver knows, bound to a name that can't be made to hold another
+struct KnownClass {
+    methods: HashSet<String>,
+    // bound by `const`, so the name holds the class wherever it is seen from, not only in its scope
+    constant: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -77,21 +99,50 @@ impl<'a> Resolver<'a> {
         Self {
             interpreter,
             scopes: Vec::new(),
+            unused: Vec::new(),
+            constants: Vec::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            loop_depth: 0,
+            loop_labels: Vec::new(),
+            returns_value: false,
+            class_methods: Vec::new(),
+            global_class_methods: HashMap::new(),
+            superclass_methods: None,
         }
     }
 
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.unused.push(HashMap::new());
+        self.constants.push(HashSet::new());
+        self.class_methods.push(HashMap::new());
     }
 
+    /*
+    Leaving a scope is the last chance to notice a local variable that was declared but never
+    referenced, which is usually a typo or leftover code. That is only a warning: the program is
+    still run. Names starting with an underscore are exempt, for variables unused on purpose.
+    */
     fn end_scope(&mut self) {
         self.scopes.pop();
+        self.constants.pop();
+        self.class_methods.pop();
+        let mut unused: Vec<Token> = self.unused.pop().unwrap_or_default().into_values().collect();
+        // by name within a line, as `for (i, x in ...)` declares two at once and the map has no order
+        unused.sort_by(|a, b| a.line.cmp(&b.line).then_with(|| a.lexeme.cmp(&b.lexeme)));
+        for name in unused {
+            error(
+                &mut self.interpreter.diagnostics,
+                Severity::Warning,
+                name.line,
+                &format!("Local variable '{}' is never used.", name.lexeme),
+            );
+        }
     }
 
     // the resolve statements apply the visitor pattern to the appropriate stmt syntax tree node
-    pub fn resolve_stmt(&mut self, statements: &Vec<Stmt>) {
+    pub fn resolve_stmt(&mut self, statements: &[Stmt]) {
         for stmt in statements {
             self.resolve_stmt_single(stmt); // resolve each statement
         }
@@ -108,9 +159,17 @@ impl<'a> Resolver<'a> {
 
     We set the variable's value in the scope map to true to mark it as fully initialized and ready for use.
     */
-    fn declare(&mut self, name: &str) {
+    fn declare(&mut self, name: &Token) {
+        // a global constant is checked as it is declared again, see `Environment::declare`
+        if let Some(constants) = self.constants.last()
+            && constants.contains(&name.lexeme)
+        {
+            error(&mut self.interpreter.diagnostics, Severity::Error, name.line, &format!("Cannot redeclare constant '{}'.", name.lexeme));
+        }
+        // whatever the name meant in this scope before, it is not that class any more
+        self.current_class_methods().remove(&name.lexeme);
         if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(name.to_string(), false);
+            scope.insert(name.lexeme.clone(), false);
         }
     }
 
@@ -120,12 +179,47 @@ impl<'a> Resolver<'a> {
         }
     }
 
-    fn resolve_local(&mut self, expr: &Expr, name: &Token) {
+    // the map of known classes for the scope being resolved, the global one outside every block
+    fn current_class_methods(&mut self) -> &mut HashMap<String, KnownClass> {
+        match self.class_methods.last_mut() {
+            Some(class_methods) => class_methods,
+            None => &mut self.global_class_methods,
+        }
+    }
+
+    /*
+    The methods of the class `name` holds where it is being used, if they can be known. A class
+    statement is run where it stands, so a class declared in the same scope is the one the name
+    holds, as long as nothing ever assigns to it: that is checked when the class is recorded, see
+    `visit_class_stmt`. From any other scope the name is only known to hold the class when it was
+    bound by `const`. Seen from a function, say, a class in an enclosing scope could have been
+    replaced by the time the function runs.
+    */
+    fn known_methods(&self, name: &str) -> Option<HashSet<String>> {
+        let (class_methods, here) = match self.scopes.iter().rposition(|scope| scope.contains_key(name)) {
+            Some(i) => (&self.class_methods[i], i + 1 == self.scopes.len()),
+            None => (&self.global_class_methods, self.scopes.is_empty()),
+        };
+        class_methods
+            .get(name)
+            .filter(|class| here || class.constant)
+            .map(|class| class.methods.clone())
+    }
+
+    // whether the code may put something else in `name`, so a class declared as it can't be known
+    fn may_reassign(&self, name: &str) -> bool {
+        // an imported file may assign to, or declare again, any global
+        let global = !self.scopes.iter().any(|scope| scope.contains_key(name));
+        self.interpreter.assigned_names.contains(name) || (global && self.interpreter.may_import)
+    }
+
+    fn resolve_local(&mut self, id: ExprId, name: &Token) {
         // Traverse the scopes stack from innermost to outermost
         for (i, scope) in self.scopes.iter().enumerate().rev() {
             if scope.contains_key(&name.lexeme) {
+                self.unused[i].remove(&name.lexeme);
                 // Let the interpreter know how deep the variable is in the scope
-                self.interpreter.resolve(expr, self.scopes.len() - 1 - i);
+                self.interpreter.resolve(id, self.scopes.len() - 1 - i);
                 return;
             }
         }
@@ -138,23 +232,177 @@ impl<'a> Resolver<'a> {
 
     fn resolve_function(
         &mut self,
-        name: &Token,
-        params: &Vec<Token>,
-        body: &Vec<Stmt>,
+        id: ExprId,
+        params: &[Token],
+        body: &[Stmt],
         declaration: FunctionType,
     ) {
+        // functions nest, so remember what we were inside of and put it back when we are done
+        let enclosing_function = std::mem::replace(&mut self.current_function, declaration);
+        // a loop around a function declaration does not make its body part of that loop
+        let enclosing_loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let enclosing_loop_labels = std::mem::take(&mut self.loop_labels);
+        let enclosing_returns_value = std::mem::replace(&mut self.returns_value, false);
         self.begin_scope();
         // Declare parameters as local variables inside the function
         for param in params {
-            self.declare(&param.lexeme);
+            self.declare(param);
             self.define(&param.lexeme);
         }
 
         // Resolve the body of the function
         self.resolve_stmt(body);
 
+        /*
+        A function that returns a value somewhere, but can also reach the end of its body, hands
+        back an implicit nil on that path. That is often a forgotten `return`. Initializers always
+        return `this`, so they are left out. What to do about it is up to the interpreter, which
+        only raises an error in its strict mode.
+        */
+        if self.returns_value && self.current_function != FunctionType::Initializer && !definitely_returns(body) {
+            self.interpreter.mark_implicit_return(id);
+        }
+
         self.end_scope();
+        self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
+        self.loop_labels = enclosing_loop_labels;
+        self.returns_value = enclosing_returns_value;
     }
+
+    // the body of a loop, inside which `break` and `continue` are allowed, naming `label` too
+    fn resolve_loop_body(&mut self, label: &Option<Token>, body: &Stmt) {
+        self.loop_depth += 1;
+        if let Some(label) = label {
+            self.loop_labels.push(label.lexeme.clone());
+        }
+        self.resolve_stmt_single(body);
+        if label.is_some() {
+            self.loop_labels.pop();
+        }
+        self.loop_depth -= 1;
+    }
+
+    // a `break` or `continue` has to be in a loop, and in one with the label it names, if any
+    fn check_loop_jump(&mut self, keyword: &Token, label: &Option<Token>) {
+        if self.loop_depth == 0 {
+            let message = format!("Can't use '{}' outside of a loop.", keyword.lexeme);
+            error(&mut self.interpreter.diagnostics, Severity::Error, keyword.line, &message);
+        } else if let Some(label) = label
+            && !self.loop_labels.contains(&label.lexeme)
+        {
+            let message = format!("No enclosing loop is labeled '{}'.", label.lexeme);
+            error(&mut self.interpreter.diagnostics, Severity::Error, label.line, &message);
+        }
+    }
+}
+
+// Whether running `statements` always ends in a `return`, whatever path is taken. This is
+// deliberately conservative: a loop is never counted, even one that can only exit by returning.
+fn definitely_returns(statements: &[Stmt]) -> bool {
+    statements.iter().any(|stmt| match stmt {
+        // a `throw` never carries on to what follows it either
+        Stmt::Return { .. } | Stmt::Throw { .. } => true,
+        Stmt::Block { statements } => definitely_returns(statements),
+        Stmt::If { consequent, alternative: Some(alternative), .. } => {
+            definitely_returns(std::slice::from_ref(consequent))
+                && definitely_returns(std::slice::from_ref(alternative))
+        }
+        // the body may stop short with an error, after which the handler runs instead
+        Stmt::TryCatch { body, handler, .. } => definitely_returns(body) && definitely_returns(handler),
+        _ => false,
+    })
+}
+
+/*
+Add to `assigned` every name `statements` assign to, anywhere in them, and return whether any of
+them imports a file. A class can only be known to the resolver if nothing ever assigns to its
+name, and an assignment that comes later in the source can still run first, from inside a function
+called before the class is used. So this looks over all of a program before it is resolved.
+
+The tree is walked with a work stack rather than by recursing, as a generated program can nest
+deeply, a long `1 + 1 + ...` chain for one.
+*/
+pub(crate) fn scan_assignments(statements: &[Stmt], assigned: &mut HashSet<String>) -> bool {
+    enum Node<'t> {
+        Expr(&'t Expr),
+        Stmt(&'t Stmt),
+    }
+    let mut imports = false;
+    let mut pending: Vec<Node> = statements.iter().map(Node::Stmt).collect();
+    while let Some(node) = pending.pop() {
+        match node {
+            Node::Expr(expr) => match expr {
+                Expr::Assign { name, value, .. } => {
+                    assigned.insert(name.lexeme.clone());
+                    pending.push(Node::Expr(value));
+                }
+                Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                    pending.extend([Node::Expr(left), Node::Expr(right)]);
+                }
+                Expr::Grouping { expression: inner } | Expr::Unary { right: inner, .. } | Expr::Get { object: inner, .. } => {
+                    pending.push(Node::Expr(inner));
+                }
+                Expr::Call { callee, arguments, .. } => {
+                    pending.push(Node::Expr(callee));
+                    pending.extend(arguments.iter().map(Node::Expr));
+                }
+                Expr::Set { object, value, .. } | Expr::Index { object, index: value, .. } => {
+                    pending.extend([Node::Expr(object), Node::Expr(value)]);
+                }
+                Expr::Slice { object, start: first, end: second, .. }
+                | Expr::IndexSet { object, index: first, value: second, .. } => {
+                    pending.extend([Node::Expr(object), Node::Expr(first), Node::Expr(second)]);
+                }
+                Expr::Array { elements } => pending.extend(elements.iter().map(Node::Expr)),
+                Expr::Map { entries, .. } => {
+                    for (key, value) in entries {
+                        pending.extend([Node::Expr(key), Node::Expr(value)]);
+                    }
+                }
+                Expr::Block { statements, value } => {
+                    pending.extend(statements.iter().map(Node::Stmt));
+                    pending.extend(value.as_deref().map(Node::Expr));
+                }
+                Expr::Literal { .. } | Expr::Variable { .. } | Expr::This { .. } | Expr::Super { .. } => {}
+            },
+            Node::Stmt(stmt) => match stmt {
+                Stmt::Expression { expression } | Stmt::Print { expression, .. } | Stmt::Throw { value: expression, .. } => {
+                    pending.push(Node::Expr(expression));
+                }
+                Stmt::Return { value, .. } | Stmt::Var { initializer: value, .. } => {
+                    pending.extend(value.as_deref().map(Node::Expr));
+                }
+                Stmt::Function { body, .. } | Stmt::Block { statements: body } => {
+                    pending.extend(body.iter().map(Node::Stmt));
+                }
+                Stmt::If { conditional, consequent, alternative } => {
+                    pending.extend([Node::Expr(conditional), Node::Stmt(consequent)]);
+                    pending.extend(alternative.as_deref().map(Node::Stmt));
+                }
+                Stmt::While { condition, body, increment, else_branch, .. } => {
+                    pending.extend([Node::Expr(condition), Node::Stmt(body)]);
+                    pending.extend(increment.as_deref().map(Node::Expr));
+                    pending.extend(else_branch.as_deref().map(Node::Stmt));
+                }
+                Stmt::Repeat { count: expr, body, .. }
+                | Stmt::DoWhile { condition: expr, body, .. }
+                | Stmt::ForIn { iterable: expr, body, .. } => {
+                    pending.extend([Node::Expr(expr), Node::Stmt(body)]);
+                }
+                Stmt::Class { methods, superclass, .. } => {
+                    pending.extend(methods.iter().flatten().map(Node::Stmt));
+                    pending.extend(superclass.as_deref().map(Node::Expr));
+                }
+                Stmt::TryCatch { body, handler, .. } => {
+                    pending.extend(body.iter().chain(handler).map(Node::Stmt));
+                }
+                Stmt::Import { .. } => imports = true,
+                Stmt::Continue { .. } | Stmt::Break { .. } => {}
+            },
+        }
+    }
+    imports
 }
 
 // Implementing StmtVisitor for Resolver
@@ -167,7 +415,7 @@ impl<'a> StmtVisitor<Result<(), RuntimeError>> for Resolver<'a> {
     }
 
     fn visit_print_stmt(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
-        if let Stmt::Print { expression } = stmt {
+        if let Stmt::Print { expression, .. } = stmt {
             self.resolve_expr(expression)?;
         }
         Ok(())
@@ -176,17 +424,32 @@ impl<'a> StmtVisitor<Result<(), RuntimeError>> for Resolver<'a> {
     // Resolving a variable declaration adds a new entry to the current innermost scope's map. We split the binding into two steps: Declaration and definition.
 
     fn visit_var_stmt(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
-        if let Stmt::Var { name, initializer, .. } = stmt {
-            self.declare(&name.lexeme);  // Declare the variable
+        if let Stmt::Var { name, initializer, constant } = stmt {
+            self.declare(name);  // Declare the variable
             if let Some(init) = initializer {
                 self.resolve_expr(init)?; // Resolve initializer expression
             }
             self.define(&name.lexeme);  // Define the variable
+            if *constant && let Some(constants) = self.constants.last_mut() {
+                constants.insert(name.lexeme.clone());
+            }
+            // `const Base = Shape;` holds that class for good, wherever it is seen from
+            if *constant
+                && let Some(Expr::Variable { name: class_name, .. }) = initializer.as_deref()
+                && let Some(methods) = self.known_methods(&class_name.lexeme)
+            {
+                self.current_class_methods().insert(name.lexeme.clone(), KnownClass { methods, constant: true });
+            }
+            if let Some(unused) = self.unused.last_mut()
+                && !name.lexeme.starts_with('_')
+            {
+                unused.insert(name.lexeme.clone(), name.clone());
+            }
         }
         Ok(())
     }
 
-    fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> Result<(), RuntimeError> {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
         self.begin_scope();
         for stmt in statements {
             self.resolve_stmt_single(stmt);
@@ -211,45 +474,124 @@ impl<'a> StmtVisitor<Result<(), RuntimeError>> for Resolver<'a> {
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<(), RuntimeError> {
+    fn visit_while_stmt(
+        &mut self,
+        _keyword: &Token,
+        label: &Option<Token>,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Box<Expr>>,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> Result<(), RuntimeError> {
         self.resolve_expr(condition)?;
-        self.resolve_stmt_single(body);
+        self.resolve_loop_body(label, body);
+        // the increment runs outside the body, in the same scope as the condition
+        if let Some(increment) = increment {
+            self.resolve_expr(increment)?;
+        }
+        // the else branch runs instead of the loop, not in it: a `break` there is not this loop's
+        if let Some(else_branch) = else_branch {
+            self.resolve_stmt_single(else_branch);
+        }
         Ok(())
     }
 
-    fn visit_fun_stmt(
-        &mut self,
-        name: &Token,
-        params: &Vec<Token>,
-        body: &Vec<Stmt>
-    ) -> Result<(), RuntimeError> {
-        // Declare and define the function name in the current scope.
-        self.declare(&name.lexeme);
-        self.define(&name.lexeme);
+    fn visit_continue_stmt(&mut self, keyword: &Token, label: &Option<Token>) -> Result<(), RuntimeError> {
+        self.check_loop_jump(keyword, label);
+        Ok(())
+    }
 
-        // Begin a new scope for the function body.
+    fn visit_repeat_stmt(&mut self, _keyword: &Token, label: &Option<Token>, count: &Expr, body: &Stmt) -> Result<(), RuntimeError> {
+        self.resolve_expr(count)?;
+        self.resolve_loop_body(label, body);
+        Ok(())
+    }
+
+    // the error variable is scoped to the handler, and may go unused: it is there because the
+    // syntax asks for it
+    fn visit_try_catch_stmt(&mut self, _keyword: &Token, body: &[Stmt], error_name: &Token, handler: &[Stmt]) -> Result<(), RuntimeError> {
+        self.begin_scope();
+        body.iter().for_each(|stmt| self.resolve_stmt_single(stmt));
+        self.end_scope();
         self.begin_scope();
+        self.declare(error_name);
+        self.define(&error_name.lexeme);
+        handler.iter().for_each(|stmt| self.resolve_stmt_single(stmt));
+        self.end_scope();
+        Ok(())
+    }
 
-        // Declare and define each function parameter in the new scope.
-        for param in params {
-            self.declare(&param.lexeme);
-            self.define(&param.lexeme);
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) -> Result<(), RuntimeError> {
+        self.resolve_expr(value)?;
+        Ok(())
+    }
+
+    // what an import declares is only known once it runs, so like any global it is not tracked here
+    fn visit_import_stmt(&mut self, keyword: &Token, _path: &Token, _alias: &Option<Token>) -> Result<(), RuntimeError> {
+        if !self.scopes.is_empty() {
+            error(&mut self.interpreter.diagnostics, Severity::Error, keyword.line, "Can only import at the top level.");
         }
+        Ok(())
+    }
 
-        // Resolve the statements (body) of the function in the new scope.
-        self.resolve_stmt(body);
+    fn visit_do_while_stmt(&mut self, _keyword: &Token, label: &Option<Token>, body: &Stmt, condition: &Expr) -> Result<(), RuntimeError> {
+        self.resolve_loop_body(label, body);
+        self.resolve_expr(condition)?;
+        Ok(())
+    }
 
-        // End the function's scope.
+    // the loop variable lives in a scope of its own around the body, fresh for every element
+    fn visit_for_in_stmt(&mut self, _keyword: &Token, label: &Option<Token>, index: &Option<Token>, name: &Token, iterable: &Expr, body: &Stmt) -> Result<(), RuntimeError> {
+        self.resolve_expr(iterable)?;
+        self.begin_scope();
+        for name in index.iter().chain([name]) {
+            self.declare(name);
+            self.define(&name.lexeme);
+            if let Some(unused) = self.unused.last_mut()
+                && !name.lexeme.starts_with('_')
+            {
+                unused.insert(name.lexeme.clone(), name.clone());
+            }
+        }
+        self.resolve_loop_body(label, body);
         self.end_scope();
+        Ok(())
+    }
+
+    fn visit_break_stmt(&mut self, keyword: &Token, label: &Option<Token>) -> Result<(), RuntimeError> {
+        self.check_loop_jump(keyword, label);
+        Ok(())
+    }
+
+    fn visit_fun_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        _param_types: &[Option<Token>],
+        body: &[Stmt],
+        _return_type: &Option<Token>,
+        id: ExprId,
+    ) -> Result<(), RuntimeError> {
+        // Declare and define the function name in the current scope before resolving the body,
+        // so that the function can refer to itself recursively.
+        self.declare(name);
+        self.define(&name.lexeme);
+
+        self.resolve_function(id, params, body, FunctionType::Function);
 
         Ok(())
     }
 
     fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Box<Expr>>) -> Result<(), RuntimeError> {
+        // a bare `return;` in top-level code stops the script, but there is nothing to hand a value to
+        if self.current_function == FunctionType::None && value.is_some() {
+            error(&mut self.interpreter.diagnostics, Severity::Error, keyword.line, "Can't return a value from top-level code.");
+        }
         if let Some(v) = value {
             if self.current_function == FunctionType::Initializer {
-                error(keyword.line, "Can't return a value from an initializer.")
+                error(&mut self.interpreter.diagnostics, Severity::Error, keyword.line, "Can't return a value from an initializer.")
             }
+            self.returns_value = true;
             self.resolve_expr(v)?;
         }
         Ok(())
@@ -258,42 +600,49 @@ impl<'a> StmtVisitor<Result<(), RuntimeError>> for Resolver<'a> {
 
     /* declaring a class as a local variable here
     If the class declaration has a superclass, we create a new scope surrounding all of its methods. In that scope, we define the name "super". Once we are done resolving that class's methods, we discard that scope.
+
+    The resolver also remembers the method names of every class it has seen, as long as it can know
+    all of them: that takes a class whose superclass, if it has one, is a variable naming another
+    class known the same way, see `known_methods`. That is what lets `super.x()` be checked before
+    anything runs, with "Superclass has no method 'x'." when the superclass really can't have one.
+    A class whose name is assigned to anywhere in the program, even in code that comes after it, in
+    a function that might run first, could be any class, and is not recorded; one declared again
+    in the same scope is forgotten from there on. When the superclass is not known, the check is
+    left to the interpreter, which makes it when `super.x` is evaluated.
     */
     fn visit_class_stmt(
         &mut self,
         name: &Token,
-        methods: &Vec<Result<Stmt, ParseError>>,
+        methods: &[Result<Stmt, ParseError>],
         superclass: &Option<Box<Expr>>
     ) -> Result<(), RuntimeError> {
         /*
         We store the previous value of the field in a local variable.
         */
-        let enclosing_class = &self.current_class;
-        self.current_class = ClassType::Class;
+        let enclosing_class = std::mem::replace(&mut self.current_class, ClassType::Class);
         // Declare the class in the current scope
-        self.declare(&name.lexeme);
+        self.declare(name);
         self.define(&name.lexeme);
 
+        // the methods `super` can reach, if we can tell what they are
+        let inherited = match superclass.as_deref() {
+            Some(Expr::Variable { name: superclass_name, .. }) => self.known_methods(&superclass_name.lexeme),
+            Some(_) => None,
+            None => Some(HashSet::new()),
+        };
+        let enclosing_superclass_methods = std::mem::replace(&mut self.superclass_methods, inherited.clone());
+
         if let Some(superclass_expr) = superclass {
             // Ensure that a class can't inherit from itself
-            if let Expr::Variable { name: superclass_name, .. } = &**superclass_expr {
-                if name.lexeme == superclass_name.lexeme {
-                    return Err(RuntimeError::new(
-                        superclass_name.clone(),
-                        "A class cannot inherit from itself.".to_string(),
-                    ));
-                }
+            if let Expr::Variable { name: superclass_name, .. } = &**superclass_expr
+                && name.lexeme == superclass_name.lexeme
+            {
+                error(&mut self.interpreter.diagnostics, Severity::Error, superclass_name.line, "A class can't inherit from itself.");
             }
+            self.current_class = ClassType::Subclass;
             // Resolve the superclass expression
             self.resolve_expr(superclass_expr)?;
-        }
 
-        if let Some(superclass) = superclass {
-            self.current_class = ClassType::Subclass;
-            self.resolve_expr(superclass).expect("TODO: panic message");
-        }
-
-        if let Some(superclass) = superclass {
             self.begin_scope();  // Start a new scope
             self.scopes
                 .last_mut()  // Access the current scope (mutably)
@@ -307,13 +656,17 @@ impl<'a> StmtVisitor<Result<(), RuntimeError>> for Resolver<'a> {
 
         // Resolve methods inside the class
         for method in methods {
-            if let Ok(Stmt::Function { name, params, body }) = method {
+            if let Ok(Stmt::Function { name, params, body, return_type, id, .. }) = method {
                 let mut declaration = FunctionType::Method;
                 // Resolve the method (similar to the visitFunctionStmt method)
                 if name.lexeme.eq("init") {
                     declaration = FunctionType::Initializer;
+                    // an initializer always returns `this`, so there is nothing to declare
+                    if let Some(return_type) = return_type {
+                        error(&mut self.interpreter.diagnostics, Severity::Error, return_type.line, "Can't declare a return type for an initializer.");
+                    }
                 }
-                self.resolve_function(&name, &params, &body, declaration);
+                self.resolve_function(*id, params, body, declaration);
             }
         }
 
@@ -322,7 +675,21 @@ impl<'a> StmtVisitor<Result<(), RuntimeError>> for Resolver<'a> {
         if superclass.is_some() {
             self.end_scope();  // End the scope created for "super"
         }
-        self.current_class = ClassType::None;
+        self.current_class = enclosing_class;
+        self.superclass_methods = enclosing_superclass_methods;
+
+        // a class has its own methods and everything it inherits
+        if let Some(mut all_methods) = inherited
+            && !self.may_reassign(&name.lexeme)
+        {
+            for method in methods {
+                if let Ok(Stmt::Function { name, .. }) = method {
+                    all_methods.insert(name.lexeme.clone());
+                }
+            }
+            let class = KnownClass { methods: all_methods, constant: false };
+            self.current_class_methods().insert(name.lexeme.clone(), class);
+        }
 
         Ok(())
     }
@@ -348,39 +715,46 @@ impl<'a> Visitor for Resolver<'a> {
         _operator: &Token,
         right: &Expr,
     ) -> Result<Value, RuntimeError> {
-        self.resolve_expr(left)?;
-        self.resolve_expr(right)
-    }
-
-    fn visit_variable_expr(&mut self, token: &Token, initializer: &Option<Box<Expr>>) -> Result<Value, RuntimeError> {
-        // If we're referencing a variable in its own initializer, throw an error
-        if self.scopes.last().unwrap().get(&token.lexeme).map_or(false, |&v| !v) {
-            return Err(RuntimeError::new(
-                token.clone(),
-                format!("Can't read local variable in its own initializer."),
-            ));
+        // a long chain like `1 + 2 + 3 + ...` hangs off the left, so walk down it with a loop
+        // rather than recursing once per operator, the same as the evaluator does
+        let mut rights = vec![right];
+        let mut innermost = left;
+        loop {
+            match innermost {
+                Expr::Binary { left, right, .. } => {
+                    rights.push(right);
+                    innermost = left;
+                }
+                Expr::Grouping { expression } => innermost = expression,
+                _ => break,
+            }
         }
-
-        // Check if it's declared and resolved
-        if let Some(init) = initializer {
-            self.resolve_local(init, token);
+        self.resolve_expr(innermost)?;
+        for right in rights.into_iter().rev() {
+            self.resolve_expr(right)?;
         }
+        Ok(Value::Nil)
+    }
 
-        // If it has an initializer, resolve that as well
-        if let Some(init_expr) = initializer {
-            self.resolve_expr(&init_expr)?;  // Resolve the expression inside the initializer
+    fn visit_variable_expr(&mut self, token: &Token, id: ExprId) -> Result<Value, RuntimeError> {
+        // If we're referencing a variable in its own initializer, report an error
+        // (the global scope is not tracked, so there is nothing to check there)
+        if self.scopes.last().and_then(|scope| scope.get(&token.lexeme)).is_some_and(|&v| !v) {
+            error(&mut self.interpreter.diagnostics, Severity::Error, token.line, "Can't read local variable in its own initializer.");
         }
 
+        self.resolve_local(id, token);
+
         Ok(Value::Nil)  // Not necessary to return a value here, it's for the resolution
     }
 
     // we resolve the expression for the assigned value in case it also contains references to other variables. Then we use our existing resolve local method top resolve the variable that's being assigned to
-    fn visit_assign_expr(&mut self, token: &Token, value: &Expr) -> Result<Value, RuntimeError> {
+    fn visit_assign_expr(&mut self, token: &Token, value: &Expr, id: ExprId) -> Result<Value, RuntimeError> {
         // Resolve the value that the variable is being assigned
         self.resolve_expr(value)?;
 
         // Resolve the variable being assigned to
-        self.resolve_local(value, token);
+        self.resolve_local(id, token);
 
         Ok(Value::Nil)  // Not necessary to return a value here either
     }
@@ -391,16 +765,28 @@ impl<'a> Visitor for Resolver<'a> {
         _operator: &Token,
         right: &Expr,
     ) -> Result<Value, RuntimeError> {
-        self.resolve_expr(left)?;
-        self.resolve_expr(right)
+        let mut rights = vec![right];
+        let mut innermost = left;
+        while let Expr::Logical { left, right, .. } = innermost {
+            rights.push(right);
+            innermost = left;
+        }
+        self.resolve_expr(innermost)?;
+        for right in rights.into_iter().rev() {
+            self.resolve_expr(right)?;
+        }
+        Ok(Value::Nil)
     }
 
     fn visit_call_expr(
         &mut self,
         callee: &Expr,
+        _open_paren: &Token,
         _paren: &Token,
         arguments: &[Expr],
+        _names: &[Option<Token>],
     ) -> Result<Value, RuntimeError> {
+        // argument names are matched against parameters when the call runs, nothing to resolve
         self.resolve_expr(callee)?;
         for arg in arguments {
             self.resolve_expr(arg)?;
@@ -409,41 +795,103 @@ impl<'a> Visitor for Resolver<'a> {
     }
 
 
-    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<Value, RuntimeError> {
+    fn visit_get_expr(&mut self, object: &Expr, _name: &Token, _optional: bool, _id: ExprId) -> Result<Value, RuntimeError> {
         // since properties are looked up dynamically, they do not need to get resolved
         // During resolution, we recurse only into the expression to the left of the dot. The actual property access happens in the interpreter.
         self.resolve_expr(object)
     }
 
-    fn visit_set_expr(&mut self, object: &Expr, name: &Token, value: &Expr) -> Result<Value, RuntimeError> {
-        self.resolve_expr(value).expect("TODO: panic message");
+    fn visit_set_expr(&mut self, object: &Expr, _name: &Token, value: &Expr) -> Result<Value, RuntimeError> {
+        self.resolve_expr(value)?;
         self.resolve_expr(object)
     }
 
-    fn visit_this_expr(&mut self, this: &Token) -> Result<Value, RuntimeError> {
+    fn visit_this_expr(&mut self, this: &Token, id: ExprId) -> Result<Value, RuntimeError> {
         if self.current_class == ClassType::None {
-            error(this.line,"Can't use 'this' outside of a class.")
+            error(&mut self.interpreter.diagnostics, Severity::Error, this.line,"Can't use 'this' outside of a class.");
+            return Ok(Nil);
         }
-        self.resolve_local(&Expr::This { keyword: this.clone() }, this);
+        self.resolve_local(id, this);
         Ok(Nil)
     }
 
     /*
     It is a minor optimization, but we only create the superclass environment if the class actually has a superclass. There is no point in creating it when there is not a superclass since there would be no superclass to store in it anyway.
     */
-    fn visit_super_expr(&mut self, keyword: &Token, method: &Token) -> Result<Value, RuntimeError> {
-        let dummy_expr = Expr::Literal {
-            value: Literal::Nil, // You can use any placeholder value here
-        };
-        
-        if self.current_class == ClassType::None { 
-            error(keyword.line, "Can't use 'super' outside of a class.")
+    fn visit_super_expr(&mut self, keyword: &Token, method: &Token, id: ExprId) -> Result<Value, RuntimeError> {
+        if self.current_class == ClassType::None {
+            error(&mut self.interpreter.diagnostics, Severity::Error, keyword.line, "Can't use 'super' outside of a class.");
+            return Ok(Value::Nil);
         } else if self.current_class != ClassType::Subclass {
-            error(keyword.line, "Can't use 'super' in a class with no superclass.")
+            error(&mut self.interpreter.diagnostics, Severity::Error, keyword.line, "Can't use 'super' in a class with no superclass.");
+            return Ok(Value::Nil);
+        }
+
+        if let Some(methods) = &self.superclass_methods
+            && !methods.contains(&method.lexeme)
+        {
+            error(&mut self.interpreter.diagnostics, Severity::Error, method.line, &format!("Superclass has no method '{}'.", method.lexeme));
         }
 
         // Resolve the "super" expression
-        self.resolve_local(&dummy_expr, keyword);
+        self.resolve_local(id, keyword);
+        Ok(Value::Nil)
+    }
+
+    fn visit_array_expr(&mut self, elements: &[Expr]) -> Result<Value, RuntimeError> {
+        for element in elements {
+            self.resolve_expr(element)?;
+        }
         Ok(Value::Nil)
     }
+
+    // the same as a block statement, with the value resolved in the block's scope
+    fn visit_block_expr(&mut self, statements: &[Stmt], value: Option<&Expr>) -> Result<Value, RuntimeError> {
+        self.begin_scope();
+        for stmt in statements {
+            self.resolve_stmt_single(stmt);
+        }
+        if let Some(value) = value {
+            self.resolve_expr(value)?;
+        }
+        self.end_scope();
+        Ok(Value::Nil)
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> Result<Value, RuntimeError> {
+        for (key, value) in entries {
+            self.resolve_expr(key)?;
+            self.resolve_expr(value)?;
+        }
+        Ok(Value::Nil)
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> Result<Value, RuntimeError> {
+        self.resolve_expr(object)?;
+        self.resolve_expr(index)
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        start: &Expr,
+        end: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        self.resolve_expr(object)?;
+        self.resolve_expr(start)?;
+        self.resolve_expr(end)
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        self.resolve_expr(object)?;
+        self.resolve_expr(index)?;
+        self.resolve_expr(value)
+    }
 }
\ No newline at end of file
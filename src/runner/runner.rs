@@ -1,32 +1,141 @@
 use std::borrow::Cow;
-use std::{fs, io};
+use std::{fmt, fs, io};
+use std::path::Path;
 use std::io::Write;
-use std::sync::atomic::{AtomicBool, Ordering};
-use crate::{Interpreter, Parser, Scanner, Token};
+use std::time::{Duration, Instant};
+use crate::{runtime_error, AstPrinter, Diagnostics, Interpreter, Literal, Parser, Scanner, Stmt, Token, TokenType};
 
-pub static HAD_ERROR: AtomicBool = AtomicBool::new(false);
-pub static HAD_RUNTIMES: AtomicBool = AtomicBool::new(false);
+/*
+How long each phase of a run took, for `jlox --time script.lox`. The times are wall-clock, taken
+with `Instant`, so they include whatever the program spends waiting, `sleepMs` included. A phase
+that never ran, like evaluating after a syntax error, is reported as zero.
+*/
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PhaseTimings {
+    pub scanning: Duration,
+    pub parsing: Duration,
+    pub resolving: Duration,
+    pub evaluating: Duration,
+}
+
+impl PhaseTimings {
+    /// The time spent in all four phases together.
+    pub fn total(&self) -> Duration {
+        self.scanning + self.parsing + self.resolving + self.evaluating
+    }
+}
+
+// e.g. `scan 1.2ms, parse 3.4ms, resolve 0.8ms, run 152.6ms`
+impl fmt::Display for PhaseTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millis = |phase: Duration| phase.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "scan {:.1}ms, parse {:.1}ms, resolve {:.1}ms, run {:.1}ms",
+            millis(self.scanning),
+            millis(self.parsing),
+            millis(self.resolving),
+            millis(self.evaluating)
+        )
+    }
+}
+
+/*
+What `run_with_report` measured about a run: how long each phase took, and how many statements
+the evaluator executed. A statement is counted every time it runs, so the body of a loop that
+goes round ten times counts ten times, and a block counts once besides its own statements.
+*/
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunReport {
+    pub phase_times: PhaseTimings,
+    pub statements_executed: u64,
+}
 
-pub fn run_file(path: &String) -> () {
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {} statements", self.phase_times, self.statements_executed)
+    }
+}
+
+/*
+What to show of a script instead of running it. `jlox --tokens script.lox` prints what the scanner
+made of the script, one token to a line, and `jlox --ast script.lox` what the parser made of it,
+each top-level statement starting a line of its own, as the AST printer writes it. Either stops
+there, and nothing in the script is run.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpMode {
+    Tokens,
+    Ast,
+}
+
+/*
+Run the script at `path`, with `args` in its global `args`. With `time`, report how long each phase
+took on stderr afterwards. With a `dump` mode, print that instead and don't run the script; the
+timings are not reported then.
+*/
+pub fn run_file(path: &String, args: &[String], time: bool, dump: Option<DumpMode>) {
     let bytes: Vec<u8> = fs::read(path).expect("Failed to read file");
     let source: Cow<str> = String::from_utf8_lossy(&bytes);
-    run(&source.to_string());
 
-    if HAD_ERROR.load(Ordering::Relaxed) {
+    if let Some(mode) = dump {
+        let diagnostics = dump_source(&source, mode, &mut io::stdout());
+        if diagnostics.had_error() {
+            std::process::exit(65);
+        }
+        return;
+    }
+
+    // imports are looked up next to the script
+    let mut interpreter = Interpreter::new();
+    interpreter.set_script_path(Path::new(path));
+    interpreter.set_args(args);
+    let (diagnostics, report) = run_source(&source.to_string(), &mut interpreter, false);
+
+    // on stderr, so the timings don't get mixed into what the program printed
+    if time {
+        eprintln!("{}", report);
+    }
+
+    // only now, with the interpreter gone, is it safe to end the process
+    drop(interpreter);
+    if let Some(code) = diagnostics.exit_code() {
+        std::process::exit(code);
+    }
+
+    if diagnostics.had_error() {
         std::process::exit(65);
     }
 
-    if HAD_RUNTIMES.load(Ordering::Relaxed) {
+    if diagnostics.had_runtime_error() {
         std::process::exit(70);
     }
 }
 
-pub fn run_prompt() -> () {
+/*
+The REPL runs every line in the same interpreter, so what one line defines the next can use:
+
+    > fun counter() { var n = 0; fun next() { n = n + 1; return n; } return next; }
+    > var c = counter();
+    > c()
+    1
+
+A line that leaves a bracket or a string open is continued on the next, under a `.. ` prompt, and
+the lines only run once everything is closed. A blank line gives up on them instead:
+
+    > fun greet(name) {
+    ..   print "hi " + name;
+    .. }
+    > greet("you")
+    hi you
+*/
+pub fn run_prompt() {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
+    let mut repl = Repl::new();
 
     loop {
-        print!("> ");
+        print!("{}", repl.prompt());
         stdout.flush().unwrap();
 
         let mut line: String = String::new();
@@ -36,18 +145,159 @@ pub fn run_prompt() -> () {
             break; // EOF or Control-D
         }
 
-        run(&line);
-        HAD_ERROR.store(false, Ordering::Relaxed);
+        // `exit(code)` ends the program on the line, not the session
+        if let Some(code) = repl.read_line(&line).and_then(|diagnostics| diagnostics.exit_code()) {
+            println!("(exited with code {})", code);
+        }
     }
 }
 
-fn run(source: &String) -> () {
+/// The REPL without the terminal: the interpreter it runs in, and the lines of input it has been
+/// given that are still waiting for the rest of what they started.
+pub struct Repl {
+    interpreter: Interpreter,
+    pending: String,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_args(&[]);
+        Self { interpreter, pending: String::new() }
+    }
+
+    /// `> `, or `.. ` while the lines so far are unfinished.
+    pub fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() { "> " } else { ".. " }
+    }
+
+    /*
+    Take one line of input. Once it finishes what the lines before it started, or starts and
+    finishes something itself, all of them are run together, and the errors reported are handed
+    back. Every run gets fresh diagnostics, so a mistake does not poison the rest of the session.
+    `None` means nothing ran: more lines are needed, or a blank line threw the unfinished ones away.
+    */
+    pub fn read_line(&mut self, line: &str) -> Option<Diagnostics> {
+        if !self.pending.is_empty() && line.trim().is_empty() {
+            self.pending.clear();
+            return None;
+        }
+        self.pending.push_str(line);
+        if !line.ends_with('\n') {
+            self.pending.push('\n');
+        }
+        if Scanner::is_unfinished(&self.pending) {
+            return None;
+        }
+        let source = std::mem::take(&mut self.pending);
+        Some(run_source(&source, &mut self.interpreter, true).0)
+    }
+}
+
+/// Write what `mode` shows of `source` to `out`, returning the errors reported on the way.
+pub fn dump_source(source: &str, mode: DumpMode, out: &mut impl Write) -> Diagnostics {
+    match mode {
+        DumpMode::Tokens => dump_tokens(source, out),
+        DumpMode::Ast => dump_ast(source, out),
+    }
+}
+
+// a lexical error does not stop the scanner, so the tokens around it are still printed
+fn dump_tokens(source: &str, out: &mut impl Write) -> Diagnostics {
+    let mut scanner = Scanner::new(source.to_string());
+    scanner.scan_tokens_with(|token| writeln!(out, "{}", token).expect("Failed to write token"));
+    scanner.take_diagnostics()
+}
+
+// after a syntax error the parser synchronizes and carries on, so the statements it could parse
+// are still printed
+fn dump_ast(source: &str, out: &mut impl Write) -> Diagnostics {
+    let mut scanner = Scanner::new(source.to_string());
+    let mut parser = Parser::new(scanner.scan_tokens().clone());
+    let statements = parser.parse();
+    let mut printer = AstPrinter::new();
+    for statement in &statements {
+        writeln!(out, "{}", printer.print_stmt(statement)).expect("Failed to write statement");
+    }
+    let mut diagnostics = scanner.take_diagnostics();
+    diagnostics.extend(parser.take_diagnostics());
+    diagnostics
+}
+
+/// Scan, parse and interpret `source`, returning every error reported along the way.
+pub fn run(source: &String) -> Diagnostics {
+    run_with_args(source, &[])
+}
+
+/// `run`, with `args` as the program's command-line arguments, as if `jlox script.lox args...`.
+pub fn run_with_args(source: &String, args: &[String]) -> Diagnostics {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_args(args);
+    run_source(source, &mut interpreter, false).0
+}
+
+/// `run`, also measuring how long each phase took and how much work the program did.
+pub fn run_with_report(source: &String) -> (Diagnostics, RunReport) {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_args(&[]);
+    run_source(source, &mut interpreter, false)
+}
+
+// With `echo`, as in the REPL, source that is only an expression, like `1 + 2`, is run as if it
+// were `print 1 + 2;`, so typing an expression shows its value
+fn run_source(source: &String, interpreter: &mut Interpreter, echo: bool) -> (Diagnostics, RunReport) {
+    let mut diagnostics = Diagnostics::new();
+    let mut report = RunReport::default();
+    let timings = &mut report.phase_times;
+
+    let started = Instant::now();
     let mut scanner: Scanner = Scanner::new(source.to_string());
     let tokens: &Vec<Token> = scanner.scan_tokens();
+    timings.scanning = started.elapsed();
 
+    let started = Instant::now();
     let mut parser = Parser::new(tokens.clone());
-    let statements = parser.parse();
+    let statements = if echo && parser.is_bare_expression() {
+        let line = tokens.last().map_or(1, |token| token.line);
+        let keyword = Token::new(TokenType::Print, "print".to_string(), Literal::Nil, line);
+        // on a syntax error there is nothing to run, and the parser has reported it
+        parser
+            .parse_expression()
+            .map(|expr| vec![Stmt::Print { keyword, expression: Box::new(expr) }])
+            .unwrap_or_default()
+    } else {
+        parser.parse()
+    };
+    timings.parsing = started.elapsed();
+    diagnostics.extend(scanner.take_diagnostics());
+    diagnostics.extend(parser.take_diagnostics());
 
-    let mut interpreter = Interpreter::new();
-    interpreter.interpret(statements);
-}
\ No newline at end of file
+    // stop if there was a syntax error, the tree may not be what the program meant
+    if diagnostics.had_error() {
+        return (diagnostics, report);
+    }
+
+    let started = Instant::now();
+    let resolved = interpreter.resolve_program(&statements);
+    timings.resolving = started.elapsed();
+
+    if resolved {
+        let started = Instant::now();
+        let result = interpreter.execute_program(statements);
+        timings.evaluating = started.elapsed();
+        // the interpreter hands the error back, reporting it is up to us
+        if let Err(err) = result {
+            runtime_error(&mut diagnostics, err);
+        }
+    }
+    diagnostics.extend(interpreter.take_diagnostics());
+    report.statements_executed = interpreter.statements_executed();
+
+    (diagnostics, report)
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use crafting_interpreters::{runtime_error, Interpreter, Parser, RuntimeError, Scanner};
+
+#[test]
+fn an_oversized_repetition_is_a_runtime_error() {
+    let run = common::run("print \"a\" * 1000000000;");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.contains("Allocation limit exceeded."), "{}", run.stderr);
+}
+
+#[test]
+fn a_repetition_that_overflows_the_size_is_a_runtime_error() {
+    let run = common::run("print \"abc\" * 9223372036854775807;");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.contains("Allocation limit exceeded."), "{}", run.stderr);
+}
+
+#[test]
+fn repetition_under_the_limit_works_in_either_order() {
+    assert_eq!(common::output("print \"ab\" * 3; print 2 * \"c\";"), "ababab\ncc\n");
+}
+
+#[test]
+fn the_limit_can_be_lowered() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_allocation_limit(8);
+    assert_eq!(interpreter.allocation_limit(), 8);
+
+    let run = |interpreter: &mut Interpreter, source: &str| {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+        let result = interpreter.interpret(Parser::new(tokens).parse());
+        let mut diagnostics = interpreter.take_diagnostics();
+        if let Err(error) = result {
+            runtime_error(&mut diagnostics, error);
+        }
+        diagnostics
+    };
+    assert!(!run(&mut interpreter, "var s = \"ab\" * 4;").had_runtime_error());
+    let diagnostics = run(&mut interpreter, "var s = \"ab\" * 5;");
+    assert!(diagnostics.had_runtime_error());
+    assert!(diagnostics.messages()[0].ends_with("Allocation limit exceeded."));
+    assert!(run(&mut interpreter, "var s = \"abcde\" + \"abcd\";").had_runtime_error());
+}
+
+// the message of the runtime error `source` stops with, if any
+fn limit_error(interpreter: &mut Interpreter, source: &str) -> Option<String> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    match interpreter.interpret(Parser::new(tokens).parse()) {
+        Ok(()) => None,
+        Err(RuntimeError::Error { message, .. }) => Some(message),
+        Err(other) => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn joining_past_the_limit_is_a_runtime_error() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_allocation_limit(8);
+    // three parts and two separators make exactly eight bytes
+    assert_eq!(limit_error(&mut interpreter, "var s = [\"ab\", \"c\", \"d\"].join(\"--\");"), None);
+    assert_eq!(
+        limit_error(&mut interpreter, "var s = [\"ab\", \"cd\", \"ef\"].join(\"--\");"),
+        Some("Allocation limit exceeded.".to_string())
+    );
+}
+
+#[test]
+fn inserting_past_the_limit_is_a_runtime_error() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_allocation_limit(2);
+    assert_eq!(limit_error(&mut interpreter, "var a = [1]; a.insert(0, 2);"), None);
+    assert_eq!(limit_error(&mut interpreter, "a.insert(0, 3);"), Some("Allocation limit exceeded.".to_string()));
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::error_of;
+
+#[test]
+fn functions_are_named() {
+    assert_eq!(error_of("fun f(a) {}\nf();"), "[line 2] RuntimeError at ')': Expected 1 arguments to 'f' but got 0.");
+    assert_eq!(error_of("fun f() {}\nf(1);"), "[line 2] RuntimeError at ')': Expected 0 arguments to 'f' but got 1.");
+}
+
+#[test]
+fn a_tail_call_names_the_function_being_called() {
+    assert_eq!(
+        error_of("fun f(a) { return g(a); }\nfun g(a, b) { return a; }\nf(1);"),
+        "[line 1] RuntimeError at ')': Expected 2 arguments to 'g' but got 1.\n  \
+         in function f (line 1), called from script (line 3)"
+    );
+}
+
+#[test]
+fn classes_are_named_with_or_without_an_initializer() {
+    assert_eq!(
+        error_of("class P { init(x) {} }\nP();"),
+        "[line 2] RuntimeError at ')': Expected 1 arguments to 'P' but got 0."
+    );
+    assert_eq!(error_of("class Q {}\nQ(1);"), "[line 2] RuntimeError at ')': Expected 0 arguments to 'Q' but got 1.");
+}
+
+#[test]
+fn methods_are_named() {
+    assert_eq!(
+        error_of("class C { m(a) {} }\nC().m();"),
+        "[line 2] RuntimeError at ')': Expected 1 arguments to 'm' but got 0."
+    );
+}
+
+#[test]
+fn natives_are_named_as_they_print() {
+    assert_eq!(error_of("len();"), "[line 1] RuntimeError at ')': Expected 1 arguments to <native fn len> but got 0.");
+    assert_eq!(
+        error_of("[].push();"),
+        "[line 1] RuntimeError at ')': Expected 1 arguments to <native fn push> but got 0."
+    );
+}
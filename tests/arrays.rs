@@ -0,0 +1,84 @@
+mod common;
+
+use common::{error_of, output};
+
+#[test]
+fn literals_print_and_index() {
+    assert_eq!(output("var a = [3, 1, 2];\nprint a;\nprint a[0];\nprint a[2];\nprint [];"), "[3, 1, 2]\n3\n2\n[]\n");
+}
+
+#[test]
+fn elements_can_be_assigned() {
+    assert_eq!(output("var a = [1, 2];\na[1] = 5;\nprint a;"), "[1, 5]\n");
+}
+
+#[test]
+fn methods_change_the_array_they_are_bound_to() {
+    let source = "var a = [3, 1, 2];
+a.push(4);
+print len(a);
+a.sort();
+print a;
+print a.pop();
+a.insert(0, 0);
+print a.removeAt(1);
+a.reverse();
+print a;
+print a.indexOf(3);
+print a.indexOf(9);
+print a.contains(2);";
+    assert_eq!(output(source), "4\n[1, 2, 3, 4]\n4\n1\n[3, 2, 0]\n0\n-1\ntrue\n");
+}
+
+#[test]
+fn slice_concat_and_join_make_new_values() {
+    let source = "var a = [1, 2, 3];
+print a.slice(1, 3);
+print a.concat([4]);
+print a.concat(a);
+print a.join(\", \");
+print a;";
+    assert_eq!(output(source), "[2, 3]\n[1, 2, 3, 4]\n[1, 2, 3, 1, 2, 3]\n1, 2, 3\n[1, 2, 3]\n");
+}
+
+#[test]
+fn variables_alias_the_same_array() {
+    let source = "var a = [1];\nvar b = a;\nb.push(2);\nb[0] = 10;\nprint a;\nfun grow(xs) { xs.push(3); }\ngrow(a);\nprint b;";
+    assert_eq!(output(source), "[10, 2]\n[10, 2, 3]\n");
+}
+
+#[test]
+fn popping_an_empty_array_gives_nil() {
+    assert_eq!(output("print [].pop();"), "nil\n");
+}
+
+#[test]
+fn indexing_out_of_range_is_an_error() {
+    assert_eq!(
+        error_of("var a = [1, 2];\nprint a[2];"),
+        "[line 2] RuntimeError at ']': Index 2 is out of range for length 2."
+    );
+    assert_eq!(
+        error_of("print [][-1];"),
+        "[line 1] RuntimeError at ']': Index -1 is out of range for length 0."
+    );
+    assert_eq!(
+        error_of("var a = [1];\na[1] = 2;"),
+        "[line 2] RuntimeError at ']': Index 1 is out of range for length 1."
+    );
+}
+
+#[test]
+fn methods_name_themselves_in_index_errors() {
+    assert_eq!(
+        error_of("var a = [1];\na.removeAt(3);"),
+        "[line 2] RuntimeError at ')': Index 3 is out of range for length 1 (in removeAt())."
+    );
+}
+
+#[test]
+fn sorting_mixed_elements_is_an_error() {
+    let run = common::run("var a = [2, 1, \"a\"];\na.sort();");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.contains("Can't order"), "{}", run.stderr);
+}
@@ -0,0 +1,22 @@
+mod common;
+
+#[test]
+fn assigning_to_this_in_a_method_is_an_error() {
+    let run = common::run("print \"start\";\nclass A {\n  m() {\n    this = 1;\n  }\n}");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stdout, "");
+    assert_eq!(run.stderr, "[line 4 ] Error  at 'this' : Cannot assign to 'this'.\n");
+}
+
+#[test]
+fn assigning_to_this_outside_a_class_is_the_same_error() {
+    let run = common::run("this = 2;");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stderr, "[line 1 ] Error  at 'this' : Cannot assign to 'this'.\n");
+}
+
+#[test]
+fn a_field_of_this_can_still_be_assigned() {
+    let source = "class A { init() { this.x = 1; } }\nprint A().x;";
+    assert_eq!(common::output(source), "1\n");
+}
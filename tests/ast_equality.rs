@@ -0,0 +1,33 @@
+use crafting_interpreters::{Parser, Scanner, Stmt};
+
+fn parse(source: &str) -> Vec<Stmt> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    Parser::new(tokens).parse()
+}
+
+#[test]
+fn the_same_program_laid_out_differently_parses_equal() {
+    let compact = "fun f(a, b) { if (a < b) return a * (b + 1); return nil; }\nclass C < B { m() { return this.x; } }";
+    let spread = "
+// the same, over more lines
+fun f(a,
+      b) {
+  if (a < b)
+    return a * (b + 1);
+  return nil;
+}
+
+class C < B {
+  m() {
+    return this.x;
+  }
+}";
+    assert_eq!(parse(compact), parse(spread));
+}
+
+#[test]
+fn a_different_lexeme_is_not_equal() {
+    assert_ne!(parse("var a = 1 + 2;"), parse("var a = 1 + 3;"));
+    assert_ne!(parse("var a = 1 + 2;"), parse("var a = 1 - 2;"));
+    assert_ne!(parse("print x;"), parse("print y;"));
+}
@@ -0,0 +1,70 @@
+use crafting_interpreters::{AstPrinter, Expr, Parser, Scanner};
+
+// each statement of `source` as the printer shows it, a line apart
+fn printed(printer: &mut AstPrinter, source: &str) -> String {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    let statements = Parser::new(tokens).parse();
+    statements.iter().map(|statement| printer.print_stmt(statement) + "\n").collect()
+}
+
+#[test]
+fn an_else_if_chain_is_printed_flat() {
+    let source = "if (a) { print 1; } else if (b) { print 2; } else { print 3; }";
+    assert_eq!(
+        printed(&mut AstPrinter::new(), source),
+        "(if a
+  (block
+    (print 1))
+ else-if b
+  (block
+    (print 2))
+ else
+  (block
+    (print 3)))
+"
+    );
+}
+
+#[test]
+fn an_else_block_holding_an_if_is_still_nested() {
+    let source = "if (a) print 1; else { if (b) print 2; }";
+    assert_eq!(
+        printed(&mut AstPrinter::new(), source),
+        "(if a
+  (print 1)
+ else
+  (block
+    (if b
+      (print 2))))
+"
+    );
+}
+
+fn expression(source: &str) -> Expr {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    Parser::new(tokens).parse_expression().unwrap()
+}
+
+#[test]
+fn a_negated_number_is_printed_as_a_negative_literal_when_folding() {
+    let cases = [
+        ("-123 * (45.67)", "(* (- 123) (group 45.67))", "(* -123 (group 45.67))"),
+        ("3 - -1.5", "(- 3 (- 1.5))", "(- 3 -1.5)"),
+        ("--2", "(- (- 2))", "(- -2)"),
+    ];
+    let mut folding = AstPrinter::new();
+    folding.set_fold_negative_literals(true);
+    for (source, plain, folded) in cases {
+        assert_eq!(AstPrinter::new().print(&expression(source)), plain);
+        assert_eq!(folding.print(&expression(source)), folded);
+    }
+}
+
+#[test]
+fn only_a_minus_directly_on_a_number_is_folded() {
+    let mut folding = AstPrinter::new();
+    folding.set_fold_negative_literals(true);
+    assert_eq!(folding.print(&expression("-x")), "(- x)");
+    assert_eq!(folding.print(&expression("-(1)")), "(- (group 1))");
+    assert_eq!(folding.print(&expression("!5")), "(! 5)");
+}
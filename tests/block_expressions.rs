@@ -0,0 +1,25 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_block_initializer_is_the_value_of_its_last_expression() {
+    let source = "fun compute() { return 4; }\nvar x = { var t = compute(); t * 2 };\nprint x;";
+    assert_eq!(output(source), "8\n");
+}
+
+#[test]
+fn a_block_ending_in_a_statement_is_nil() {
+    assert_eq!(output("var y = { print \"side\"; };\nprint y;"), "side\nnil\n");
+}
+
+#[test]
+fn the_block_has_a_scope_of_its_own() {
+    let source = "var outer = 10;\nvar v = { var outer = 1; outer + 1 };\nprint v;\nprint outer;";
+    assert_eq!(output(source), "2\n10\n");
+}
+
+#[test]
+fn braces_with_nothing_in_them_are_still_a_map() {
+    assert_eq!(output("var z = {};\nprint z;\nvar m = {1: 2};\nprint m;"), "{}\n{1: 2}\n");
+}
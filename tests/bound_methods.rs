@@ -0,0 +1,29 @@
+mod common;
+
+use common::output;
+
+const COUNTER: &str = "class Counter {
+  init() { this.n = 0; }
+  bump() { this.n = this.n + 1; return this.n; }
+  show() { return this.n; }
+}
+var c = Counter();
+";
+
+#[test]
+fn a_detached_method_sees_later_changes_to_its_instance() {
+    let source = format!("{}var show = c.show;\nc.bump();\nc.n = c.n + 10;\nprint show();", COUNTER);
+    assert_eq!(output(&source), "11\n");
+}
+
+#[test]
+fn a_bound_method_can_be_passed_to_a_function() {
+    let source = format!("{}fun thrice(f) {{ f(); f(); return f(); }}\nprint thrice(c.bump);\nprint c.n;", COUNTER);
+    assert_eq!(output(&source), "3\n3\n");
+}
+
+#[test]
+fn a_bound_method_is_a_function() {
+    let source = format!("{}print type(c.bump);\nprint c.show;", COUNTER);
+    assert_eq!(output(&source), "function\n<fn show of Counter instance>\n");
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::interpret;
+use crafting_interpreters::{Interpreter, Limits};
+
+fn steps(max_steps: u64) -> Interpreter {
+    Interpreter::with_limits(Limits { max_steps: Some(max_steps), max_millis: None })
+}
+
+#[test]
+fn there_are_no_limits_by_default() {
+    assert!(Interpreter::new().limits().is_unlimited());
+}
+
+#[test]
+fn a_step_limit_stops_an_infinite_loop() {
+    assert_eq!(
+        interpret(&mut steps(1000), "var i = 0;\nwhile (true) i = i + 1;").as_deref(),
+        Some("[line 2] RuntimeError at 'while': Execution budget exceeded.")
+    );
+}
+
+#[test]
+fn a_step_limit_stops_endless_recursion_and_tail_calls() {
+    assert_eq!(
+        interpret(&mut steps(1000), "fun f() { return f(); }\nf();").as_deref(),
+        Some("[line 1] RuntimeError at ')': Execution budget exceeded.")
+    );
+    let error = interpret(&mut steps(100), "fun f(n) { if (n > 0) f(n - 1); f(n - 1); }\nf(3);").unwrap();
+    assert!(error.ends_with("Execution budget exceeded."), "{error}");
+}
+
+#[test]
+fn a_program_within_the_limit_runs_to_the_end() {
+    assert_eq!(interpret(&mut steps(1000), "var i = 0;\nwhile (i < 10) i = i + 1;"), None);
+}
+
+#[test]
+fn a_time_limit_stops_an_infinite_loop() {
+    let mut interpreter = Interpreter::with_limits(Limits { max_steps: None, max_millis: Some(50) });
+    let started = Instant::now();
+    let error = interpret(&mut interpreter, "while (true) {}").unwrap();
+    assert_eq!(error, "[line 1] RuntimeError at 'while': Execution budget exceeded.");
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn every_run_gets_the_whole_budget() {
+    let mut interpreter = steps(1000);
+    assert!(interpret(&mut interpreter, "while (true) {}").is_some());
+    assert_eq!(interpret(&mut interpreter, "var i = 0;\nwhile (i < 10) i = i + 1;"), None);
+}
+
+#[test]
+fn a_step_limit_stops_repeat_and_for_in_loops() {
+    assert_eq!(
+        interpret(&mut steps(1000), "repeat (200000) {}").as_deref(),
+        Some("[line 1] RuntimeError at 'repeat': Execution budget exceeded.")
+    );
+    assert_eq!(
+        interpret(&mut steps(1000), "var n = 0;\nfor (i in 0..200000) n = n + i;").as_deref(),
+        Some("[line 2] RuntimeError at 'for': Execution budget exceeded.")
+    );
+    let source = "var xs = [];\nrepeat (2000) xs.push(0);\nfor (x in xs) {}";
+    assert!(interpret(&mut steps(10_000), source).is_none());
+    assert!(interpret(&mut steps(1000), source).is_some());
+}
+
+#[test]
+fn a_loop_body_catching_errors_is_still_stopped() {
+    // once the budget is spent the handler no longer catches, so the run ends with the error it
+    // was about to handle, wherever in the body the budget ran out
+    for source in [
+        "for (i in 0..200000) {\n  try { nil + 1; } catch (e) {}\n}",
+        "repeat (200000) {\n  try { nil + 1; } catch (e) {}\n}",
+    ] {
+        assert!(interpret(&mut steps(1000), source).is_some(), "{source}");
+    }
+}
+
+#[test]
+fn the_budget_error_itself_is_not_caught() {
+    let source = "for (i in 0..200000) {\n  try { while (true) {} } catch (e) {}\n}";
+    assert_eq!(
+        interpret(&mut steps(1000), source).as_deref(),
+        Some("[line 2] RuntimeError at 'while': Execution budget exceeded.")
+    );
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::messages_with;
+use crafting_interpreters::runner::run;
+use crafting_interpreters::{Interpreter, DEFAULT_MAX_CALL_DEPTH};
+
+// `depth(n)` makes n + 1 nested calls
+const DEPTH: &str = "fun depth(n) { if (n == 0) return 0; return 1 + depth(n - 1); }\n";
+
+// The first line of each diagnostic from running `source` with at most `limit` nested calls. This
+// runs on the test's own thread, with its 2 MiB of stack: deep calls have to grow the stack for
+// themselves rather than count on the binary's large interpreter thread.
+fn messages(source: &str, limit: usize) -> Vec<String> {
+    first_lines(&messages_with(source, |interpreter| interpreter.set_max_call_depth(limit)))
+}
+
+fn first_lines(messages: &[String]) -> Vec<String> {
+    messages.iter().map(|message| message.lines().next().unwrap_or_default().to_string()).collect()
+}
+
+#[test]
+fn infinite_recursion_is_a_runtime_error() {
+    let run = common::run("fun f(n) { return 1 + f(n + 1); }\nf(0);");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr.lines().next().unwrap(),
+        format!("[line 1] RuntimeError at ')': Stack overflow: exceeded {} nested calls.", DEFAULT_MAX_CALL_DEPTH)
+    );
+}
+
+#[test]
+fn recursion_just_inside_the_default_limit_succeeds() {
+    let source = format!("{DEPTH}print depth({});", DEFAULT_MAX_CALL_DEPTH - 2);
+    assert_eq!(common::output(&source), format!("{}\n", DEFAULT_MAX_CALL_DEPTH - 2));
+}
+
+#[test]
+fn the_limit_is_configurable() {
+    assert_eq!(Interpreter::new().max_call_depth(), DEFAULT_MAX_CALL_DEPTH);
+    assert!(messages(&format!("{DEPTH}depth(9);"), 10).is_empty());
+    assert_eq!(
+        messages(&format!("{DEPTH}depth(10);"), 10),
+        ["[line 1] RuntimeError at ')': Stack overflow: exceeded 10 nested calls."]
+    );
+    assert!(messages(&format!("{DEPTH}depth(4999);"), 5000).is_empty());
+}
+
+#[test]
+fn infinite_recursion_is_a_runtime_error_on_the_test_thread() {
+    let diagnostics = run(&"fun f(n) { return 1 + f(n + 1); }\nf(0);".to_string());
+    assert_eq!(
+        first_lines(diagnostics.messages()),
+        [format!("[line 1] RuntimeError at ')': Stack overflow: exceeded {} nested calls.", DEFAULT_MAX_CALL_DEPTH)]
+    );
+}
+
+#[test]
+fn recursion_up_to_the_default_limit_succeeds_on_the_test_thread() {
+    // DEFAULT_MAX_CALL_DEPTH nested calls, the most the limit allows
+    let source = format!("{DEPTH}depth({});", DEFAULT_MAX_CALL_DEPTH - 1);
+    assert!(run(&source).messages().is_empty());
+    let source = format!("{DEPTH}depth({});", DEFAULT_MAX_CALL_DEPTH);
+    assert_eq!(run(&source).messages().len(), 1);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_chained_comparison_is_a_syntax_error() {
+    let run = common::run("print 1 < 2 < 3;");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stderr, "[line 1 ] Error  at '<' : Chained comparison is not allowed; use explicit parentheses.\n");
+
+    let run = common::run("var a = 1;\nprint a >= 2 <= 3;");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stderr, "[line 2 ] Error  at '<=' : Chained comparison is not allowed; use explicit parentheses.\n");
+}
+
+#[test]
+fn parentheses_make_the_comparison_explicit() {
+    // it parses, and fails at runtime the way comparing a bool with a number always does
+    let run = common::run("print (1 < 2) < 3;");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at '<': Operator '<' expects numbers"), "{}", run.stderr);
+}
+
+#[test]
+fn comparisons_joined_by_other_operators_are_not_chained() {
+    assert_eq!(output("print 1 < 2 == true;\nprint 1 < 2 and 2 < 3;\nprint (1 < 2) == (3 > 2);"), "true\ntrue\ntrue\n");
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn clock_moves_on_by_at_least_the_time_slept() {
+    let source = "var before = clock();\nsleepMs(50);\nvar slept = clock() - before;\nprint slept >= 0.04;\nprint slept < 10;";
+    assert_eq!(output(source), "true\ntrue\n");
+}
+
+#[test]
+fn clock_never_goes_backwards() {
+    let source = "var last = clock();\nvar backwards = 0;
+for (var i = 0; i < 10000; i = i + 1) {
+  var now = clock();
+  if (now < last) backwards = backwards + 1;
+  last = now;
+}
+print backwards;";
+    assert_eq!(output(source), "0\n");
+}
+
+#[test]
+fn sleep_takes_a_non_negative_number() {
+    let run = common::run("sleepMs(-1);");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at ')': sleepMs() expects a finite, non-negative duration, got -1."), "{}", run.stderr);
+
+    let run = common::run("sleepMs(\"a\");");
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at ')': sleepMs() expects a number, got string."), "{}", run.stderr);
+
+    let run = common::run("sleepMs(pow(10, 300) * 1.0);");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at ')': sleepMs() duration is too long."), "{}", run.stderr);
+}
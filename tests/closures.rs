@@ -0,0 +1,81 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn sibling_closures_share_the_variable_they_capture() {
+    let source = "
+        fun make() {
+          var n = 0;
+          fun inc() { n = n + 1; }
+          fun get() { return n; }
+          inc();
+          inc();
+          print get();
+          return get;
+        }
+        var get = make();
+        print get();
+    ";
+    assert_eq!(output(source), "2\n2\n");
+}
+
+#[test]
+fn a_closure_can_update_a_loop_variable() {
+    let source = "
+        for (var i = 0; i < 5; i = i + 1) {
+          fun skip() { i = i + 1; }
+          skip();
+          print i;
+        }
+    ";
+    assert_eq!(output(source), "1\n3\n5\n");
+}
+
+#[test]
+fn a_closure_sees_assignments_made_after_it_was_created() {
+    let source = "
+        fun outer() {
+          var message = \"before\";
+          fun show() { print message; }
+          message = \"after\";
+          show();
+        }
+        outer();
+    ";
+    assert_eq!(output(source), "after\n");
+}
+
+#[test]
+fn a_closure_keeps_the_variable_it_resolved_to() {
+    // the book's example: the later `var a` in the block must not change what `showA` reads
+    let source = "
+        var a = \"global\";
+        {
+          fun showA() {
+            print a;
+          }
+
+          showA();
+          var a = \"block\";
+          showA();
+        }
+    ";
+    assert_eq!(output(source), "global\nglobal\n");
+}
+
+#[test]
+fn a_closure_captures_a_block_scoped_variable() {
+    let source = "
+        {
+          var a = \"outer\";
+          fun show() { print a; }
+          {
+            var a = \"inner\";
+            show();
+          }
+          show();
+        }
+    ";
+    assert_eq!(output(source), "outer\nouter\n");
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::{error_of, output};
+
+#[test]
+fn contains_looks_for_a_substring_in_a_string() {
+    let source = "print contains(\"hello\", \"ell\");\nprint contains(\"hello\", \"xyz\");\nprint contains(\"hello\", \"\");";
+    assert_eq!(output(source), "true\nfalse\ntrue\n");
+    assert_eq!(
+        error_of("print contains(\"abc\", 1);"),
+        "[line 1] RuntimeError at ')': contains() can only look for a string in a string, got number."
+    );
+}
+
+#[test]
+fn contains_looks_for_an_equal_element_in_an_array() {
+    let source = "var a = [1, \"a\", nil];\nprint contains(a, \"a\");\nprint contains(a, nil);\nprint contains(a, 2);\nprint contains([1, 2], 1.0);";
+    assert_eq!(output(source), "true\ntrue\nfalse\ntrue\n");
+}
+
+#[test]
+fn contains_looks_for_a_key_in_a_map() {
+    let source = "var m = {\"k\": 1};\nprint contains(m, \"k\");\nprint contains(m, 1);";
+    assert_eq!(output(source), "true\nfalse\n");
+}
+
+#[test]
+fn contains_takes_nothing_else() {
+    assert_eq!(
+        error_of("print contains(5, 1);"),
+        "[line 1] RuntimeError at ')': contains() expects a string, array or map, got number."
+    );
+}
+
+#[test]
+fn keys_and_values_are_lists_in_insertion_order() {
+    let source = "var m = {\"b\": 2, \"a\": 1, 3: \"c\"};\nprint keys(m);\nprint values(m);\nprint keys({});\nprint values({});";
+    assert_eq!(output(source), "[\"b\", \"a\", 3]\n[2, 1, \"c\"]\n[]\n[]\n");
+}
+
+#[test]
+fn the_lists_are_new_and_can_be_used_as_any_other() {
+    let source = "var m = {1: \"x\"};\nvar k = keys(m);\nk.push(2);\nprint k;\nprint len(keys(m));\nprint values(m)[0];";
+    assert_eq!(output(source), "[1, 2]\n1\nx\n");
+}
+
+#[test]
+fn keys_and_values_take_only_a_map() {
+    assert_eq!(error_of("print keys([1]);"), "[line 1] RuntimeError at ')': keys() expects a map, got array.");
+    assert_eq!(error_of("print values(\"s\");"), "[line 1] RuntimeError at ')': values() expects a map, got string.");
+}
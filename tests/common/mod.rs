@@ -0,0 +1,125 @@
+/*
+Runs Lox programs through the `jlox` binary, the way a user would, so that a test sees exactly
+what a script prints and how the process ends. Each source is written to a file of its own in the
+temp directory, since the tests of one binary run in parallel.
+*/
+#![allow(dead_code)]
+
+use crafting_interpreters::{runtime_error, Interpreter, Parser, RuntimeError, Scanner};
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_SCRIPT: AtomicUsize = AtomicUsize::new(0);
+
+/// A writer the test keeps a handle to, so it can read back what an embedded interpreter wrote
+/// through the other.
+#[derive(Clone, Default)]
+pub struct SharedBuffer(pub Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct Run {
+    pub stdout: String,
+    pub stderr: String,
+    // None when the process was killed by a signal
+    pub status: Option<i32>,
+}
+
+pub fn run(source: &str) -> Run {
+    run_with_flags(&[], source)
+}
+
+/// `run`, with `flags` such as `--ast` given before the script's path.
+pub fn run_with_flags(flags: &[&str], source: &str) -> Run {
+    let path = std::env::temp_dir().join(format!(
+        "jlox-test-{}-{}.lox",
+        std::process::id(),
+        NEXT_SCRIPT.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&path, source).expect("Failed to write the script");
+    let output = Command::new(env!("CARGO_BIN_EXE_crafting-interpreters"))
+        .args(flags)
+        .arg(&path)
+        .output()
+        .expect("Failed to run jlox");
+    let _ = fs::remove_file(&path);
+    Run {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        status: output.status.code(),
+    }
+}
+
+/// What `source` prints, asserting that it ran to the end without an error.
+pub fn output(source: &str) -> String {
+    let run = run(source);
+    assert_eq!(run.status, Some(0), "the script failed:\n{}", run.stderr);
+    run.stdout
+}
+
+/// The one runtime error `source` stops with, as reported on stderr, stack trace and all.
+pub fn error_of(source: &str) -> String {
+    let run = run(source);
+    assert_eq!(run.status, Some(70), "expected a runtime error, got:\n{}", run.stdout);
+    run.stderr.trim_end().to_string()
+}
+
+/// The message, lexeme and line of the error `interpret` hands back for `source`, for the tests
+/// that embed an `Interpreter` rather than run the binary.
+pub fn interpret_error_of(interpreter: &mut Interpreter, source: &str) -> (String, String, usize) {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    match interpreter.interpret(Parser::new(tokens).parse()) {
+        Err(RuntimeError::Error { token, message, .. }) => (message, token.lexeme, token.line),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+/// The runtime error running `source` in `interpreter` stopped with, if any, as it would be reported.
+pub fn interpret(interpreter: &mut Interpreter, source: &str) -> Option<String> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    interpreter.interpret(Parser::new(tokens).parse()).err().map(|error| error.to_string())
+}
+
+/// Every diagnostic from running `source` in a new `Interpreter` set up by `configure`, the
+/// runtime error that stopped it included.
+pub fn messages_with(source: &str, configure: impl FnOnce(&mut Interpreter)) -> Vec<String> {
+    let mut interpreter = Interpreter::new();
+    configure(&mut interpreter);
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    let result = interpreter.interpret(Parser::new(tokens).parse());
+    let mut diagnostics = interpreter.take_diagnostics();
+    if let Err(error) = result {
+        runtime_error(&mut diagnostics, error);
+    }
+    diagnostics.messages().to_vec()
+}
+
+/// The REPL session for `input`, typed a line at a time: everything it writes to stdout and stderr.
+pub fn repl(input: &str) -> Run {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_crafting-interpreters"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run jlox");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).expect("Failed to type into the REPL");
+    let output = child.wait_with_output().expect("Failed to run jlox");
+    Run {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        status: output.status.code(),
+    }
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn a_string_concatenates_with_a_number_on_either_side() {
+    assert_eq!(output("print \"count: \" + 3;"), "count: 3\n");
+    assert_eq!(output("print 3 + \"x\";"), "3x\n");
+    assert_eq!(output("print \"half: \" + 0.5;"), "half: 0.5\n");
+}
+
+#[test]
+fn a_string_concatenates_with_booleans_and_nil() {
+    assert_eq!(output("print \"flag: \" + true;"), "flag: true\n");
+    assert_eq!(output("print false + \"!\";"), "false!\n");
+    assert_eq!(output("print \"value: \" + nil;"), "value: nil\n");
+}
+
+#[test]
+fn numbers_still_add() {
+    assert_eq!(output("print 1 + 2;"), "3\n");
+}
+
+#[test]
+fn adding_two_non_strings_that_are_not_numbers_is_an_error() {
+    let run = run("print nil + nil;");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(run.stdout, "");
+}
+
+#[test]
+fn adjacent_string_literals_are_joined() {
+    assert_eq!(output("print \"Hello \" \"World\";"), "Hello World\n");
+    assert_eq!(output("print \"a\" \"b\" \"c\";"), "abc\n");
+}
+
+#[test]
+fn adjacent_literals_may_be_on_separate_lines() {
+    assert_eq!(output("var s = \"first, \"\n  \"second, \"\n  \"third\";\nprint s;"), "first, second, third\n");
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{error_of, output};
+
+#[test]
+fn a_constant_reads_like_a_variable() {
+    assert_eq!(output("const k = 1;\nprint k;\n{ const c = k + 1; print c; }"), "1\n2\n");
+}
+
+#[test]
+fn assigning_to_a_global_constant_is_an_error() {
+    assert_eq!(error_of("const k = 1;\nk = 2;"), "[line 2] RuntimeError at 'k': Cannot assign to constant 'k'.");
+}
+
+#[test]
+fn assigning_to_a_local_constant_is_an_error() {
+    assert_eq!(error_of("{\n  const c = 1;\n  c = 2;\n}"), "[line 3] RuntimeError at 'c': Cannot assign to constant 'c'.");
+}
+
+#[test]
+fn a_closure_cannot_assign_to_a_captured_constant() {
+    assert_eq!(
+        error_of("const k = 1;\nfun g() { k = 3; }\ng();"),
+        "[line 2] RuntimeError at 'k': Cannot assign to constant 'k'.\n  \
+         in function g (line 2), called from script (line 3)"
+    );
+}
+
+#[test]
+fn the_value_itself_can_still_change() {
+    assert_eq!(output("const xs = [1];\nxs.push(2);\nprint xs;"), "[1, 2]\n");
+}
+
+#[test]
+fn a_constant_must_be_initialized() {
+    let run = common::run("const k;");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stderr, "[line 1 ] Error  at 'k' : Constant 'k' must be initialized.\n");
+}
+
+#[test]
+fn a_global_constant_cannot_be_declared_again() {
+    assert_eq!(error_of("const k = 1;\nvar k = 2;\nprint k;"), "[line 2] RuntimeError at 'k': Cannot redeclare constant 'k'.");
+    assert_eq!(error_of("const k = 1;\nconst k = 2;"), "[line 2] RuntimeError at 'k': Cannot redeclare constant 'k'.");
+    assert_eq!(error_of("const k = 1;\nfun k() {}"), "[line 2] RuntimeError at 'k': Cannot redeclare constant 'k'.");
+}
+
+#[test]
+fn a_local_constant_cannot_be_declared_again() {
+    let run = common::run("{\n  const c = 1;\n  var c = 2;\n  print c;\n}");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stdout, "");
+    assert_eq!(run.stderr, "[line 3 ] Error  : Cannot redeclare constant 'c'.\n");
+    let run = common::run("{\n  const c = 1;\n  class c {}\n  print c;\n}");
+    assert_eq!(run.stderr, "[line 3 ] Error  : Cannot redeclare constant 'c'.\n");
+}
+
+#[test]
+fn a_constant_can_still_be_shadowed_in_an_inner_scope() {
+    assert_eq!(output("const k = 1;\n{ var k = 2; print k; }\nprint k;"), "2\n1\n");
+}
+
+#[test]
+fn a_global_constant_declared_on_an_earlier_repl_line_cannot_be_declared_again() {
+    let run = common::repl("const k = 1;\nvar k = 2;\nprint k;\n");
+    assert!(run.stderr.contains("Cannot redeclare constant 'k'."), "{}", run.stderr);
+    assert!(run.stdout.contains('1') && !run.stdout.contains('2'), "{}", run.stdout);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::output;
+
+const NODE: &str = "class Node { init(v) { this.v = v; this.next = nil; } }\n";
+
+#[test]
+fn nested_arrays_maps_and_instances_are_copied() {
+    let source = format!("{NODE}var a = Node(1);
+a.items = [1, [2, 3]];
+a.meta = {{\"k\": [4]}};
+var b = clone(a);
+b.items[1].push(9);
+b.meta[\"k\"].push(5);
+b.v = 7;
+print a.items;
+print a.meta[\"k\"];
+print a.v;
+print b.items;
+print b.meta[\"k\"];");
+    assert_eq!(output(&source), "[1, [2, 3]]\n[4]\n1\n[1, [2, 3, 9]]\n[4, 5]\n");
+}
+
+#[test]
+fn an_object_reached_twice_is_copied_once() {
+    let source = "var shared = [1];\nvar pair = [shared, shared];\nvar copy = clone(pair);\ncopy[0].push(2);\nprint copy[1];\nprint pair[0];";
+    assert_eq!(output(source), "[1, 2]\n[1]\n");
+}
+
+#[test]
+fn a_cycle_is_copied_into_a_cycle() {
+    let source = format!("{NODE}var a = Node(0);\na.next = a;\nvar b = clone(a);\nprint b.next == b;\nprint b == a;");
+    assert_eq!(output(&source), "true\nfalse\n");
+}
+
+#[test]
+fn a_long_linked_list_is_copied_without_recursion() {
+    let source = format!("{NODE}var head = nil;
+for (var i = 0; i < 100000; i = i + 1) {{ var n = Node(i); n.next = head; head = n; }}
+var copy = clone(head);
+print copy.v;
+print copy.next.next.v;");
+    assert_eq!(output(&source), "99999\n99997\n");
+}
+
+#[test]
+fn functions_are_shared_with_their_closures() {
+    let source = format!("{NODE}var count = 0;
+fun bump() {{ count = count + 1; return count; }}
+var holder = Node(bump);
+clone(holder).v();
+print holder.v();");
+    assert_eq!(output(&source), "2\n");
+}
+
+#[test]
+fn immutable_values_come_back_as_they_are() {
+    assert_eq!(output("print clone(3);\nprint clone(\"s\");\nprint clone(nil);\nprint clone(clock) == clock;"), "3\ns\nnil\ntrue\n");
+}
+
+#[test]
+fn the_copy_of_a_frozen_instance_is_not_frozen() {
+    let source = format!("{NODE}var a = freeze(Node(1));\nvar b = clone(a);\nb.v = 2;\nprint b.v;");
+    assert_eq!(output(&source), "2\n");
+}
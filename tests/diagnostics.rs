@@ -0,0 +1,49 @@
+mod common;
+
+use std::thread;
+
+use crafting_interpreters::run;
+
+#[test]
+fn separate_runs_keep_their_errors_to_themselves() {
+    let broken = run(&"print 1 +;".to_string());
+    let failing = run(&"print nil - 1;".to_string());
+    let fine = run(&"print 1;".to_string());
+
+    assert!(broken.had_error());
+    assert!(!broken.had_runtime_error());
+    assert_eq!(broken.messages().len(), 1);
+
+    assert!(!failing.had_error());
+    assert!(failing.had_runtime_error());
+    assert_eq!(failing.messages().len(), 1);
+    assert!(failing.messages()[0].starts_with("[line 1] RuntimeError"));
+
+    assert!(!fine.had_error());
+    assert!(!fine.had_runtime_error());
+    assert!(fine.messages().is_empty());
+}
+
+#[test]
+fn runs_on_different_threads_do_not_interfere() {
+    let sources = ["print 1 +;", "print 1;", "print nil - 1;", "print 2;"];
+    let handles: Vec<_> = sources
+        .iter()
+        .map(|source| {
+            let source = source.to_string();
+            thread::spawn(move || {
+                let diagnostics = run(&source);
+                (diagnostics.had_error(), diagnostics.had_runtime_error())
+            })
+        })
+        .collect();
+    let results: Vec<(bool, bool)> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+    assert_eq!(results, [(true, false), (false, false), (false, true), (false, false)]);
+}
+
+#[test]
+fn the_exit_status_says_what_kind_of_error_stopped_the_script() {
+    assert_eq!(common::run("print 1;").status, Some(0));
+    assert_eq!(common::run("print 1 +;").status, Some(65));
+    assert_eq!(common::run("print nil - 1;").status, Some(70));
+}
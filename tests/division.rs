@@ -0,0 +1,37 @@
+mod common;
+
+use common::{error_of, output};
+
+#[test]
+fn a_whole_zero_divisor_is_an_error() {
+    assert_eq!(error_of("print 1 / 0;"), "[line 1] RuntimeError at '/': Division by zero.");
+    assert_eq!(error_of("print 0 / 0;"), "[line 1] RuntimeError at '/': Division by zero.");
+}
+
+#[test]
+fn a_fractional_zero_divisor_is_an_error() {
+    assert_eq!(error_of("print 1 / 0.0;"), "[line 1] RuntimeError at '/': Division by zero.");
+    assert_eq!(error_of("var zero = 0.5 - 0.5;\nprint 2.5 / zero;"), "[line 2] RuntimeError at '/': Division by zero.");
+}
+
+#[test]
+fn negative_zero_is_zero_too() {
+    assert_eq!(error_of("print 1 / -0;"), "[line 1] RuntimeError at '/': Division by zero.");
+    assert_eq!(error_of("print -1 / -0.0;"), "[line 1] RuntimeError at '/': Division by zero.");
+}
+
+#[test]
+fn the_error_is_reported_on_the_line_of_the_operator() {
+    assert_eq!(error_of("var a = 1;\nvar b = a\n  /\n  0;"), "[line 3] RuntimeError at '/': Division by zero.");
+}
+
+#[test]
+fn nothing_after_the_division_runs() {
+    let run = common::run("print \"before\";\nprint 1 / 0;\nprint \"after\";");
+    assert_eq!(run.stdout, "before\n");
+}
+
+#[test]
+fn other_divisors_are_unaffected() {
+    assert_eq!(output("print 1 / 2;\nprint 1 / 0.5;\nprint -3 / 4;"), "0.5\n2\n-0.75\n");
+}
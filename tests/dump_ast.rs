@@ -0,0 +1,37 @@
+mod common;
+
+use crafting_interpreters::{dump_source, DumpMode};
+
+const SOURCE: &str = "fun add(a, b) { return a + b; }
+class Point < Base { init(x) { this.x = x; } }
+for (var i = 0; i < 2; i = i + 1) print add(i, 1);
+";
+
+const AST: &str = "(fun add (a b)
+  (return (+ a b)))
+(class Point < Base
+  (fun init (x)
+    (; (= .x this x))))
+(block
+  (var i 0)
+  (while (< i 2)
+    (print (call add i 1))
+    (step (= i (+ i 1)))))
+";
+
+#[test]
+fn each_top_level_statement_is_printed_as_an_s_expression() {
+    let mut out = Vec::new();
+    let diagnostics = dump_source(SOURCE, DumpMode::Ast, &mut out);
+    assert!(diagnostics.messages().is_empty(), "{:?}", diagnostics.messages());
+    assert_eq!(String::from_utf8(out).unwrap(), AST);
+}
+
+#[test]
+fn the_program_itself_is_not_run() {
+    let run = common::run_with_flags(&["--ast"], SOURCE);
+    assert_eq!(run.status, Some(0));
+    // no "1" and "2" from the loop's prints, and no complaint that `Base` is undefined
+    assert_eq!(run.stdout, AST);
+    assert_eq!(run.stderr, "");
+}
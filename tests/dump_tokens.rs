@@ -0,0 +1,36 @@
+mod common;
+
+use crafting_interpreters::{dump_source, DumpMode};
+
+#[test]
+fn each_token_is_printed_with_its_lexeme_literal_and_line() {
+    let mut out = Vec::new();
+    let diagnostics = dump_source("var x = \"hi\" + 1.5;\nprint x;\n", DumpMode::Tokens, &mut out);
+    assert!(diagnostics.messages().is_empty(), "{:?}", diagnostics.messages());
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "VAR var null [line 1]
+IDENTIFIER x null [line 1]
+EQUAL = null [line 1]
+STRING \"hi\" hi [line 1]
+PLUS + null [line 1]
+NUMBER 1.5 1.5 [line 1]
+SEMI_COLON ; null [line 1]
+PRINT print null [line 2]
+IDENTIFIER x null [line 2]
+SEMI_COLON ; null [line 2]
+EOF  null [line 3]
+"
+    );
+}
+
+#[test]
+fn a_lexical_error_is_reported_among_the_tokens_and_exits_with_65() {
+    let run = common::run_with_flags(&["--tokens"], "print 1 @ 2;");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(
+        run.stdout,
+        "PRINT print null [line 1]\nNUMBER 1 1 [line 1]\nNUMBER 2 2 [line 1]\nSEMI_COLON ; null [line 1]\nEOF  null [line 1]\n"
+    );
+    assert_eq!(run.stderr, "[line 1 ] Error  : Unexpected character.\n");
+}
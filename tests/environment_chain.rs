@@ -0,0 +1,61 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::thread;
+
+use crafting_interpreters::{Environment, Literal, Token, TokenType, Value};
+
+const DEPTH: usize = 200_000;
+
+fn identifier(name: &str) -> Token {
+    Token::new(TokenType::Identifier, name.to_string(), Literal::Nil, 1)
+}
+
+// runs `f` on a thread with a small stack, far too small for a walk of DEPTH scopes by recursion
+fn on_small_stack<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    thread::Builder::new().stack_size(256 * 1024).spawn(f).unwrap().join().unwrap()
+}
+
+// a global environment holding `global`, under DEPTH empty scopes; the innermost is returned
+fn deep_chain() -> Rc<RefCell<Environment>> {
+    let globals = Rc::new(RefCell::new(Environment::new_global()));
+    globals.borrow_mut().define("global".to_string(), Value::String("top".into()));
+    let mut innermost = globals;
+    for _ in 0..DEPTH {
+        innermost = Rc::new(RefCell::new(Environment::new_enclosed(innermost)));
+    }
+    innermost
+}
+
+#[test]
+fn get_walks_a_deep_chain_without_recursion() {
+    on_small_stack(|| {
+        let chain = deep_chain();
+        let value = chain.borrow().get(&identifier("global")).unwrap();
+        assert!(value.equals(&Value::String("top".into())));
+        assert!(chain.borrow().get(&identifier("missing")).is_err());
+        // dropping the chain is recursive, which is not what is being tested here
+        std::mem::forget(chain);
+    });
+}
+
+#[test]
+fn assign_walks_a_deep_chain_without_recursion() {
+    on_small_stack(|| {
+        let chain = deep_chain();
+        chain.borrow_mut().assign(&identifier("global"), Value::Bool(true)).unwrap();
+        assert!(chain.borrow().get(&identifier("global")).unwrap().equals(&Value::Bool(true)));
+        assert!(chain.borrow_mut().assign(&identifier("missing"), Value::Nil).is_err());
+        std::mem::forget(chain);
+    });
+}
+
+#[test]
+fn the_nearest_binding_wins() {
+    let globals = Rc::new(RefCell::new(Environment::new_global()));
+    globals.borrow_mut().define("x".to_string(), Value::Bool(false));
+    let inner = Rc::new(RefCell::new(Environment::new_enclosed(Rc::clone(&globals))));
+    inner.borrow_mut().define("x".to_string(), Value::Nil);
+    inner.borrow_mut().assign(&identifier("x"), Value::Bool(true)).unwrap();
+    assert!(inner.borrow().get(&identifier("x")).unwrap().equals(&Value::Bool(true)));
+    assert!(globals.borrow().get(&identifier("x")).unwrap().equals(&Value::Bool(false)));
+}
@@ -0,0 +1,92 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use crafting_interpreters::{Environment, Evaluator, LoxCallable, Parser, RuntimeError, Scanner, Value};
+
+/*
+Every copy of a `Value::Callable` is one more strong reference to the callable it holds, so its
+strong count is a clone counter for the value. `Probe` records the count of `target` at the
+moment the program calls it.
+*/
+#[derive(Debug)]
+struct Probe {
+    target: Rc<dyn LoxCallable>,
+    seen: Rc<Cell<usize>>,
+}
+
+impl LoxCallable for Probe {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn name(&self) -> &str {
+        "probe"
+    }
+
+    fn call(&self, _interpreter: &mut Evaluator, _arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        self.seen.set(Rc::strong_count(&self.target));
+        Ok(Value::Nil)
+    }
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+// stands in for a large value bound in an outer scope
+#[derive(Debug)]
+struct Large;
+
+impl LoxCallable for Large {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn name(&self) -> &str {
+        "large"
+    }
+
+    fn call(&self, _interpreter: &mut Evaluator, _arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        Ok(Value::Nil)
+    }
+}
+
+impl fmt::Display for Large {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+// how many copies of the outer `large` exist while `source` is calling `probe()`
+fn copies_seen_by(source: &str) -> usize {
+    let large: Rc<dyn LoxCallable> = Rc::new(Large);
+    let seen = Rc::new(Cell::new(0));
+    let mut globals = Environment::new_global();
+    globals.define("large".to_string(), Value::Callable(Rc::clone(&large)));
+    let probe = Probe { target: Rc::clone(&large), seen: Rc::clone(&seen) };
+    globals.define("probe".to_string(), Value::Callable(Rc::new(probe)));
+
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    let mut evaluator = Evaluator::new(Rc::new(RefCell::new(globals)));
+    for statement in Parser::new(tokens).parse() {
+        evaluator.execute(&statement).expect("the program should run");
+    }
+    seen.get()
+}
+
+#[test]
+fn entering_blocks_copies_nothing_from_the_outer_scopes() {
+    // the test's handle, the global binding and the probe's handle
+    let at_top_level = copies_seen_by("probe();");
+    assert_eq!(at_top_level, 3);
+    assert_eq!(copies_seen_by("{ var a = 1; { var b = 2; { probe(); } } }"), at_top_level);
+}
+
+#[test]
+fn calling_a_function_copies_nothing_from_the_outer_scopes() {
+    let at_top_level = copies_seen_by("probe();");
+    assert_eq!(copies_seen_by("fun f() { { probe(); } } { f(); }"), at_top_level);
+}
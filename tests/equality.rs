@@ -0,0 +1,96 @@
+mod common;
+
+use common::{output, SharedBuffer};
+use crafting_interpreters::{Interpreter, Parser, Scanner, Value};
+
+#[test]
+fn an_instance_equals_itself_through_two_variables() {
+    let source = "
+        class Point {}
+        var a = Point();
+        var b = a;
+        print a == b;
+        print a != b;
+    ";
+    assert_eq!(output(source), "true\nfalse\n");
+}
+
+#[test]
+fn distinct_instances_with_the_same_fields_are_not_equal() {
+    let source = "
+        class Point { init(x) { this.x = x; } }
+        print Point(1) == Point(1);
+    ";
+    assert_eq!(output(source), "false\n");
+}
+
+#[test]
+fn a_bound_method_equals_itself() {
+    let source = "
+        class Point { m() { return 1; } }
+        var p = Point();
+        var m = p.m;
+        print m == m;
+    ";
+    assert_eq!(output(source), "true\n");
+}
+
+#[test]
+fn classes_and_functions_are_equal_only_to_themselves() {
+    let source = "
+        class A {}
+        class B {}
+        fun f() {}
+        fun g() {}
+        var h = f;
+        print A == A;
+        print A == B;
+        print f == h;
+        print f == g;
+        print clock == clock;
+    ";
+    assert_eq!(output(source), "true\nfalse\ntrue\nfalse\ntrue\n");
+}
+
+#[test]
+fn nan_is_not_equal_to_itself() {
+    let source = "
+        var inf = 10;
+        for (var i = 0; i < 10; i = i + 1) inf = inf * inf;
+        var nan = inf - inf;
+        print nan == nan;
+        print nan != nan;
+    ";
+    assert_eq!(output(source), "false\ntrue\n");
+}
+
+#[test]
+fn ieee_division_by_zero_prints_nan_and_the_infinities() {
+    let buffer = SharedBuffer::default();
+    let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+    interpreter.set_ieee_division(true);
+    let source = "print 0/0;\nprint 1/0;\nprint -1/0;\nprint 0/0 == 0/0;\nprint 1/0 == 1/0;";
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    interpreter.interpret(Parser::new(tokens).parse()).unwrap();
+    assert_eq!(String::from_utf8(buffer.0.take()).unwrap(), "nan\ninf\n-inf\nfalse\ntrue\n");
+}
+
+#[test]
+fn only_nil_equals_nil_and_types_never_mix() {
+    let source = "
+        print nil == nil;
+        print nil == false;
+        print 1 == \"1\";
+        print \"a\" == \"a\";
+    ";
+    assert_eq!(output(source), "true\nfalse\nfalse\ntrue\n");
+}
+
+#[test]
+fn equals_is_value_equality_for_primitives() {
+    assert!(Value::Nil.equals(&Value::Nil));
+    assert!(Value::Bool(true).equals(&Value::Bool(true)));
+    assert!(!Value::Bool(true).equals(&Value::Nil));
+    assert!(Value::String("lox".into()).equals(&Value::String("lox".into())));
+    assert!(!Value::String("lox".into()).equals(&Value::String("Lox".into())));
+}
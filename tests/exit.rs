@@ -0,0 +1,39 @@
+use crafting_interpreters::{run, Interpreter, Parser, RuntimeError, Scanner};
+
+fn interpret(source: &str) -> Result<(), RuntimeError> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    let statements = Parser::new(tokens).parse();
+    Interpreter::new().interpret(statements)
+}
+
+#[test]
+fn interpret_hands_the_code_back_instead_of_ending_the_process() {
+    let result = interpret("fun stop() { while (true) { exit(3); } }\nstop();");
+    assert!(matches!(result, Err(RuntimeError::Exit(3))), "{:?}", result);
+    // still running: the exit unwound out of `interpret` and no further
+}
+
+#[test]
+fn nothing_after_the_exit_runs() {
+    let result = interpret("print \"before\";\nexit(0);\nthrow \"not reached\";");
+    assert!(matches!(result, Err(RuntimeError::Exit(0))), "{:?}", result);
+}
+
+#[test]
+fn run_reports_the_code_in_its_diagnostics() {
+    let diagnostics = run(&"exit(42);".to_string());
+    assert_eq!(diagnostics.exit_code(), Some(42));
+    assert!(diagnostics.messages().is_empty(), "{:?}", diagnostics.messages());
+}
+
+#[test]
+fn a_code_out_of_range_is_a_runtime_error() {
+    for code in ["256", "-1", "1.5", "\"1\""] {
+        match interpret(&format!("exit({});", code)) {
+            Err(RuntimeError::Error { message, .. }) => {
+                assert!(message.starts_with("exit() expects a whole number from 0 to 255"), "{}", message)
+            }
+            other => panic!("exit({}) gave {:?}", code, other),
+        }
+    }
+}
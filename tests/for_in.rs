@@ -0,0 +1,62 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn arrays_are_visited_in_order() {
+    assert_eq!(output("for (x in [3, 1, 2]) print x;"), "3\n1\n2\n");
+}
+
+#[test]
+fn strings_are_visited_by_character() {
+    assert_eq!(output("for (c in \"héy\") print c;"), "h\né\ny\n");
+}
+
+#[test]
+fn maps_are_visited_by_key_in_insertion_order() {
+    let source = "var m = {\"b\": 1, \"a\": 2, 1: 3};
+m[\"b\"] = 4;
+m.remove(\"a\");
+m[\"a\"] = 5;
+for (k in m) print k;";
+    assert_eq!(output(source), "b\n1\na\n");
+}
+
+#[test]
+fn changes_made_by_the_body_do_not_affect_the_loop() {
+    let source = "var a = [1, 2];\nfor (x in a) { a.push(x); print x; }\nprint len(a);";
+    assert_eq!(output(source), "1\n2\n4\n");
+}
+
+#[test]
+fn each_iteration_binds_a_fresh_variable() {
+    let source = "var fs = [];\nfor (x in [1, 2]) { fun f() { return x; } fs.push(f); }\nprint fs[0]();\nprint fs[1]();";
+    assert_eq!(output(source), "1\n2\n");
+}
+
+#[test]
+fn break_and_continue_work_inside() {
+    let source = "for (x in [1, 2, 3, 4]) { if (x == 2) continue; if (x == 4) break; print x; }";
+    assert_eq!(output(source), "1\n3\n");
+}
+
+#[test]
+fn an_empty_sequence_runs_nothing() {
+    assert_eq!(output("for (x in []) print x;\nfor (c in \"\") print c;\nprint \"after\";"), "after\n");
+}
+
+#[test]
+fn other_values_cannot_be_looped_over() {
+    let run = common::run("for (x in 5) print x;");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr.trim_end(),
+        "[line 1] RuntimeError at 'for': Can only loop over arrays, maps, strings and ranges, got number."
+    );
+}
+
+#[test]
+fn the_index_or_key_can_be_bound_alongside() {
+    let source = "for (i, x in [\"a\", \"b\"]) print str(i) + x;\nfor (k, v in {\"one\": 1}) print k + str(v);";
+    assert_eq!(output(source), "0a\n1b\none1\n");
+}
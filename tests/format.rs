@@ -0,0 +1,46 @@
+mod common;
+
+use common::{error_of, output};
+
+#[test]
+fn positional_placeholders_take_the_arguments_in_turn() {
+    assert_eq!(output("print format(\"{} + {} = {}\", 1, 2, 3);"), "1 + 2 = 3\n");
+}
+
+#[test]
+fn indexed_placeholders_pick_their_argument() {
+    assert_eq!(output("print format(\"{1} {0} {1}\", \"a\", \"b\");"), "b a b\n");
+}
+
+#[test]
+fn doubled_braces_are_literal_braces() {
+    assert_eq!(output("print format(\"{{}} {{{}}}\", 7);"), "{} {7}\n");
+}
+
+#[test]
+fn a_missing_argument_is_named_by_its_index() {
+    assert_eq!(
+        error_of("print format(\"{} {}\", 1);"),
+        "[line 1] RuntimeError at ')': format() has no argument 1 for its placeholder, got 1 arguments."
+    );
+    assert_eq!(
+        error_of("print format(\"{3}\", 1);"),
+        "[line 1] RuntimeError at ')': format() has no argument 3 for its placeholder, got 1 arguments."
+    );
+}
+
+#[test]
+fn values_are_shown_the_way_print_shows_them() {
+    let source = "class P {}
+class Q { toString() { return \"Q!\"; } }
+print format(\"{} {} {} {} {} {}\", 2.5, 3.0, nil, true, P(), Q());";
+    assert_eq!(output(source), "2.5 3 nil true P instance Q!\n");
+}
+
+#[test]
+fn an_argument_no_placeholder_uses_is_an_error() {
+    assert_eq!(
+        error_of("print format(\"{}\", 1, 2);"),
+        "[line 1] RuntimeError at ')': format() argument 1 is not used by any placeholder."
+    );
+}
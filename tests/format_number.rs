@@ -0,0 +1,37 @@
+mod common;
+
+use common::{error_of, output};
+
+#[test]
+fn a_number_is_rounded_to_the_given_decimals() {
+    let source = "print format_number(3.14159, 2);\nprint format_number(1 / 3, 4);\nprint format_number(1.75, 0);\nprint format_number(-2.345, 1);";
+    assert_eq!(output(source), "3.14\n0.3333\n2\n-2.3\n");
+}
+
+#[test]
+fn a_whole_number_is_padded_with_zeros() {
+    assert_eq!(output("print format_number(2, 3);\nprint format_number(0, 1);"), "2.000\n0.0\n");
+}
+
+#[test]
+fn the_result_is_a_string() {
+    assert_eq!(output("print type(format_number(1, 1));\nprint format_number(1, 1) + \"!\";"), "string\n1.0!\n");
+}
+
+#[test]
+fn only_a_number_can_be_formatted() {
+    assert_eq!(
+        error_of("print format_number(\"1\", 2);"),
+        "[line 1] RuntimeError at ')': format_number() expects a number to format, got string."
+    );
+}
+
+#[test]
+fn the_decimals_must_be_a_whole_number_from_0_to_100() {
+    for decimals in ["-1", "1.5", "101", "nil"] {
+        assert_eq!(
+            error_of(&format!("print format_number(1, {});", decimals)),
+            "[line 1] RuntimeError at ')': format_number() expects a whole number of decimals from 0 to 100."
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use std::collections::HashSet;
+
+use crafting_interpreters::{free_variables, free_variables_in, Parser, Scanner};
+
+fn names(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn a_closure_captures_the_outer_variable_but_not_its_own_parameter() {
+    // the body of `make`: `count` is local to it, `step` is its parameter, `start` comes from outside
+    let source = "var count = start;
+fun next(by) { count = count + by * step; return count; }
+return next;";
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    let body = Parser::new(tokens).parse();
+    assert_eq!(free_variables_in(&body), names(&["start", "step"]));
+
+    // the inner function alone captures `count` as well
+    assert_eq!(free_variables_in(&body[1..2]), names(&["count", "step"]));
+}
+
+#[test]
+fn an_expression_reports_every_name_it_reads() {
+    let tokens = Scanner::new("a + b.field * f(a, c)".to_string()).scan_tokens().clone();
+    let expr = Parser::new(tokens).parse_expression().unwrap();
+    assert_eq!(free_variables(&expr), names(&["a", "b", "f", "c"]));
+}
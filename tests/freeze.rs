@@ -0,0 +1,51 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn fields_can_be_set_before_freezing() {
+    let source = "
+        class Point {}
+        var p = Point();
+        print p.x = 1;
+    ";
+    assert_eq!(output(source), "1\n");
+}
+
+#[test]
+fn setting_a_field_after_freezing_is_an_error() {
+    let run = run("
+        class Point {}
+        var p = freeze(Point());
+        print \"frozen\";
+        p.x = 2;
+        print \"unreachable\";
+    ");
+    assert_eq!(run.stdout, "frozen\n");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.contains("[line 5] RuntimeError at 'x': Cannot set property on frozen instance."), "{}", run.stderr);
+}
+
+#[test]
+fn freeze_hands_back_an_instance_and_leaves_other_values_alone() {
+    let source = "
+        class Point {}
+        print freeze(Point());
+        print freeze(3);
+        print freeze(\"text\");
+    ";
+    assert_eq!(output(source), "Point instance\n3\ntext\n");
+}
+
+#[test]
+fn freezing_one_reference_freezes_the_shared_instance() {
+    let run = run("
+        class Point {}
+        var p = Point();
+        freeze(p);
+        var q = p;
+        q.x = 1;
+    ");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.contains("[line 6] RuntimeError at 'x': Cannot set property on frozen instance."), "{}", run.stderr);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn collecting_frees_an_unreachable_closure_cycle() {
+    // `make`'s environment holds `f`, which holds the environment back: a cycle once `make` returns
+    let source = "
+        fun make() {
+          var me;
+          fun f() { return me; }
+          me = f;
+        }
+        var baseline = gcStats();
+        for (var i = 0; i < 10; i = i + 1) make();
+        var live = gcStats();
+        print live > baseline;
+        collectGarbage();
+        print gcStats() < live;
+        print gcStats() == baseline;
+    ";
+    assert_eq!(output(source), "true\ntrue\ntrue\n");
+}
+
+#[test]
+fn collecting_keeps_what_is_still_reachable() {
+    let source = "
+        fun counter() {
+          var n = 0;
+          fun next() { n = n + 1; return n; }
+          return next;
+        }
+        var c = counter();
+        c();
+        collectGarbage();
+        print c();
+    ";
+    assert_eq!(output(source), "2\n");
+}
+
+#[test]
+fn collecting_frees_two_instances_pointing_at_each_other() {
+    let source = "
+        class A {}
+        var baseline = gcStats();
+        var a = A();
+        var b = A();
+        a.o = b;
+        b.o = a;
+        print gcStats() == baseline + 2;
+        a = nil;
+        b = nil;
+        print gcStats() == baseline + 2;
+        print collectGarbage();
+        print gcStats() == baseline;
+    ";
+    assert_eq!(output(source), "true\ntrue\n2\ntrue\n");
+}
+
+#[test]
+fn collecting_frees_an_array_holding_itself_and_keeps_a_reachable_one() {
+    let source = "
+        var baseline = gcStats();
+        var kept = [1];
+        kept.push(kept);
+        var lost = {\"k\": 1};
+        lost[\"self\"] = lost;
+        lost = nil;
+        print collectGarbage();
+        print gcStats() == baseline + 1;
+        print kept[0];
+    ";
+    assert_eq!(output(source), "1\ntrue\n1\n");
+}
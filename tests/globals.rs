@@ -0,0 +1,22 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn defined_globals_appear_sorted_by_name() {
+    let source = "var b = 2;\nvar a = \"one\";\nfun f() {}\nprint globals();";
+    assert_eq!(output(source), "{\"a\": \"one\", \"b\": 2, \"f\": <fn f>}\n");
+}
+
+#[test]
+fn the_values_are_the_current_ones_and_natives_are_left_out() {
+    let source = "var answer = 41;
+class K {}
+answer = answer + 1;
+var g = globals();
+print g[\"answer\"];
+print g[\"K\"];
+print contains(g, \"clock\");
+print contains(g, \"Error\");";
+    assert_eq!(output(source), "42\nK\nfalse\nfalse\n");
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::fs;
+
+use common::output;
+
+/*
+A module beside the scripts `common::run` writes, which is where their imports are looked up,
+under a name no other test uses. The file is removed when it goes out of scope.
+*/
+struct Module(String);
+
+impl Module {
+    fn new(name: &str, source: &str) -> Self {
+        let file = format!("jlox-module-{}-{}.lox", std::process::id(), name);
+        fs::write(std::env::temp_dir().join(&file), source).expect("Failed to write the module");
+        Module(file)
+    }
+}
+
+impl Drop for Module {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(std::env::temp_dir().join(&self.0));
+    }
+}
+
+#[test]
+fn a_function_from_an_imported_file_can_be_called_afterwards() {
+    let lib = Module::new("add", "fun add(a, b) { return a + b; }\nvar greeting = \"hi\";");
+    let source = format!("import \"{}\";\nprint add(1, 2);\nprint greeting;", lib.0);
+    assert_eq!(output(&source), "3\nhi\n");
+}
+
+#[test]
+fn a_module_imported_as_a_name_is_reached_through_it() {
+    let lib = Module::new("math", "fun add(a, b) { return a + b; }\nvar greeting = \"hi\";");
+    let source = format!("import \"{}\" as m;\nprint m.add(1, 2);\nprint m.greeting;", lib.0);
+    assert_eq!(output(&source), "3\nhi\n");
+}
+
+#[test]
+fn a_namespaced_import_leaves_the_globals_alone() {
+    let lib = Module::new("hidden", "fun add(a, b) { return a + b; }");
+    let run = common::run(&format!("import \"{}\" as m;\nprint add(1, 2);", lib.0));
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 2] RuntimeError at 'add': Undefined variable 'add'."), "{}", run.stderr);
+}
+
+#[test]
+fn errors_in_an_imported_file_name_it() {
+    let lib = Module::new("bad", "var a = 1;\nprint ;");
+    let run = common::run(&format!("print \"start\";\nimport \"{}\";", lib.0));
+    assert_eq!((run.stdout.as_str(), run.status), ("start\n", Some(65)));
+    assert_eq!(
+        run.stderr,
+        format!(
+            "[{0} line 2 ] Error  at ';' : Expected an expression.\n\
+             [line 2] RuntimeError at '\"{0}\"': Can't import '{0}': it has errors.\n",
+            lib.0
+        )
+    );
+}
+
+#[test]
+fn resolution_errors_in_an_imported_file_name_it_too() {
+    let lib = Module::new("unresolved", "return 1;");
+    let run = common::run(&format!("import \"{}\";\nprint \"after\";", lib.0));
+    assert_eq!(
+        run.stderr,
+        format!(
+            "[{0} line 1 ] Error  : Can't return a value from top-level code.\n\
+             [line 1] RuntimeError at '\"{0}\"': Can't import '{0}': it has errors.\n",
+            lib.0
+        )
+    );
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn calling_init_again_reinitializes_and_returns_the_same_instance() {
+    let source = "class P { init(x) { this.x = x; } }
+var p = P(1);
+var q = p.init(2);
+print q == p;
+print p.x;
+print p.init(3);";
+    assert_eq!(output(source), "true\n2\nP instance\n");
+}
+
+#[test]
+fn a_bare_return_in_init_still_gives_the_instance() {
+    let source = "class B {\n  init() { this.a = 1; return; }\n}\nvar b = B();\nprint b;\nprint b.a;\nprint b.init() == b;";
+    assert_eq!(output(source), "B instance\n1\ntrue\n");
+}
+
+#[test]
+fn returning_a_value_from_init_is_a_resolver_error() {
+    let run = common::run("print \"start\";\nclass C {\n  init() {\n    return 5;\n  }\n}");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stdout, "");
+    assert_eq!(run.stderr, "[line 4 ] Error  : Can't return a value from an initializer.\n");
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{error_of, output};
+
+const P: &str = "class P { init() { this.a = 1; this.b = 2; } m() {} }\nvar p = P();\n";
+
+#[test]
+fn has_field_sees_fields_but_not_methods() {
+    let source = format!("{}print hasField(p, \"a\");\nprint hasField(p, \"z\");\nprint hasField(p, \"m\");", P);
+    assert_eq!(output(&source), "true\nfalse\nfalse\n");
+}
+
+#[test]
+fn fields_lists_the_names_in_the_order_they_were_set() {
+    let source = format!("{}p.c = 3;\nprint fields(p);\nclass Empty {{}}\nprint fields(Empty());", P);
+    assert_eq!(output(&source), "[\"a\", \"b\", \"c\"]\n[]\n");
+}
+
+#[test]
+fn a_removed_field_is_gone_through_every_reference() {
+    let source = format!(
+        "{}var q = p;\nprint removeField(q, \"a\");\nprint removeField(q, \"a\");\nprint hasField(p, \"a\");\nprint fields(p);\nprint p.m;",
+        P
+    );
+    assert_eq!(output(&source), "true\nfalse\nfalse\n[\"b\"]\n<fn m of P instance>\n");
+}
+
+#[test]
+fn each_takes_only_an_instance() {
+    assert_eq!(error_of("print hasField(1, \"a\");"), "[line 1] RuntimeError at ')': hasField() expects an instance, got number.");
+    assert_eq!(error_of("print removeField(nil, \"a\");"), "[line 1] RuntimeError at ')': removeField() expects an instance, got nil.");
+    assert_eq!(error_of("class P {}\nprint fields(P);"), "[line 2] RuntimeError at ')': fields() expects an instance, got class.");
+}
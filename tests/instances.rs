@@ -0,0 +1,50 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn every_reference_sees_field_changes() {
+    let source = "class P {}\nvar p = P();\nvar q = p;\nq.x = 1;\nprint p.x;";
+    assert_eq!(output(source), "1\n");
+}
+
+#[test]
+fn functions_and_methods_change_the_caller_s_instance() {
+    let source = "class Counter {
+  init() { this.n = 0; }
+  bump() { this.n = this.n + 1; }
+}
+fun twice(c) { c.bump(); c.bump(); }
+var c = Counter();
+twice(c);
+c.bump();
+print c.n;";
+    assert_eq!(output(source), "3\n");
+}
+
+#[test]
+fn instances_in_collections_are_shared() {
+    let source = "class P {}\nvar p = P();\nvar a = [p];\na[0].x = 5;\nvar m = {1: p};\nm[1].y = 6;\nprint p.x + p.y;";
+    assert_eq!(output(source), "11\n");
+}
+
+#[test]
+fn changing_a_clone_leaves_the_original_alone() {
+    let source = "class P { init() { this.x = 1; } }
+var p = P();
+var c = p.clone();
+c.x = 2;
+c.y = 3;
+print p.x;
+print c.x;
+print hasField(p, \"y\");
+print c is P;
+print c == p;";
+    assert_eq!(output(source), "1\n2\nfalse\ntrue\nfalse\n");
+}
+
+#[test]
+fn a_clone_is_shallow() {
+    let source = "class P { init() { this.items = [1]; } }\nvar p = P();\np.clone().items.push(2);\nprint p.items;";
+    assert_eq!(output(source), "[1, 2]\n");
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::interpret_error_of;
+use crafting_interpreters::{Interpreter, Parser, Scanner};
+
+#[test]
+fn the_error_comes_back_with_its_message_and_token() {
+    let mut interpreter = Interpreter::with_output(Box::new(std::io::sink()));
+    assert_eq!(
+        interpret_error_of(&mut interpreter, "var a = 1;\nvar b = a +\n  nil;"),
+        ("Operator '+' expects two numbers or a string, got number (1) and nil (nil).".to_string(), "+".to_string(), 2)
+    );
+}
+
+#[test]
+fn an_error_inside_a_function_points_at_where_it_happened() {
+    let mut interpreter = Interpreter::with_output(Box::new(std::io::sink()));
+    let (message, lexeme, line) = interpret_error_of(&mut interpreter, "fun f() {\n  return missing;\n}\nf();");
+    assert_eq!((message.as_str(), lexeme.as_str(), line), ("Undefined variable 'missing'.", "missing", 2));
+}
+
+#[test]
+fn the_error_is_returned_rather_than_reported() {
+    let mut interpreter = Interpreter::with_output(Box::new(std::io::sink()));
+    interpret_error_of(&mut interpreter, "print 1 / 0;");
+    assert!(interpreter.take_diagnostics().messages().is_empty());
+}
+
+#[test]
+fn the_interpreter_is_still_usable_after_an_error() {
+    let mut interpreter = Interpreter::with_output(Box::new(std::io::sink()));
+    interpret_error_of(&mut interpreter, "var kept = 1;\nnil();");
+    let tokens = Scanner::new("if (kept != 1) throw kept;".to_string()).scan_tokens().clone();
+    assert!(interpreter.interpret(Parser::new(tokens).parse()).is_ok());
+}
+
+#[test]
+fn the_error_is_a_std_error() {
+    let mut interpreter = Interpreter::with_output(Box::new(std::io::sink()));
+    let tokens = Scanner::new("print -\"x\";".to_string()).scan_tokens().clone();
+    let error: Box<dyn std::error::Error> = Box::new(interpreter.interpret(Parser::new(tokens).parse()).unwrap_err());
+    assert_eq!(error.to_string(), "[line 1] RuntimeError at '-': Operator '-' expects a number, got string (\"x\").");
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::output;
+
+const SHAPES: &str = "class Shape {}
+class Polygon < Shape {}
+class Square < Polygon {}
+class Circle < Shape {}
+";
+
+#[test]
+fn an_instance_is_its_class_and_every_class_above_it() {
+    let source = format!("{}var s = Square();\nprint s is Square;\nprint s is Polygon;\nprint s is Shape;", SHAPES);
+    assert_eq!(output(&source), "true\ntrue\ntrue\n");
+}
+
+#[test]
+fn an_instance_is_not_a_sibling_or_a_subclass() {
+    let source = format!("{}print Square() is Circle;\nprint Shape() is Square;\nprint Polygon() is Square;", SHAPES);
+    assert_eq!(output(&source), "false\nfalse\nfalse\n");
+}
+
+#[test]
+fn values_that_are_not_instances_are_no_class() {
+    let source = format!("{}print 5 is Shape;\nprint nil is Shape;\nprint Square is Shape;", SHAPES);
+    assert_eq!(output(&source), "false\nfalse\nfalse\n");
+}
+
+#[test]
+fn the_right_operand_must_be_a_class() {
+    let run = common::run("class A {}\nprint A() is 3;");
+    assert_eq!(run.status, Some(70));
+    assert!(
+        run.stderr.starts_with("[line 2] RuntimeError at 'is': Right operand of 'is' must be a class, got number (3)."),
+        "{}",
+        run.stderr
+    );
+}
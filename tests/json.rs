@@ -0,0 +1,75 @@
+mod common;
+
+use common::output;
+use crafting_interpreters::{from_json, to_json};
+
+// reads `text` and writes it back out
+fn round_trip(text: &str) -> String {
+    to_json(&from_json(text).unwrap(), usize::MAX).unwrap()
+}
+
+#[test]
+fn values_round_trip_through_json() {
+    let source = "var v = {\"name\": \"Ada\", \"tags\": [\"a\", \"b\"], \"n\": 1.5, \"ok\": true, \"none\": nil};
+var s = to_json(v);
+print s;
+print to_json(from_json(s)) == s;
+print from_json(s)[\"tags\"][1];";
+    assert_eq!(
+        output(source),
+        "{\"name\":\"Ada\",\"tags\":[\"a\",\"b\"],\"n\":1.5,\"ok\":true,\"none\":null}\ntrue\nb\n"
+    );
+}
+
+#[test]
+fn objects_keep_their_key_order() {
+    assert_eq!(round_trip(r#"{"b": 1, "a": {"d": [], "c": {}}}"#), r#"{"b":1,"a":{"d":[],"c":{}}}"#);
+}
+
+#[test]
+fn a_repeated_key_keeps_its_first_place_and_last_value() {
+    assert_eq!(round_trip(r#"{"a": 1, "b": 2, "a": 3}"#), r#"{"a":3,"b":2}"#);
+}
+
+#[test]
+fn strings_escape_and_unescape() {
+    assert_eq!(round_trip(r#""q\"\\\n\t\u0001""#), r#""q\"\\\n\t\u0001""#);
+    assert_eq!(round_trip(r#""é😀""#), "\"é😀\"");
+}
+
+#[test]
+fn numbers_and_whitespace() {
+    assert_eq!(round_trip(" [ 1 , 2.5e3 , -0.25 ,\n null ] "), "[1,2500,-0.25,null]");
+}
+
+#[test]
+fn invalid_json_is_rejected() {
+    for text in ["[1,]", r#"{"a" 1}"#, "'a'", "[1] 2", "// no", "", "01"] {
+        assert!(from_json(text).is_err(), "{text} was accepted");
+    }
+    assert_eq!(from_json("[1,]").unwrap_err(), "Invalid JSON at character 3: expected a value.");
+}
+
+#[test]
+fn nesting_is_limited() {
+    assert!(from_json(&"[".repeat(100_000)).is_err());
+    assert!(from_json(&format!("{}{}", "[".repeat(100), "]".repeat(100))).is_ok());
+}
+
+#[test]
+fn values_without_a_json_form_are_refused() {
+    let run = common::run("var a = [];\na.push(a);\nto_json(a);");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr.trim_end(),
+        "[line 3] RuntimeError at ')': Can't convert a collection that contains itself to JSON."
+    );
+    for source in ["class A {}\nto_json(A());", "to_json({1: 2});", "to_json(clock);"] {
+        assert_eq!(common::run(source).status, Some(70), "{source}");
+    }
+}
+
+#[test]
+fn output_is_held_to_the_allocation_limit() {
+    assert!(to_json(&from_json("[[1, 2, 3], [4, 5, 6]]").unwrap(), 8).is_err());
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn len_counts_the_characters_of_a_string() {
+    assert_eq!(output("print len(\"hello\");\nprint len(\"\");"), "5\n0\n");
+}
+
+#[test]
+fn a_multi_byte_character_counts_once() {
+    // "é" and "→" are two and three bytes long in UTF-8, "😀" four
+    assert_eq!(output("print len(\"héllo→\");\nprint len(\"😀\");"), "6\n1\n");
+}
+
+#[test]
+fn len_counts_the_elements_of_an_array_or_map() {
+    assert_eq!(output("print len([1, 2]);\nprint len([]);\nprint len({1: 2});"), "2\n0\n1\n");
+}
+
+#[test]
+fn a_number_has_no_length() {
+    let run = common::run("print len(5);");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at ')': Object has no length. Got number."), "{}", run.stderr);
+}
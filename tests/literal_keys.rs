@@ -0,0 +1,45 @@
+mod common;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use crafting_interpreters::Literal;
+
+fn hash(literal: &Literal) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    literal.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn nan_is_the_same_key_as_itself() {
+    let nan = Literal::Float(f64::NAN);
+    let other_nan = Literal::Float(-f64::NAN);
+    assert_eq!(nan, nan.clone());
+    assert_eq!(nan, other_nan);
+    assert_eq!(hash(&nan), hash(&other_nan));
+    assert_eq!(HashSet::from([nan, other_nan]).len(), 1);
+}
+
+#[test]
+fn zero_and_negative_zero_are_one_key() {
+    let zero = Literal::Float(0.0);
+    let negative = Literal::Float(-0.0);
+    assert_eq!(zero, negative);
+    assert_eq!(hash(&zero), hash(&negative));
+}
+
+#[test]
+fn other_literals_compare_by_value() {
+    assert_eq!(Literal::String("a".to_string()), Literal::String("a".to_string()));
+    assert_ne!(Literal::Float(1.0), Literal::Float(2.0));
+    assert_ne!(Literal::Int(0), Literal::Bool(false));
+    assert_ne!(Literal::Nil, Literal::Bool(false));
+}
+
+#[test]
+fn lox_equality_still_follows_ieee_754() {
+    let source = "var inf = 10;\nfor (var i = 0; i < 10; i = i + 1) inf = inf * inf;\nvar nan = inf - inf;\nprint nan;\nprint nan == nan;\nprint nan != nan;\nprint 0 == -0;";
+    assert_eq!(common::output(source), "nan\nfalse\ntrue\ntrue\n");
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::output;
+use crafting_interpreters::{Interpreter, Parser, Scanner};
+
+// `0 + 1 + 1 + ...` with `terms` ones
+fn sum(terms: usize) -> String {
+    format!("print 0{};", " + 1".repeat(terms))
+}
+
+#[test]
+fn a_sum_of_200000_terms_is_evaluated() {
+    assert_eq!(output(&sum(200_000)), "200000\n");
+}
+
+#[test]
+fn a_long_logical_chain_is_evaluated_and_short_circuits() {
+    let source = format!("print false{} or \"last\";\nprint true{} and nil;", " or false".repeat(100_000), " and true".repeat(100_000));
+    assert_eq!(output(&source), "last\nnil\n");
+    let source = format!("fun boom() {{ print \"evaluated\"; }}\nprint true{} or boom();", " or false".repeat(1000));
+    assert_eq!(output(&source), "true\n");
+}
+
+#[test]
+fn operands_are_combined_left_to_right() {
+    assert_eq!(output("print 10 - 2 - 3;\nprint (10 - 2) - 3;\nprint 10 - (2 - 3);"), "5\n5\n11\n");
+    assert_eq!(output("print \"a\" + \"b\" + 1 + 2;\nprint 1 + 2 + \"a\";"), "ab12\n3a\n");
+}
+
+#[test]
+fn an_error_partway_along_a_chain_stops_at_its_operator() {
+    let run = common::run("print 1 + 2 + nil + 3;");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at '+'"), "{}", run.stderr);
+}
+
+#[test]
+fn operands_are_evaluated_in_order() {
+    let source = "var log = \"\";\nfun t(s) { log = log + s; return 1; }\nprint t(\"a\") + t(\"b\") * t(\"c\") - t(\"d\");\nprint log;";
+    assert_eq!(output(source), "1\nabcd\n");
+}
+
+#[test]
+fn a_long_chain_is_dropped_without_recursing() {
+    let tokens = Scanner::new(format!("0{}", " + 1".repeat(200_000))).scan_tokens().clone();
+    let expr = Parser::new(tokens).parse_expression().unwrap();
+    drop(expr);
+}
+
+#[test]
+fn a_long_chain_is_run_and_dropped_in_process() {
+    let source = format!("var total = 0{};", " + 1".repeat(200_000));
+    let tokens = Scanner::new(source).scan_tokens().clone();
+    let mut interpreter = Interpreter::new();
+    assert!(interpreter.interpret(Parser::new(tokens).parse()).is_ok());
+    assert!(interpreter.take_diagnostics().messages().is_empty());
+}
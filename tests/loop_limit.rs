@@ -0,0 +1,66 @@
+mod common;
+
+use common::messages_with;
+use crafting_interpreters::Interpreter;
+
+// the diagnostics from running `source` with loops limited to `limit` iterations
+fn messages(source: &str, limit: Option<usize>) -> Vec<String> {
+    messages_with(source, |interpreter| interpreter.set_max_loop_iterations(limit))
+}
+
+#[test]
+fn there_is_no_limit_by_default() {
+    assert_eq!(Interpreter::new().max_loop_iterations(), None);
+    assert!(messages("var i = 0;\nwhile (i < 10000) i = i + 1;", None).is_empty());
+}
+
+#[test]
+fn an_infinite_while_loop_is_stopped() {
+    assert_eq!(
+        messages("var i = 0;\nwhile (true) i = i + 1;", Some(100)),
+        ["[line 2] RuntimeError at 'while': Loop iteration limit exceeded."]
+    );
+}
+
+#[test]
+fn an_infinite_for_loop_is_stopped_at_its_keyword() {
+    assert_eq!(
+        messages("for (;;) {}", Some(100)),
+        ["[line 1] RuntimeError at 'for': Loop iteration limit exceeded."]
+    );
+}
+
+#[test]
+fn a_loop_may_run_exactly_the_limit() {
+    assert!(messages("for (var i = 0; i < 100; i = i + 1) {}", Some(100)).is_empty());
+    assert_eq!(messages("for (var i = 0; i < 101; i = i + 1) {}", Some(100)).len(), 1);
+}
+
+#[test]
+fn the_count_starts_over_each_time_a_loop_is_entered() {
+    let source = "for (var i = 0; i < 50; i = i + 1) {\n  for (var j = 0; j < 50; j = j + 1) {}\n}";
+    assert!(messages(source, Some(50)).is_empty());
+}
+
+#[test]
+fn a_repeat_loop_is_limited_too() {
+    assert!(messages("repeat (100) {}", Some(100)).is_empty());
+    assert_eq!(
+        messages("repeat (101) {}", Some(100)),
+        ["[line 1] RuntimeError at 'repeat': Loop iteration limit exceeded."]
+    );
+}
+
+#[test]
+fn a_for_in_loop_is_limited_too() {
+    assert!(messages("for (_i in 0..100) {}", Some(100)).is_empty());
+    assert_eq!(
+        messages("for (_i in 0..101) {}", Some(100)),
+        ["[line 1] RuntimeError at 'for': Loop iteration limit exceeded."]
+    );
+}
+
+#[test]
+fn each_entry_of_a_nested_for_in_loop_starts_a_new_count() {
+    assert!(messages("for (_i in 0..50) {\n  for (_j in 0..50) {}\n}", Some(50)).is_empty());
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn continue_in_a_for_loop_still_runs_the_increment() {
+    let source = "for (var i = 0; i < 3; i = i + 1) { if (i == 1) continue; print i; }";
+    assert_eq!(output(source), "0\n2\n");
+}
+
+#[test]
+fn continue_in_a_while_loop_goes_back_to_the_condition() {
+    let source = "
+        var i = 0;
+        while (i < 5) {
+          i = i + 1;
+          if (i == 2 or i == 4) continue;
+          print i;
+        }
+    ";
+    assert_eq!(output(source), "1\n3\n5\n");
+}
+
+#[test]
+fn continue_applies_to_the_innermost_loop() {
+    let source = "
+        for (var i = 0; i < 2; i = i + 1) {
+          for (var j = 0; j < 3; j = j + 1) {
+            if (j == 1) continue;
+            print i * 10 + j;
+          }
+        }
+    ";
+    assert_eq!(output(source), "0\n2\n10\n12\n");
+}
+
+#[test]
+fn continue_outside_a_loop_is_an_error() {
+    assert_eq!(run("continue;").status, Some(65));
+}
+
+#[test]
+fn a_labeled_break_leaves_both_loops() {
+    let source = "
+        outer: for (var i = 0; i < 3; i = i + 1) {
+          var j = 0;
+          while (true) {
+            if (j == 2) continue outer;
+            if (i == 2) break outer;
+            print i * 10 + j;
+            j = j + 1;
+          }
+        }
+        print \"done\";
+    ";
+    assert_eq!(output(source), "0\n1\n10\n11\ndone\n");
+}
+
+#[test]
+fn an_unknown_label_is_an_error() {
+    let session = run("while (true) {\n  break nowhere;\n}");
+    assert_eq!(session.status, Some(65));
+    assert_eq!(session.stderr, "[line 2 ] Error  : No enclosing loop is labeled 'nowhere'.\n");
+}
+
+#[test]
+fn a_do_while_body_runs_once_when_the_condition_starts_false() {
+    let source = "var n = 0;\ndo { print \"once\"; n = n + 1; } while (false);\nprint n;";
+    assert_eq!(output(source), "once\n1\n");
+}
+
+#[test]
+fn break_and_continue_work_in_a_do_while() {
+    let source = "var i = 0;\ndo {\n  i = i + 1;\n  if (i == 2) continue;\n  if (i == 4) break;\n  print i;\n} while (i < 10);";
+    assert_eq!(output(source), "1\n3\n");
+}
+
+#[test]
+fn repeat_runs_its_body_the_given_number_of_times() {
+    assert_eq!(output("repeat (0) { print \"never\"; }\nrepeat (3) { print \"hi\"; }\nvar k = 2;\nrepeat (k + 0.0) print k;"), "hi\nhi\nhi\n2\n2\n");
+}
+
+#[test]
+fn break_and_continue_work_in_a_repeat() {
+    let source = "var n = 0;\nrepeat (5) {\n  n = n + 1;\n  if (n == 2) continue;\n  if (n == 4) break;\n  print n;\n}";
+    assert_eq!(output(source), "1\n3\n");
+}
+
+#[test]
+fn a_repeat_count_must_be_a_whole_number_from_zero() {
+    for (count, shown) in [("-1", "number (-1)"), ("1.5", "number (1.5)"), ("\"2\"", "string (\"2\")")] {
+        let session = run(&format!("repeat ({}) {{}}", count));
+        assert_eq!(session.status, Some(70));
+        assert_eq!(
+            session.stderr.trim_end(),
+            format!("[line 1] RuntimeError at 'repeat': Repeat count must be a non-negative whole number, got {}.", shown)
+        );
+    }
+}
+
+#[test]
+fn while_else_runs_the_else_only_when_the_body_never_ran() {
+    let source = "var i = 0;
+while (i < 0) { print \"body\"; } else { print \"none\"; }
+while (i < 2) { i = i + 1; } else { print \"not after iterating\"; }
+print i;";
+    assert_eq!(output(source), "none\n2\n");
+}
+
+#[test]
+fn a_break_skips_the_else() {
+    let source = "var i = 0;\nwhile (true) { i = i + 1; break; } else { print \"skipped\"; }\nprint i;";
+    assert_eq!(output(source), "1\n");
+}
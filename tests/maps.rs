@@ -0,0 +1,55 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn literals_print_in_insertion_order() {
+    assert_eq!(output("print {2: 1, 1: 2, true: nil};\nprint {};"), "{2: 1, 1: 2, true: nil}\n{}\n");
+}
+
+#[test]
+fn indexing_finds_values_and_gives_nil_for_missing_keys() {
+    let source = "var m = {\"a\": 1, 2: \"two\"};\nprint m[\"a\"];\nprint m[2];\nprint m[\"b\"];";
+    assert_eq!(output(source), "1\ntwo\nnil\n");
+}
+
+#[test]
+fn replacing_a_value_keeps_its_place_and_removing_moves_it_to_the_end() {
+    let source = "var m = {\"b\": 1, \"a\": 2};
+m[\"b\"] = 5;
+m[\"c\"] = 3;
+print m.keys().join(\" \");
+print m.values();
+print m.remove(\"b\");
+print m.remove(\"b\");
+m.set(\"b\", 0);
+print m.keys().join(\" \");";
+    assert_eq!(output(source), "b a c\n[5, 2, 3]\n5\nnil\na c b\n");
+}
+
+#[test]
+fn methods_query_the_map() {
+    let source = "var m = {1: 10};\nprint m.get(1);\nprint m.get(2);\nprint m.has(1);\nprint m.has(2);\nprint m.size();";
+    assert_eq!(output(source), "10\nnil\ntrue\nfalse\n1\n");
+}
+
+#[test]
+fn maps_are_shared_and_compared_by_identity() {
+    let source = "var m = {};\nvar n = m;\nn[1] = 2;\nprint m[1];\nprint m == n;\nprint {} == {};";
+    assert_eq!(output(source), "2\ntrue\nfalse\n");
+}
+
+#[test]
+fn negative_zero_is_the_same_key_as_zero() {
+    assert_eq!(output("var m = {0: \"zero\"};\nprint m[-0];"), "zero\n");
+}
+
+#[test]
+fn objects_are_not_keys() {
+    let run = common::run("var m = {};\nm[[1]] = 2;");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr.trim_end(),
+        "[line 2] RuntimeError at ']': Map keys must be strings, numbers, booleans or nil, got array."
+    );
+}
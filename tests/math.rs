@@ -0,0 +1,62 @@
+mod common;
+
+use common::{error_of, output};
+
+// `print` of each call, compared with what it should show
+fn assert_prints(cases: &[(&str, &str)]) {
+    let source: String = cases.iter().map(|(call, _)| format!("print {};\n", call)).collect();
+    let expected: String = cases.iter().map(|(_, shown)| format!("{}\n", shown)).collect();
+    assert_eq!(output(&source), expected);
+}
+
+#[test]
+fn sqrt_and_abs() {
+    assert_prints(&[
+        ("sqrt(16)", "4"),
+        ("sqrt(2)", "1.4142135623730951"),
+        ("sqrt(0)", "0"),
+        ("abs(-3)", "3"),
+        ("abs(2.5)", "2.5"),
+    ]);
+}
+
+#[test]
+fn floor_ceil_and_round() {
+    assert_prints(&[
+        ("floor(2.7)", "2"),
+        ("floor(-2.5)", "-3"),
+        ("ceil(2.1)", "3"),
+        ("ceil(-2.1)", "-2"),
+        ("round(2.4)", "2"),
+        ("round(2.5)", "3"),
+        ("round(-2.5)", "-3"),
+    ]);
+}
+
+#[test]
+fn pow_min_and_max() {
+    assert_prints(&[
+        ("pow(2, 10)", "1024"),
+        ("pow(4, 0.5)", "2"),
+        ("min(3, 1)", "1"),
+        ("max(3, 1)", "3"),
+        ("min(1.5, -1)", "-1"),
+    ]);
+}
+
+#[test]
+fn min_and_max_of_equal_arguments_is_that_value() {
+    assert_prints(&[("min(2, 2)", "2"), ("max(2, 2)", "2"), ("min(-0.5, -0.5)", "-0.5")]);
+}
+
+#[test]
+fn the_square_root_of_a_negative_number_is_an_error() {
+    assert_eq!(error_of("print sqrt(-1);"), "[line 1] RuntimeError at ')': Can't take sqrt() of a negative number (-1).");
+}
+
+#[test]
+fn a_non_number_is_an_error_naming_the_function() {
+    assert_eq!(error_of("print abs(\"a\");"), "[line 1] RuntimeError at ')': abs() expects numbers, got string.");
+    assert_eq!(error_of("print pow(2, nil);"), "[line 1] RuntimeError at ')': pow() expects numbers, got nil.");
+    assert_eq!(error_of("print max(1, \"2\");"), "[line 1] RuntimeError at ')': max() expects numbers, got string.");
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn one_site_finds_the_right_method_for_each_class() {
+    let source = "class A { m() { return \"a\"; } }
+class B < A { m() { return \"b\"; } }
+class C < A {}
+var out = \"\";
+for (x in [A(), B(), C(), A(), B(), C()]) out = out + x.m();
+print out;";
+    assert_eq!(output(source), "abaaba\n");
+}
+
+#[test]
+fn a_field_added_later_still_shadows_the_cached_method() {
+    let source = "class A { m() { return \"method\"; } }
+fun f() { return \"field\"; }
+var a = A();
+for (i in [1, 2, 3]) { if (i == 2) a.m = f; print a.m(); }";
+    assert_eq!(output(source), "method\nfield\nfield\n");
+}
+
+#[test]
+fn a_cached_method_is_bound_to_each_instance() {
+    let source = "class P { init(n) { this.n = n; } get() { return this.n; } }
+var total = 0;
+for (p in [P(1), P(2), P(3)]) total = total + p.get();
+print total;";
+    assert_eq!(output(source), "6\n");
+}
+
+#[test]
+fn every_access_binds_a_new_method() {
+    assert_eq!(output("class A { m() {} }\nvar a = A();\nprint a.m == a.m;"), "false\n");
+}
+
+#[test]
+fn a_missing_method_is_still_an_error_after_a_hit() {
+    let run = common::run("class A { m() {} }\nclass B {}\nfor (x in [A(), B()]) x.m();");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.contains("Undefined property 'm'"), "{}", run.stderr);
+}
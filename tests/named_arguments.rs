@@ -0,0 +1,45 @@
+mod common;
+
+use common::output;
+
+const GREET: &str = "fun greet(greeting, name) { return greeting + \", \" + name; }\n";
+
+// the first line of what `source` reports on stderr
+fn first_error(source: &str) -> String {
+    common::run(source).stderr.lines().next().unwrap_or_default().to_string()
+}
+
+#[test]
+fn named_arguments_go_to_their_parameters_in_any_order() {
+    assert_eq!(output(&format!("{}print greet(name: \"Sam\", greeting: \"Hi\");", GREET)), "Hi, Sam\n");
+}
+
+#[test]
+fn positional_arguments_still_work() {
+    assert_eq!(output(&format!("{}print greet(\"Hello\", \"Ann\");", GREET)), "Hello, Ann\n");
+}
+
+#[test]
+fn positional_arguments_may_come_before_named_ones() {
+    assert_eq!(output(&format!("{}print greet(\"Hey\", name: \"Bo\");", GREET)), "Hey, Bo\n");
+}
+
+#[test]
+fn a_positional_argument_after_a_named_one_is_a_syntax_error() {
+    assert_eq!(
+        first_error(&format!("{}print greet(name: \"a\", \"b\");", GREET)),
+        "[line 2 ] Error  at '\"b\"' : Positional argument can't follow a named argument."
+    );
+}
+
+#[test]
+fn an_unknown_or_repeated_name_is_a_runtime_error() {
+    assert_eq!(
+        first_error(&format!("{}print greet(\"a\", nope: \"b\");", GREET)),
+        "[line 2] RuntimeError at 'nope': No parameter named 'nope'."
+    );
+    assert_eq!(
+        first_error(&format!("{}print greet(\"a\", greeting: \"b\");", GREET)),
+        "[line 2] RuntimeError at 'greeting': Argument 'greeting' was given more than once."
+    );
+}
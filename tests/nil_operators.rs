@@ -0,0 +1,47 @@
+mod common;
+
+use common::output;
+
+const NODE: &str = "class Node { init(next) { this.next = next; this.value = 1; } }\n";
+
+#[test]
+fn optional_access_on_nil_is_nil() {
+    assert_eq!(output("var none = nil;\nprint none?.value;"), "nil\n");
+}
+
+#[test]
+fn optional_access_on_an_instance_is_the_property() {
+    assert_eq!(output(&format!("{}var n = Node(nil);\nprint n?.value;\nprint n?.next;", NODE)), "1\nnil\n");
+}
+
+#[test]
+fn a_chain_of_optional_accesses_passes_nil_along() {
+    let source = format!(
+        "{}var n = Node(Node(nil));\nprint n?.next?.value;\nprint n?.next?.next?.value;\nvar none = nil;\nprint none?.next?.value;",
+        NODE
+    );
+    assert_eq!(output(&source), "1\nnil\nnil\n");
+}
+
+#[test]
+fn a_plain_access_after_an_optional_one_still_needs_an_instance() {
+    let run = common::run("var a = nil;\nprint a?.b.c;");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 2] RuntimeError at 'c': Cannot access property 'c' on nil."), "{}", run.stderr);
+}
+
+#[test]
+fn coalescing_nil_gives_the_right_hand_side() {
+    assert_eq!(output("print nil ?? 5;\nvar a = nil;\nprint a ?? \"default\";\nprint nil ?? nil ?? 3;"), "5\ndefault\n3\n");
+}
+
+#[test]
+fn only_nil_is_replaced() {
+    assert_eq!(output("print 0 ?? 5;\nprint false ?? 5;\nprint (\"\" ?? 5) == \"\";"), "0\nfalse\ntrue\n");
+}
+
+#[test]
+fn the_right_hand_side_only_runs_when_the_left_is_nil() {
+    let source = "fun boom() { print \"evaluated\"; return 9; }\nprint 1 ?? boom();\nprint nil ?? boom();";
+    assert_eq!(output(source), "1\nevaluated\n9\n");
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use common::output;
+
+/*
+What the reference jlox prints for each of these, which is what the book's test suite expects.
+jlox keeps every number a double and prints a whole one without its ".0"; past ten million, or
+under a thousandth, Java switches to scientific notation, so the cases stay inside that range.
+*/
+const CASES: [(&str, &str); 14] = [
+    ("2 + 3", "5"),
+    ("7 - 10", "-3"),
+    ("6 * 7", "42"),
+    ("10 / 4", "2.5"),
+    ("1 / 3", "0.3333333333333333"),
+    ("2 / 3", "0.6666666666666666"),
+    ("0.1 + 0.2", "0.30000000000000004"),
+    ("3.0", "3"),
+    ("2 * 3.5", "7"),
+    ("-(2 - 2.5)", "0.5"),
+    ("-0.0", "-0"),
+    ("1.5 * 1.5", "2.25"),
+    ("1234.5678", "1234.5678"),
+    ("(1 + 2) * 3 - 4 / 8", "8.5"),
+];
+
+#[test]
+fn arithmetic_prints_what_jlox_prints() {
+    let source: String = CASES.iter().map(|(expression, _)| format!("print {};\n", expression)).collect();
+    let expected: String = CASES.iter().map(|(_, printed)| format!("{}\n", printed)).collect();
+    assert_eq!(output(&source), expected);
+}
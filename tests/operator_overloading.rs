@@ -0,0 +1,42 @@
+mod common;
+
+use common::output;
+
+const VECTOR: &str = "class Vector {
+  init(x, y) { this.x = x; this.y = y; }
+  plus(other) { return Vector(this.x + other.x, this.y + other.y); }
+  times(k) { return Vector(this.x * k, this.y * k); }
+  equals(other) { return this.x == other.x and this.y == other.y; }
+  show() { print str(this.x) + \" \" + str(this.y); }
+}
+";
+
+#[test]
+fn plus_adds_two_vectors() {
+    assert_eq!(output(&format!("{}(Vector(1, 2) + Vector(3, 4)).show();", VECTOR)), "4 6\n");
+}
+
+#[test]
+fn times_scales_by_a_number() {
+    assert_eq!(output(&format!("{}(Vector(1, 2) * 3).show();", VECTOR)), "3 6\n");
+}
+
+#[test]
+fn equals_compares_by_value() {
+    let source = format!(
+        "{}var a = Vector(1, 2);\nprint a == Vector(1, 2);\nprint a == Vector(2, 1);\nprint a != Vector(1, 2);",
+        VECTOR
+    );
+    assert_eq!(output(&source), "true\nfalse\nfalse\n");
+}
+
+#[test]
+fn an_instance_without_the_method_is_the_usual_type_error() {
+    let run = common::run(&format!("{}print Vector(1, 2) - Vector(1, 2);", VECTOR));
+    assert_eq!(run.status, Some(70));
+    assert!(
+        run.stderr.starts_with("[line 8] RuntimeError at '-': Operator '-' expects numbers, got instance"),
+        "{}",
+        run.stderr
+    );
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::SharedBuffer;
+use crafting_interpreters::{Interpreter, Parser, Scanner};
+
+// one operand of each type, with how it is written and how it prints
+const OPERANDS: [(&str, &str, &str); 10] = [
+    ("int", "2", "2"),
+    ("float", "2.5", "2.5"),
+    ("string", "\"s\"", "s"),
+    ("bool", "true", "true"),
+    ("nil", "nil", "nil"),
+    ("array", "[1]", "[1]"),
+    ("map", "{1: 2}", "{1: 2}"),
+    ("instance", "C()", "C instance"),
+    ("class", "C", "C"),
+    ("function", "f", "<fn f>"),
+];
+
+const BINARY: [&str; 10] = ["+", "-", "*", "/", "<", "<=", ">", ">=", "==", "!="];
+
+// what `print <expression>;` shows, or the runtime error it stops with, without the operands
+fn outcome(expression: &str) -> Result<String, String> {
+    let source = format!("class C {{}}\nfun f() {{}}\nprint {expression};");
+    let buffer = SharedBuffer::default();
+    let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+    let tokens = Scanner::new(source).scan_tokens().clone();
+    match interpreter.interpret(Parser::new(tokens).parse()) {
+        Ok(()) => Ok(String::from_utf8(buffer.0.take()).unwrap().trim_end().to_string()),
+        Err(error) => {
+            let message = error.to_string();
+            let message = message.split_once("': ").unwrap().1;
+            Err(message.split(", got").next().unwrap().to_string())
+        }
+    }
+}
+
+fn numeric(kind: &str) -> Option<f64> {
+    match kind {
+        "int" => Some(2.0),
+        "float" => Some(2.5),
+        _ => None,
+    }
+}
+
+// what the language says `a op b` should give, for the operands in OPERANDS
+fn expected(op: &str, (a, _, a_text): (&str, &str, &str), (b, _, b_text): (&str, &str, &str)) -> Result<String, String> {
+    let numbers = numeric(a).zip(numeric(b));
+    // f64 prints whole numbers without a decimal point, as Lox does
+    let arithmetic = |result: f64| Ok(result.to_string());
+    match op {
+        "+" => match numbers {
+            Some((x, y)) => arithmetic(x + y),
+            None if a == "string" || b == "string" => Ok(format!("{a_text}{b_text}")),
+            None => Err("Operator '+' expects two numbers or a string".to_string()),
+        },
+        "-" | "/" => match numbers {
+            Some((x, y)) if op == "-" => arithmetic(x - y),
+            Some((x, y)) => arithmetic(x / y),
+            None => Err(format!("Operator '{op}' expects numbers")),
+        },
+        "*" => match numbers {
+            Some((x, y)) => arithmetic(x * y),
+            None => match (a, b) {
+                ("string", "int") | ("int", "string") => Ok("ss".to_string()),
+                ("string", "float") | ("float", "string") => {
+                    Err("Repetition count must be a non-negative whole number.".to_string())
+                }
+                _ => Err("Operator '*' expects numbers".to_string()),
+            },
+        },
+        "<" | "<=" | ">" | ">=" => match numbers {
+            Some((x, y)) => Ok(match op {
+                "<" => x < y,
+                "<=" => x <= y,
+                ">" => x > y,
+                _ => x >= y,
+            }
+            .to_string()),
+            None => Err(format!("Operator '{op}' expects numbers")),
+        },
+        _ => {
+            // every operand equals itself but the instance and the map, which are two objects
+            // made afresh on each side; values of different types are never equal
+            let equal = a == b && !matches!(a, "instance" | "map");
+            Ok((equal == (op == "==")).to_string())
+        }
+    }
+}
+
+#[test]
+fn every_binary_operator_on_every_pair_of_types() {
+    let mut mismatches = Vec::new();
+    for op in BINARY {
+        for a in OPERANDS {
+            for b in OPERANDS {
+                let expression = format!("{} {op} {}", a.1, b.1);
+                let (got, want) = (outcome(&expression), expected(op, a, b));
+                if got != want {
+                    mismatches.push(format!("{expression}: got {got:?}, expected {want:?}"));
+                }
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn every_unary_operator_on_every_type() {
+    for (kind, source, _) in OPERANDS {
+        let negated = outcome(&format!("-{source}"));
+        match kind {
+            "int" => assert_eq!(negated, Ok("-2".to_string())),
+            "float" => assert_eq!(negated, Ok("-2.5".to_string())),
+            _ => assert_eq!(negated, Err("Operator '-' expects a number".to_string()), "-{source}"),
+        }
+        let falsey = matches!(kind, "nil");
+        assert_eq!(outcome(&format!("!{source}")), Ok(falsey.to_string()), "!{source}");
+    }
+    assert_eq!(outcome("!false"), Ok("true".to_string()));
+}
+
+#[test]
+fn ints_stay_exact_until_they_overflow() {
+    assert_eq!(outcome("9007199254740993 + 0"), Ok("9007199254740993".to_string()));
+    assert_eq!(outcome("9007199254740993 == 9007199254740992.0"), Ok("false".to_string()));
+    assert_eq!(outcome("1 == 1.0"), Ok("true".to_string()));
+    assert_eq!(outcome("-9223372036854775807 - 1"), Ok("-9223372036854775808".to_string()));
+    assert_eq!(outcome("9223372036854775807 + 1"), Ok("9223372036854776000".to_string()));
+}
+
+#[test]
+fn division_always_gives_a_float() {
+    assert_eq!(outcome("4 / 2"), Ok("2".to_string()));
+    assert_eq!(outcome("7 / 2"), Ok("3.5".to_string()));
+    assert_eq!(outcome("divInt(7, 2)"), Ok("3".to_string()));
+    assert_eq!(outcome("type(4 / 2) == type(4)"), Ok("true".to_string()));
+}
+
+#[test]
+fn whole_floats_are_accepted_as_indexes() {
+    assert_eq!(common::output("print [1, 2][1.0];"), "2\n");
+    let run = common::run("print [1, 2][0.5];");
+    assert_eq!(run.status, Some(70));
+}
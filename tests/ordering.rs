@@ -0,0 +1,76 @@
+mod common;
+
+use std::cmp::Ordering;
+
+use common::output;
+use crafting_interpreters::{compare_values, Literal, Token, TokenType, Value};
+
+fn token() -> Token {
+    Token::new(TokenType::Less, "<".to_string(), Literal::Nil, 7)
+}
+
+fn number(n: f64) -> Value {
+    Value::Float(n)
+}
+
+#[test]
+fn numbers_sort_numerically() {
+    assert_eq!(output("var a = [3, 1, 2];\na.sort();\nprint a;"), "[1, 2, 3]\n");
+}
+
+#[test]
+fn strings_sort_by_code_point() {
+    assert_eq!(output("var a = [\"a\", \"B\", \"\"];\na.sort();\nprint a;"), "[\"\", \"B\", \"a\"]\n");
+}
+
+#[test]
+fn the_sort_native_sorts_in_place() {
+    assert_eq!(output("var a = [3, 1, 2];\nprint sort(a);\nprint a;"), "nil\n[1, 2, 3]\n");
+}
+
+#[test]
+fn the_sort_native_needs_an_array() {
+    let run = common::run("sort(\"cba\");");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(run.stderr, "[line 1] RuntimeError at ')': sort() expects an array, got string.\n");
+}
+
+#[test]
+fn a_mixed_list_is_reported_in_list_order() {
+    let run = common::run("sort([1, \"x\"]);");
+    assert_eq!(run.stderr, "[line 1] RuntimeError at ')': Can't order number (1) and string (\"x\").\n");
+    let run = common::run("[\"x\", 1].sort();");
+    assert_eq!(run.stderr, "[line 1] RuntimeError at ')': Can't order string (\"x\") and number (1).\n");
+}
+
+#[test]
+fn a_mixed_list_has_no_order() {
+    let run = common::run("var a = [2, \"one\", 1];\na.sort();");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(run.stderr, "[line 2] RuntimeError at ')': Can't order number (2) and string (\"one\").\n");
+}
+
+#[test]
+fn a_sort_that_fails_leaves_the_list_as_it_was() {
+    let source = "var a = [2, \"one\", 1];\ntry { a.sort(); } catch (e) { print e.message; }\nprint a;";
+    assert_eq!(output(source), "Can't order number (2) and string (\"one\").\n[2, \"one\", 1]\n");
+}
+
+#[test]
+fn nan_has_no_order() {
+    assert!(compare_values(&number(f64::NAN), &number(1.0), &token()).is_err());
+    assert_eq!(compare_values(&number(2.0), &number(2.0), &token()).unwrap(), Ordering::Equal);
+}
+
+#[test]
+fn other_values_have_no_order() {
+    assert!(compare_values(&Value::Nil, &Value::Nil, &token()).is_err());
+    assert!(compare_values(&Value::Bool(false), &Value::Bool(true), &token()).is_err());
+}
+
+#[test]
+fn ints_and_floats_order_by_value() {
+    assert_eq!(compare_values(&Value::Int(2), &number(2.5), &token()).unwrap(), Ordering::Less);
+    assert_eq!(compare_values(&number(2.0), &Value::Int(2), &token()).unwrap(), Ordering::Equal);
+    assert_eq!(compare_values(&Value::Int(i64::MAX), &number(9.3e18), &token()).unwrap(), Ordering::Less);
+}
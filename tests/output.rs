@@ -0,0 +1,96 @@
+mod common;
+
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use common::SharedBuffer;
+use crafting_interpreters::{Environment, Evaluator, Interpreter, Parser, Scanner};
+
+#[test]
+fn print_writes_to_the_evaluators_output() {
+    let tokens = Scanner::new("print 1 + 2; print \"two\"; print nil;".to_string()).scan_tokens().clone();
+    let statements = Parser::new(tokens).parse();
+
+    let buffer = SharedBuffer::default();
+    let globals = Rc::new(RefCell::new(Environment::new_global()));
+    let mut evaluator = Evaluator::with_output(globals, Box::new(buffer.clone()));
+    for statement in &statements {
+        evaluator.execute(statement).expect("the program should run");
+    }
+
+    assert_eq!(String::from_utf8(buffer.0.borrow().clone()).unwrap(), "3\ntwo\nnil\n");
+}
+
+// what `source` prints when the whole interpreter writes into a buffer, and whether it ran to the end
+fn interpret_into_buffer(source: &str) -> (String, bool) {
+    let buffer = SharedBuffer::default();
+    let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    let finished = interpreter.interpret(Parser::new(tokens).parse()).is_ok();
+    (String::from_utf8(buffer.0.take()).unwrap(), finished)
+}
+
+#[test]
+fn the_interpreter_writes_a_whole_program_to_its_output() {
+    let source = "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }
+for (var i = 0; i < 6; i = i + 1) print fib(i);
+class P { toString() { return \"a P\"; } }
+print P();";
+    assert_eq!(interpret_into_buffer(source), ("0\n1\n1\n2\n3\n5\na P\n".to_string(), true));
+}
+
+#[test]
+fn what_was_printed_before_an_error_is_kept() {
+    let source = "print \"one\";\nprint \"two\";\nprint nil + 1;\nprint \"three\";";
+    assert_eq!(interpret_into_buffer(source), ("one\ntwo\n".to_string(), false));
+}
+
+#[test]
+fn eprint_writes_to_the_error_output_and_print_does_not() {
+    let (output, errors) = (SharedBuffer::default(), SharedBuffer::default());
+    let mut interpreter = Interpreter::with_output(Box::new(output.clone()));
+    interpreter.set_error_output(Box::new(errors.clone()));
+    let tokens = Scanner::new("print eprint(\"oops\");\neprint([1, \"a\"]);\nprint \"out\";".to_string()).scan_tokens().clone();
+    interpreter.interpret(Parser::new(tokens).parse()).unwrap();
+    assert_eq!(String::from_utf8(output.0.take()).unwrap(), "nil\nout\n");
+    assert_eq!(String::from_utf8(errors.0.take()).unwrap(), "oops\n[1, \"a\"]\n");
+}
+
+#[test]
+fn eprint_goes_to_stderr_by_default() {
+    let run = common::run("eprint(\"oops\");\nprint \"out\";");
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout, "out\n");
+    assert_eq!(run.stderr, "oops\n");
+}
+
+// a writer that refuses everything, like a pipe whose reader has gone away
+struct BrokenPipe;
+
+impl io::Write for BrokenPipe {
+    fn write(&mut self, _bytes: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "the reader has gone"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_failing_output_is_a_runtime_error_at_print() {
+    let mut interpreter = Interpreter::with_output(Box::new(BrokenPipe));
+    let tokens = Scanner::new("var a = 1;\nprint a;".to_string()).scan_tokens().clone();
+    let error = interpreter.interpret(Parser::new(tokens).parse()).unwrap_err();
+    assert_eq!(error.to_string(), "[line 2] RuntimeError at 'print': Can't write to output: the reader has gone.");
+}
+
+#[test]
+fn a_failing_error_output_is_a_runtime_error_at_eprint() {
+    let mut interpreter = Interpreter::with_output(Box::new(io::sink()));
+    interpreter.set_error_output(Box::new(BrokenPipe));
+    let tokens = Scanner::new("eprint(\"oops\");".to_string()).scan_tokens().clone();
+    let error = interpreter.interpret(Parser::new(tokens).parse()).unwrap_err();
+    assert_eq!(error.to_string(), "[line 1] RuntimeError at ')': Can't write to error output: the reader has gone.");
+}
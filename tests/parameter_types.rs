@@ -0,0 +1,32 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn arguments_of_the_declared_types_are_accepted() {
+    let source = "fun area(w: number, h: number) { return w * h; }
+fun greet(name: string, loud: bool, extra: any) { if (loud) return name + \"!\"; return name; }
+print area(2, 3.5);
+print greet(\"a\", true, nil);
+print greet(\"b\", false, [1]);";
+    assert_eq!(output(source), "7\na!\nb\n");
+}
+
+#[test]
+fn an_argument_of_another_type_names_the_parameter() {
+    let run = common::run("fun area(w: number, h: number) { return w * h; }\nvar a = area(2, \"3\");\nprint a;");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(run.stdout, "");
+    assert_eq!(
+        run.stderr,
+        "[line 1] RuntimeError at 'h': Parameter 'h' of 'area' must be number, got string.\n  \
+         in function area (line 1), called from script (line 2)\n"
+    );
+}
+
+#[test]
+fn a_bool_parameter_takes_only_a_bool() {
+    let run = common::run("fun f(flag: bool) {}\nf(1);");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at 'flag': Parameter 'flag' of 'f' must be bool, got number."), "{}", run.stderr);
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use crafting_interpreters::{AstPrinter, Expr, Parser, Scanner};
+
+fn parse(source: &str) -> Option<Expr> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    Parser::new(tokens).parse_expression().ok()
+}
+
+#[test]
+fn an_expression_parses_with_the_usual_precedence() {
+    let expr = parse("1 + 2 * 3").unwrap();
+    assert_eq!(AstPrinter::new().print(&expr), "(+ 1 (* 2 3))");
+    // compared structurally, ignoring lines and ids
+    assert_eq!(expr, parse("1 +\n2 * 3").unwrap());
+    assert_ne!(expr, parse("(1 + 2) * 3").unwrap());
+}
+
+#[test]
+fn an_incomplete_expression_is_rejected() {
+    assert!(parse("1 +").is_none());
+}
+
+#[test]
+fn the_expression_must_use_up_every_token() {
+    assert!(parse("1 2").is_none());
+    assert!(parse("1 + 2;").is_none());
+}
+
+#[test]
+fn the_repl_echoes_the_value_of_a_bare_expression() {
+    assert_eq!(common::repl("1 + 2 * 3\n\"x\" + \"y\"\n").stdout, "> 7\n> xy\n> ");
+}
+
+#[test]
+fn the_repl_still_runs_statements_without_echoing() {
+    assert_eq!(common::repl("print 1;\nvar a = 2;\n").stdout, "> 1\n> > ");
+}
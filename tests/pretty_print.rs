@@ -0,0 +1,21 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn nested_lists_and_maps_print_with_their_strings_quoted() {
+    let source = "print [1, [2, [\"x\", nil]], {\"k\": [3]}];\nprint [];\nprint [[]];";
+    assert_eq!(output(source), "[1, [2, [\"x\", nil]], {\"k\": [3]}]\n[]\n[[]]\n");
+}
+
+#[test]
+fn a_collection_inside_itself_prints_as_dots() {
+    let source = "var l = [1];\nl.push(l);\nprint l;\nvar m = {};\nm[\"me\"] = m;\nprint m;";
+    assert_eq!(output(source), "[1, ...]\n{\"me\": ...}\n");
+}
+
+#[test]
+fn the_same_list_twice_is_not_a_cycle() {
+    let source = "var inner = [1];\nprint [inner, inner];";
+    assert_eq!(output(source), "[[1], [1]]\n");
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::output;
+
+// ten draws of `random()` after `randomSeed(seed)`, as printed
+fn sequence(seed: u64) -> String {
+    output(&format!("randomSeed({});\nfor (var i = 0; i < 10; i = i + 1) print random();", seed))
+}
+
+#[test]
+fn a_seed_gives_the_same_sequence_every_time() {
+    assert_eq!(sequence(7), sequence(7));
+    let source = "randomSeed(7);\nvar a = random();\nvar b = randomInt(1, 100);\nrandomSeed(7);\nprint random() == a;\nprint randomInt(1, 100) == b;";
+    assert_eq!(output(source), "true\ntrue\n");
+}
+
+#[test]
+fn different_seeds_give_different_sequences() {
+    assert_ne!(sequence(7), sequence(8));
+}
+
+#[test]
+fn random_stays_in_zero_to_one() {
+    let source = "var outside = 0;
+for (var i = 0; i < 10000; i = i + 1) {
+  var r = random();
+  if (r < 0 or r >= 1) outside = outside + 1;
+}
+print outside;";
+    assert_eq!(output(source), "0\n");
+}
+
+#[test]
+fn random_int_stays_within_its_inclusive_bounds() {
+    let source = "var seen = {};
+var outside = 0;
+for (var i = 0; i < 10000; i = i + 1) {
+  var n = randomInt(-2, 3);
+  if (n < -2 or n > 3 or type(n) != \"number\") outside = outside + 1;
+  seen[n] = true;
+}
+print outside;
+print len(keys(seen));
+print randomInt(4, 4);";
+    assert_eq!(output(source), "0\n6\n4\n");
+}
+
+#[test]
+fn random_int_needs_ordered_whole_bounds() {
+    let run = common::run("print randomInt(3, 1);");
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at ')': randomInt() lower bound 3 is greater than upper bound 1."), "{}", run.stderr);
+    let run = common::run("print randomInt(1.5, 3);");
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at ')': randomInt() expects two whole numbers."), "{}", run.stderr);
+}
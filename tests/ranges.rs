@@ -0,0 +1,44 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_range_counts_up_to_its_end() {
+    assert_eq!(output("for (i in 0..3) print i;"), "0\n1\n2\n");
+}
+
+#[test]
+fn an_empty_or_backward_range_runs_nothing() {
+    assert_eq!(output("for (i in 0..0) print i;\nfor (i in 3..0) print i;\nprint \"done\";"), "done\n");
+}
+
+#[test]
+fn a_range_is_a_value_of_its_own() {
+    let source = "var r = 1..4;\nprint r;\nprint type(r);\nprint r == (1..4);\nprint r == (1..5);";
+    assert_eq!(output(source), "1..4\nrange\ntrue\nfalse\n");
+}
+
+#[test]
+fn the_bounds_bind_looser_than_arithmetic() {
+    assert_eq!(output("var n = 2;\nfor (i in n - 1..n + 1) print i;"), "1\n2\n");
+}
+
+#[test]
+fn whole_floats_are_accepted_as_bounds() {
+    assert_eq!(output("for (i in 0..2.0) print i;"), "0\n1\n");
+}
+
+#[test]
+fn bounds_must_be_integers() {
+    let run = common::run("for (i in 0.5..2) print i;");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr.trim_end(),
+        "[line 1] RuntimeError at '..': Range bounds must be integers, got number (0.5) and number (2)."
+    );
+}
+
+#[test]
+fn a_long_range_is_not_listed_first() {
+    assert_eq!(output("var n = 0;\nfor (i in 0..1000000000000) { n = n + 1; if (n == 3) break; }\nprint n;"), "3\n");
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{repl, SharedBuffer};
+use crafting_interpreters::{Interpreter, Parser, Repl, Scanner};
+
+#[test]
+fn a_closure_made_on_one_line_is_called_on_a_later_one() {
+    let input = "fun counter() { var n = 0; fun next() { n = n + 1; return n; } return next; }
+var c = counter();
+c()
+c()
+";
+    assert_eq!(repl(input).stdout, "> > > 1\n> 2\n> ");
+}
+
+#[test]
+fn a_function_sees_globals_defined_after_it() {
+    let input = "var a = 4;\nfun f() { return a + later; }\nvar later = 10;\nf()\na = 5;\nf()\n";
+    assert_eq!(repl(input).stdout, "> > > > 14\n> > 15\n> ");
+}
+
+#[test]
+fn a_mistake_on_one_line_does_not_stop_the_next() {
+    let session = repl("var a = 1;\nprint a +;\nprint nil + 1;\nprint a;\n");
+    assert!(session.stdout.ends_with("1\n> "), "{}", session.stdout);
+    assert_eq!(session.stderr.lines().count(), 2, "{}", session.stderr);
+}
+
+#[test]
+fn interpret_carries_on_from_the_last_call() {
+    let buffer = SharedBuffer::default();
+    let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+    for line in [
+        "fun adder(x) { fun add(y) { return x + y; } return add; }",
+        "var add2 = adder(2);",
+        "{ var local = 40; print add2(local); }",
+        "print add2(1);",
+    ] {
+        let tokens = Scanner::new(line.to_string()).scan_tokens().clone();
+        interpreter.interpret(Parser::new(tokens).parse()).unwrap();
+        assert!(interpreter.take_diagnostics().messages().is_empty());
+    }
+    assert_eq!(String::from_utf8(buffer.0.take()).unwrap(), "42\n3\n");
+}
+
+#[test]
+fn a_function_typed_over_several_lines_is_defined_once_they_balance() {
+    let mut repl = Repl::new();
+    for line in ["fun square(x) {", "  if (x == \"}\") return 0;", "  return x * x;"] {
+        assert!(repl.read_line(line).is_none(), "ran early at {:?}", line);
+        assert_eq!(repl.prompt(), ".. ");
+    }
+    let diagnostics = repl.read_line("}").expect("the closing brace runs the definition");
+    assert!(diagnostics.messages().is_empty(), "{:?}", diagnostics.messages());
+    assert_eq!(repl.prompt(), "> ");
+
+    let diagnostics = repl.read_line("if (square(3) != 9) throw square(3);").unwrap();
+    assert!(diagnostics.messages().is_empty(), "{:?}", diagnostics.messages());
+}
+
+#[test]
+fn a_blank_line_throws_the_unfinished_lines_away() {
+    let mut repl = Repl::new();
+    assert!(repl.read_line("fun never(x) {").is_none());
+    assert!(repl.read_line("").is_none());
+    assert_eq!(repl.prompt(), "> ");
+    let diagnostics = repl.read_line("never(1);").unwrap();
+    assert_eq!(diagnostics.messages(), ["[line 1] RuntimeError at 'never': Undefined variable 'never'."]);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_return_of_the_declared_type_is_accepted() {
+    let source = "fun name() -> string { return \"lox\"; }
+fun count() -> number { return 2.5; }
+fun ok() -> bool { return true; }
+fun nothing() -> nil { return; }
+print name();
+print count();
+print ok();
+print nothing();";
+    assert_eq!(output(source), "lox\n2.5\ntrue\nnil\n");
+}
+
+#[test]
+fn a_return_of_another_type_is_a_runtime_error() {
+    let run = common::run("fun f() -> number {\n  return \"one\";\n}\nprint f();");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr,
+        "[line 1] RuntimeError at 'number': 'f' must return number, got string.\n  \
+         in function f (line 1), called from script (line 4)\n"
+    );
+}
+
+#[test]
+fn falling_off_the_end_returns_nil_which_must_match_too() {
+    let run = common::run("fun f() -> string { }\nf();");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at 'string': 'f' must return string, got nil."), "{}", run.stderr);
+}
+
+#[test]
+fn a_function_without_a_return_type_returns_anything() {
+    assert_eq!(output("fun f(x) { return x; }\nprint f(1);\nprint f(\"a\");"), "1\na\n");
+}
+
+#[test]
+fn a_mismatch_after_a_tail_call_names_the_caller_whose_type_it_misses() {
+    // `g` returns a number as it promises; `f` hands that back and promised a string
+    let run = common::run("fun g() -> number { return 1; }\nfun f() -> string { return g(); }\nf();");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr,
+        "[line 2] RuntimeError at 'string': 'f' must return string, got number.\n  \
+         in function f (line 2), called from script (line 3)\n"
+    );
+}
@@ -0,0 +1,34 @@
+use std::time::{Duration, Instant};
+
+use crafting_interpreters::run_with_report;
+
+#[test]
+fn every_phase_is_timed_and_every_statement_counted() {
+    // the `var`, the `while`, and its body three times
+    let source = "var a = 0;\nwhile (a < 3) a = a + 1;".to_string();
+    let started = Instant::now();
+    let (diagnostics, report) = run_with_report(&source);
+    let wall = started.elapsed();
+
+    assert!(diagnostics.messages().is_empty(), "{:?}", diagnostics.messages());
+    assert_eq!(report.statements_executed, 5);
+    let phases = report.phase_times;
+    for (phase, time) in [
+        ("scanning", phases.scanning),
+        ("parsing", phases.parsing),
+        ("resolving", phases.resolving),
+        ("evaluating", phases.evaluating),
+    ] {
+        assert!(time > Duration::ZERO, "{} was not timed", phase);
+    }
+    assert!(phases.total() <= wall, "{:?} is more than the {:?} the call took", phases.total(), wall);
+}
+
+#[test]
+fn a_phase_that_never_ran_is_zero() {
+    let (diagnostics, report) = run_with_report(&"print (;".to_string());
+    assert!(!diagnostics.messages().is_empty());
+    assert_eq!(report.phase_times.resolving, Duration::ZERO);
+    assert_eq!(report.phase_times.evaluating, Duration::ZERO);
+    assert_eq!(report.statements_executed, 0);
+}
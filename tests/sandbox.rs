@@ -0,0 +1,40 @@
+mod common;
+
+use common::interpret;
+use crafting_interpreters::Interpreter;
+
+#[test]
+fn natives_reaching_outside_are_not_defined() {
+    let mut interpreter = Interpreter::sandboxed();
+    assert!(interpreter.is_sandboxed());
+    assert_eq!(
+        interpret(&mut interpreter, "eprint(\"out\");").as_deref(),
+        Some("[line 1] RuntimeError at 'eprint': Undefined variable 'eprint'.")
+    );
+    assert_eq!(
+        interpret(&mut interpreter, "sleepMs(1);").as_deref(),
+        Some("[line 1] RuntimeError at 'sleepMs': Undefined variable 'sleepMs'.")
+    );
+}
+
+#[test]
+fn imports_are_refused() {
+    let refused = "[line 1] RuntimeError at 'import': Can't import files in a sandboxed interpreter.";
+    assert_eq!(interpret(&mut Interpreter::sandboxed(), "import \"x.lox\";").as_deref(), Some(refused));
+    // an ordinary interpreter goes looking for the file, which isn't there
+    let error = interpret(&mut Interpreter::new(), "import \"x.lox\";").unwrap();
+    assert!(error.starts_with("[line 1] RuntimeError at '\"x.lox\"': Can't import 'x.lox': "), "{}", error);
+}
+
+#[test]
+fn natives_that_only_compute_are_still_there() {
+    let mut interpreter = Interpreter::sandboxed();
+    assert_eq!(interpret(&mut interpreter, "var n = len(\"ab\") + sqrt(4);\nvar t = clock();\nvar s = str(n);"), None);
+}
+
+#[test]
+fn an_ordinary_interpreter_keeps_them() {
+    let mut interpreter = Interpreter::new();
+    assert!(!interpreter.is_sandboxed());
+    assert_eq!(interpret(&mut interpreter, "var e = eprint;\nvar s = sleepMs;\nsleepMs(0);"), None);
+}
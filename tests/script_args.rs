@@ -0,0 +1,30 @@
+use crafting_interpreters::{run, run_with_args};
+
+// throws with what it was given unless `args` is exactly "a", "b c" and ""
+const EXPECTS_THREE: &str = "
+var seen = str(len(args)) + \":\";
+for (arg in args) seen = seen + \" [\" + arg + \"]\";
+if (seen != \"3: [a] [b c] []\") throw seen;
+";
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn the_program_sees_each_argument_in_order() {
+    let diagnostics = run_with_args(&EXPECTS_THREE.to_string(), &strings(&["a", "b c", ""]));
+    assert!(diagnostics.messages().is_empty(), "{:?}", diagnostics.messages());
+}
+
+#[test]
+fn other_arguments_are_seen_as_they_are() {
+    let diagnostics = run_with_args(&EXPECTS_THREE.to_string(), &strings(&["--time", "a"]));
+    assert_eq!(diagnostics.messages(), ["[line 4] RuntimeError at 'throw': 2: [--time] [a]"]);
+}
+
+#[test]
+fn run_without_arguments_gives_an_empty_array() {
+    let diagnostics = run(&"if (len(args) != 0) throw args;".to_string());
+    assert!(diagnostics.messages().is_empty(), "{:?}", diagnostics.messages());
+}
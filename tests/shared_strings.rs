@@ -0,0 +1,67 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crafting_interpreters::{Environment, Evaluator, Literal, Parser, Scanner, Token, TokenType, Value};
+
+// runs `source`, which only uses globals, and hands back the global strings called `names`, each
+// with its reference count while the program's values were still alive
+fn strings_after(source: &str, names: &[&str]) -> Vec<(Rc<str>, usize)> {
+    let globals = Rc::new(RefCell::new(Environment::new_global()));
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    // the evaluator clears its environments when dropped, so read them while it is still here
+    let mut evaluator = Evaluator::new(Rc::clone(&globals));
+    for statement in Parser::new(tokens).parse() {
+        evaluator.execute(&statement).expect("the program should run");
+    }
+    let strings = names
+        .iter()
+        .map(|name| {
+            let token = Token::new(TokenType::Identifier, name.to_string(), Literal::Nil, 1);
+            match globals.borrow().get(&token).unwrap() {
+                Value::String(s) => {
+                    let count = Rc::strong_count(&s);
+                    (s, count)
+                }
+                other => panic!("{name} is {other}, not a string"),
+            }
+        })
+        .collect();
+    drop(evaluator);
+    strings
+}
+
+#[test]
+fn copies_of_a_string_share_one_allocation() {
+    let strings = strings_after("var a = \"hello\" + \"!\";\nvar b = a;\nvar c = [b][0];", &["a", "b", "c"]);
+    assert!(Rc::ptr_eq(&strings[0].0, &strings[1].0));
+    assert!(Rc::ptr_eq(&strings[0].0, &strings[2].0));
+}
+
+#[test]
+fn literals_with_the_same_text_are_interned() {
+    let source = "var a = \"hello\";
+var b = \"hello\";
+var xs = [];
+var i = 0;
+while (i < 2) { xs.push(\"hello\"); i = i + 1; }
+var c = xs[0];
+var d = xs[1];";
+    let strings = strings_after(source, &["a", "b", "c", "d"]);
+    assert!(strings.iter().all(|(s, _)| Rc::ptr_eq(s, &strings[0].0)));
+}
+
+#[test]
+fn computed_strings_are_not_interned() {
+    let strings = strings_after("var a = \"hello\";\nvar b = \"hel\" + \"lo\";", &["a", "b"]);
+    assert_eq!(*strings[0].0, *strings[1].0);
+    assert!(!Rc::ptr_eq(&strings[0].0, &strings[1].0));
+}
+
+#[test]
+fn holding_a_string_only_counts_references() {
+    let strings = strings_after("var s = \"x\" + \"y\";\nvar xs = [s, s];\nvar m = {1: s};", &["s"]);
+    // the binding s, both elements, the map's value and the handle read here
+    assert_eq!(strings[0].1, 5);
+    // with the evaluator gone, only the handle read here is left
+    assert_eq!(Rc::strong_count(&strings[0].0), 1);
+}
@@ -0,0 +1,22 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_script_starting_with_a_shebang_runs() {
+    assert_eq!(output("#!/usr/bin/env jlox\nprint \"ran\";"), "ran\n");
+}
+
+#[test]
+fn the_shebang_still_counts_as_a_line() {
+    let run = common::run("#!/usr/bin/env jlox\nprint \"ran\";\nprint nil + 1;");
+    assert_eq!(run.stdout, "ran\n");
+    assert!(run.stderr.starts_with("[line 3] RuntimeError at '+'"), "{}", run.stderr);
+}
+
+#[test]
+fn only_a_shebang_at_the_very_start_is_skipped() {
+    let run = common::run(" #!/usr/bin/env jlox\nprint \"ran\";");
+    assert_eq!(run.status, Some(65));
+    assert!(run.stderr.starts_with("[line 1 ] Error  : Unexpected character."), "{}", run.stderr);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+// the stack trace printed under the runtime error `source` stops with
+fn trace_of(source: &str) -> String {
+    let run = common::run(source);
+    assert_eq!(run.status, Some(70), "expected a runtime error, got:\n{}", run.stdout);
+    run.stderr.lines().skip(1).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn the_trace_names_each_caller_from_the_innermost_out() {
+    let source = "fun inner() {
+  return nil + 1;
+}
+fun middle() {
+  inner();
+}
+class K { run() { middle(); } }
+K().run();";
+    assert_eq!(
+        trace_of(source),
+        "  in function inner (line 2), called from function middle (line 5), \
+         called from method run (line 7), called from script (line 8)"
+    );
+}
+
+#[test]
+fn repeated_recursive_callers_are_counted() {
+    let source = "fun down(n) {
+  if (n == 0) return nil + 1;
+  return 1 + down(n - 1);
+}
+down(3);";
+    assert_eq!(
+        trace_of(source),
+        "  in function down (line 2), called from function down (line 3) 3 times, called from script (line 5)"
+    );
+}
+
+#[test]
+fn tail_calls_leave_no_frame_behind() {
+    let source = "fun f(n) { if (n == 0) return nil + 1; return f(n - 1); }\nf(3);";
+    assert_eq!(trace_of(source), "  in function f (line 1), called from script (line 2)");
+}
+
+#[test]
+fn an_error_outside_any_function_has_no_trace() {
+    assert_eq!(trace_of("print nil + 1;"), "");
+}
+
+#[test]
+fn a_later_error_starts_with_a_clean_trace() {
+    let source = "fun bad() { return nil + 1; }
+fun call() { bad(); }
+call();";
+    assert_eq!(trace_of(source), "  in function bad (line 1), called from function call (line 2), called from script (line 3)");
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::messages_with;
+use crafting_interpreters::{Interpreter, Parser, Scanner};
+
+// the diagnostics from running `source` with strict returns switched on or off
+fn messages(source: &str, strict: bool) -> Vec<String> {
+    messages_with(source, |interpreter| interpreter.set_strict_returns(strict))
+}
+
+#[test]
+fn strict_mode_is_off_by_default() {
+    assert!(!Interpreter::new().strict_returns());
+}
+
+#[test]
+fn falling_off_a_function_that_returns_elsewhere_is_an_error() {
+    let source = "fun sign(x) {\n  if (x > 0) return 1;\n}\nprint sign(-1);";
+    assert_eq!(
+        messages(source, true),
+        ["[line 1] RuntimeError at 'sign': Function 'sign' reached the end of its body without returning a value.\n  \
+          in function sign (line 1), called from script (line 4)"]
+    );
+}
+
+#[test]
+fn the_path_that_returns_is_still_fine() {
+    assert!(messages("fun sign(x) { if (x > 0) return 1; }\nprint sign(1);", true).is_empty());
+}
+
+#[test]
+fn a_function_returning_on_every_path_is_accepted() {
+    let source = "fun sign(x) { if (x > 0) return 1; else { return -1; } }\nprint sign(-1);";
+    assert!(messages(source, true).is_empty());
+}
+
+#[test]
+fn a_function_that_never_returns_a_value_is_accepted() {
+    assert!(messages("fun greet() { print \"hi\"; }\ngreet();", true).is_empty());
+    assert!(messages("fun stop() { return; }\nstop();", true).is_empty());
+}
+
+#[test]
+fn initializers_are_left_out() {
+    let source = "class P { init(x) { if (x) return; this.x = 1; } }\nP(false);";
+    assert!(messages(source, true).is_empty());
+}
+
+#[test]
+fn without_strict_mode_the_missing_return_is_nil() {
+    assert!(messages("fun sign(x) { if (x > 0) return 1; }\nprint sign(-1);", false).is_empty());
+}
+
+#[test]
+fn a_method_is_not_mistaken_for_another_with_the_same_name_and_line() {
+    let source = "class A { m(x) { if (x) return 1; } } class B { m() { print \"b\"; } } B().m();";
+    assert!(messages(source, true).is_empty());
+}
+
+#[test]
+fn a_function_declared_again_on_a_later_repl_line_is_not_mistaken_for_the_first() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_strict_returns(true);
+    for line in ["fun f(x) { if (x) return 1; }", "fun f() { print \"b\"; }", "f();"] {
+        let tokens = Scanner::new(line.to_string()).scan_tokens().clone();
+        assert!(interpreter.interpret(Parser::new(tokens).parse()).is_ok(), "{line}");
+    }
+    assert!(interpreter.take_diagnostics().messages().is_empty());
+}
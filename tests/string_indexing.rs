@@ -0,0 +1,36 @@
+mod common;
+
+use common::{error_of, output};
+
+#[test]
+fn an_index_is_a_one_character_string() {
+    assert_eq!(output("var s = \"hello\";\nprint s[0];\nprint s[4];"), "h\no\n");
+}
+
+#[test]
+fn a_multi_byte_string_is_indexed_by_character() {
+    assert_eq!(output("var u = \"héllo→\";\nprint u[1];\nprint u[5];\nprint u[1..3];\nprint u[4..6];"), "é\n→\nél\no→\n");
+}
+
+#[test]
+fn a_slice_may_reach_either_end() {
+    let source = "var s = \"hello\";\nprint s[1..3];\nprint s[0..5];\nprint s[0..0] == \"\";\nprint s[5..5] == \"\";";
+    assert_eq!(output(source), "el\nhello\ntrue\ntrue\n");
+}
+
+#[test]
+fn strings_cannot_be_changed_through_an_index() {
+    assert_eq!(error_of("var s = \"hello\";\ns[0] = \"j\";"), "[line 2] RuntimeError at ']': Strings are immutable.");
+}
+
+#[test]
+fn an_index_out_of_range_is_an_error() {
+    assert_eq!(error_of("print \"hello\"[5];"), "[line 1] RuntimeError at ']': Index 5 is out of range for length 5.");
+    assert_eq!(error_of("print \"→\"[1];"), "[line 1] RuntimeError at ']': Index 1 is out of range for length 1.");
+    assert_eq!(error_of("print \"hello\"[3..6];"), "[line 1] RuntimeError at ']': Index 6 is out of range for length 5.");
+}
+
+#[test]
+fn a_negative_index_is_out_of_range_too() {
+    assert_eq!(error_of("print \"hello\"[-1];"), "[line 1] RuntimeError at ']': Index -1 is out of range for length 5.");
+}
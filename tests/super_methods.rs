@@ -0,0 +1,99 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_method_the_superclass_has_is_found() {
+    let source = "class A { x() { return \"ax\"; } }\nclass B < A { m() { print super.x; } }\nB().m();";
+    assert_eq!(output(source), "<fn x of B instance>\n");
+}
+
+#[test]
+fn methods_are_found_further_up_the_chain() {
+    let source = "class A { y() { return 1; } }\nclass B < A {}\nclass C < B { m() { print super.y; } }\nC().m();";
+    assert_eq!(output(source), "<fn y of C instance>\n");
+}
+
+#[test]
+fn a_missing_method_is_reported_before_running() {
+    let run = common::run("print \"start\";\nclass A {}\nclass B < A { m() { super.y; } }\nclass C < A { m() { return super.z(); } }");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stdout, "");
+    assert_eq!(
+        run.stderr,
+        "[line 3 ] Error  : Superclass has no method 'y'.\n[line 4 ] Error  : Superclass has no method 'z'.\n"
+    );
+}
+
+#[test]
+fn a_superclass_only_known_at_runtime_is_not_checked() {
+    let source = "fun make(S) { class B < S { m() { print super.y; } } return B; }\nclass A { y() {} }\nmake(A)().m();";
+    assert_eq!(output(source), "<fn y of B instance>\n");
+}
+
+#[test]
+fn a_superclass_assigned_to_later_is_left_to_runtime() {
+    let source = "class A {}\nfun make() { class C < A { m() { return super.y(); } } return C; }\nclass B { y() { return \"ok\"; } }\nA = B;\nprint make()().m();";
+    assert_eq!(output(source), "ok\n");
+}
+
+#[test]
+fn a_superclass_assigned_in_a_function_that_runs_first_is_left_to_runtime() {
+    let source = "fun swap() { A = B; }\nclass A {}\nclass B { y() { return \"ok\"; } }\nswap();\nclass C < A { m() { return super.y(); } }\nprint C().m();";
+    assert_eq!(output(source), "ok\n");
+}
+
+#[test]
+fn a_class_from_an_enclosing_scope_is_left_to_runtime() {
+    let run = common::run("class A {}\nfun make() { class C < A { m() { return super.y(); } } return C; }\nmake()().m();");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 2] RuntimeError at 'y': Undefined property 'y'."), "{}", run.stderr);
+}
+
+#[test]
+fn a_class_bound_to_a_const_is_known_from_anywhere() {
+    let run = common::run("class A {}\nconst Base = A;\nfun make() { class C < Base { m() { return super.y(); } } return C; }");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stderr, "[line 3 ] Error  : Superclass has no method 'y'.\n");
+}
+
+#[test]
+fn an_assignment_on_an_earlier_repl_line_is_remembered() {
+    let run = common::repl("fun swap() { A = B; }\nclass A {} class B { y() { return \"ok\"; } } swap(); class C < A { m() { return super.y(); } } print C().m();\n");
+    assert!(run.stdout.contains("ok"), "{}{}", run.stdout, run.stderr);
+}
+
+#[test]
+fn the_books_boston_cream_calls_the_doughnut_method_it_overrides() {
+    let source = "class Doughnut {
+  cook() { print \"Fry until golden brown.\"; }
+}
+class BostonCream < Doughnut {
+  cook() {
+    super.cook();
+    print \"Pipe full of custard and coat with chocolate.\";
+  }
+}
+BostonCream().cook();";
+    assert_eq!(output(source), "Fry until golden brown.\nPipe full of custard and coat with chocolate.\n");
+}
+
+#[test]
+fn super_in_an_inherited_method_is_the_superclass_of_the_class_that_declared_it() {
+    // `C` inherits `method` from `B`, so its `super` is still `A`, not `B`
+    let source = "class A { method() { print \"A method\"; } }
+class B < A {
+  method() { print \"B method\"; }
+  test() { super.method(); }
+}
+class C < B {}
+C().test();";
+    assert_eq!(output(source), "A method\n");
+}
+
+#[test]
+fn a_method_missing_all_the_way_up_is_an_undefined_property() {
+    let run = common::run("fun make(S) {\n  class B < S { m() { return super.nope(); } }\n  return B;\n}\nclass A {}\nclass Z < A {}\nmake(Z)().m();");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 2] RuntimeError at 'nope': Undefined property 'nope'."), "{}", run.stderr);
+}
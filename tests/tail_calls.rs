@@ -0,0 +1,70 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_million_step_countdown_runs_in_constant_stack() {
+    let source = "fun count(n) { if (n == 0) return \"done\"; return count(n - 1); }\nprint count(1000000);";
+    assert_eq!(output(source), "done\n");
+}
+
+#[test]
+fn mutual_recursion_in_tail_position() {
+    let source = "fun even(n) { if (n == 0) return true; return odd(n - 1); }
+fun odd(n) { if (n == 0) return false; return even(n - 1); }
+print even(100001);";
+    assert_eq!(output(source), "false\n");
+}
+
+#[test]
+fn methods_and_closures_make_tail_calls_too() {
+    let source = "class C { loop(n) { if (n == 0) return this; return this.loop(n - 1); } }
+print C().loop(5000);
+fun make() { var k = 0; fun go(n) { k = k + 1; if (n == 0) return k; return go(n - 1); } return go; }
+print make()(3000);";
+    assert_eq!(output(source), "C instance\n3001\n");
+}
+
+#[test]
+fn a_call_that_is_not_in_tail_position_still_counts_toward_the_limit() {
+    let run = common::run("fun f(n) { if (n == 0) return 0; return 1 + f(n - 1); }\nprint f(5000);");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.contains("Stack overflow"), "{}", run.stderr);
+}
+
+#[test]
+fn a_tail_call_checks_its_arity() {
+    let run = common::run("fun f(a) { return g(a); }\nfun g(a, b) { return a; }\nf(1);");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(run.stderr.lines().next().unwrap(), "[line 1] RuntimeError at ')': Expected 2 arguments to 'g' but got 1.");
+}
+
+#[test]
+fn an_accumulator_loop_runs_far_past_the_call_depth_limit() {
+    let source = "fun sum(n, total) { if (n == 0) return total; return sum(n - 1, total + n); }\nprint sum(200000, 0);";
+    assert_eq!(output(source), "20000100000\n");
+}
+
+#[test]
+fn a_return_type_is_checked_in_the_frame_of_the_function_that_declared_it() {
+    let run = common::run("fun g() { return 1; }\nfun f() -> string { return g(); }\nf();");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr,
+        "[line 2] RuntimeError at 'string': 'f' must return string, got number.\n  \
+         in function f (line 2), called from script (line 3)\n"
+    );
+}
+
+#[test]
+fn a_return_type_shared_along_a_tail_call_blames_the_function_that_returned() {
+    let run = common::run(
+        "fun g() -> number { return \"s\"; }\nfun f() -> number { return g(); }\nf();",
+    );
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr,
+        "[line 1] RuntimeError at 'number': 'g' must return number, got string.\n  \
+         in function g (line 1), called from script (line 3)\n"
+    );
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_thrown_map_is_caught_as_it_was() {
+    let source = "try { throw {\"code\": 42, \"why\": \"bad\"}; } catch (e) { print e[\"code\"]; print e[\"why\"]; }";
+    assert_eq!(output(source), "42\nbad\n");
+}
+
+#[test]
+fn any_value_can_be_thrown() {
+    let source = "try { throw \"text\"; } catch (e) { print e; }
+fun f() { throw 7; }
+try { f(); } catch (e) { print e + 1; }
+try { throw nil; } catch (e) { print e; }";
+    assert_eq!(output(source), "text\n8\nnil\n");
+}
+
+#[test]
+fn an_error_instance_can_be_thrown() {
+    assert_eq!(output("try { throw Error(\"custom\"); } catch (e) { print e.message; }"), "custom\n");
+}
+
+#[test]
+fn a_value_nobody_catches_is_a_runtime_error() {
+    let run = common::run("fun f() {\n  throw \"x\";\n}\nf();");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr,
+        "[line 2] RuntimeError at 'throw': x\n  in function f (line 2), called from script (line 4)\n"
+    );
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::run_with_flags;
+
+// a time as the report writes it: digits, a point, one digit, then "ms"
+fn is_millis(text: &str) -> bool {
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    match text.strip_suffix("ms").and_then(|number| number.split_once('.')) {
+        Some((whole, tenths)) => digits(whole) && tenths.len() == 1 && digits(tenths),
+        None => false,
+    }
+}
+
+#[test]
+fn the_timings_follow_the_output_on_stderr() {
+    let run = run_with_flags(&["--time"], "var a = 1;\nprint a + 1;");
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout, "2\n");
+
+    let report = run.stderr.strip_suffix('\n').expect("a line of timings");
+    let parts: Vec<&str> = report.split(", ").collect();
+    assert_eq!(parts.len(), 5, "{}", report);
+    for (part, phase) in parts.iter().zip(["scan", "parse", "resolve", "run"]) {
+        let time = part.strip_prefix(phase).and_then(|rest| rest.strip_prefix(' '));
+        assert!(time.is_some_and(is_millis), "{:?} in {}", part, report);
+    }
+    assert_eq!(parts[4], "2 statements");
+}
+
+#[test]
+fn without_the_flag_there_are_no_timings() {
+    let run = common::run("print 1;");
+    assert_eq!(run.stderr, "");
+}
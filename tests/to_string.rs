@@ -0,0 +1,77 @@
+mod common;
+
+use common::output;
+
+const POINT: &str = "class P {\n  init(x) { this.x = x; }\n  toString() { return \"P(\" + this.x + \")\"; }\n}\n";
+
+#[test]
+fn print_uses_to_string() {
+    assert_eq!(output(&format!("{POINT}print P(1);")), "P(1)\n");
+}
+
+#[test]
+fn concatenation_uses_to_string() {
+    assert_eq!(output(&format!("{POINT}print \"p is \" + P(1);\nprint P(2) + \"!\";")), "p is P(1)\nP(2)!\n");
+}
+
+#[test]
+fn printed_collections_use_to_string() {
+    assert_eq!(output(&format!("{POINT}print [P(1), P(2)];\nprint {{1: P(3)}};")), "[P(1), P(2)]\n{1: P(3)}\n");
+}
+
+#[test]
+fn str_and_eprint_use_to_string() {
+    let run = common::run(&format!("{POINT}var s = str(P(4));\nprint len(s);\neprint(P(5));"));
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout, "4\n");
+    assert_eq!(run.stderr, "P(5)\n");
+}
+
+#[test]
+fn a_class_without_to_string_prints_as_before() {
+    assert_eq!(output("class Q {}\nprint Q();\nprint \"\" + Q();"), "Q instance\nQ instance\n");
+}
+
+#[test]
+fn to_string_must_return_a_string() {
+    let run = common::run("class Bad { toString() { return 3; } }\nprint Bad();");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(run.stderr.trim_end(), "[line 2] RuntimeError at 'print': toString must return a string.");
+}
+
+#[test]
+fn an_error_inside_to_string_is_reported() {
+    let run = common::run("class R { toString() { return nil + 1; } }\nprint R();");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.contains("in method toString (line 1), called from script (line 2)"), "{}", run.stderr);
+}
+
+#[test]
+fn join_uses_to_string() {
+    assert_eq!(output(&format!("{POINT}print [P(1), \"a\", 2].join(\",\");")), "P(1),a,2\n");
+}
+
+#[test]
+fn type_errors_show_operands_with_to_string() {
+    let run = common::run(&format!("{POINT}print P(3) > P(2);"));
+    assert_eq!(run.status, Some(70));
+    assert_eq!(
+        run.stderr.lines().next().unwrap(),
+        "[line 5] RuntimeError at '>': Operator '>' expects numbers, got instance (P(3)) and instance (P(2))."
+    );
+    let run = common::run(&format!("{POINT}print -P(1);"));
+    assert!(run.stderr.contains("expects a number, got instance (P(1))."), "{}", run.stderr);
+    let run = common::run(&format!("{POINT}[P(1), 2].sort();"));
+    assert!(run.stderr.contains("Can't order "), "{}", run.stderr);
+    assert!(run.stderr.contains("instance (P(1))"), "{}", run.stderr);
+}
+
+#[test]
+fn a_failing_to_string_does_not_replace_the_type_error() {
+    let source = "class Q { toString() { return 1; } }\nprint Q() > 1;";
+    let run = common::run(source);
+    assert_eq!(
+        run.stderr.lines().next().unwrap(),
+        "[line 2] RuntimeError at '>': Operator '>' expects numbers, got instance (Q instance) and number (1)."
+    );
+}
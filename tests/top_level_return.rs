@@ -0,0 +1,28 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_bare_return_ends_the_script() {
+    assert_eq!(output("print 1;\nreturn;\nprint 2;"), "1\n");
+}
+
+#[test]
+fn it_can_end_the_script_from_inside_blocks_and_loops() {
+    assert_eq!(output("print 1;\nif (true) { return; }\nprint 2;"), "1\n");
+    let source = "for (var i = 0; i < 3; i = i + 1) { print i; if (i == 1) return; }\nprint \"after\";";
+    assert_eq!(output(source), "0\n1\n");
+}
+
+#[test]
+fn returning_a_value_from_the_top_level_is_still_an_error() {
+    let run = common::run("print 1;\nreturn 1;");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stdout, "");
+    assert_eq!(run.stderr, "[line 2 ] Error  : Can't return a value from top-level code.\n");
+}
+
+#[test]
+fn return_inside_a_function_only_ends_the_function() {
+    assert_eq!(output("fun f() { return; }\nf();\nprint \"still here\";"), "still here\n");
+}
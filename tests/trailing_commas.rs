@@ -0,0 +1,34 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn a_trailing_comma_ends_a_parameter_or_argument_list() {
+    let source = "fun f(a, b,) { return a + b; }
+print f(1, 2,);
+print f(
+  3,
+  4,
+);
+class P { init(x,) { this.x = x; } }
+print P(5,).x;";
+    assert_eq!(output(source), "3\n7\n5\n");
+}
+
+#[test]
+fn a_comma_alone_is_still_an_error() {
+    let run = common::run("fun f() {}\nf(,);");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stderr, "[line 2 ] Error  at ',' : Expected an expression.\n");
+
+    let run = common::run("fun g(,) {}");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stderr, "[line 1 ] Error  at ',' : Expect parameter name.\n");
+}
+
+#[test]
+fn only_one_trailing_comma_is_allowed() {
+    let run = common::run("fun f(a) {}\nf(1,,);");
+    assert_eq!(run.status, Some(65));
+    assert_eq!(run.stderr, "[line 2 ] Error  at ',' : Expected an expression.\n");
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn division_by_zero_is_caught() {
+    let source = "try { print 1 / 0; print \"skipped\"; } catch (e) { print e.message; print e; }\nprint \"after\";";
+    assert_eq!(output(source), "Division by zero.\nDivision by zero.\nafter\n");
+}
+
+#[test]
+fn the_caught_value_is_an_error_instance_with_a_line() {
+    let source = "fun f() {\n  return nil.x;\n}\ntry { f(); } catch (e) { print e is Error; print e.line; print e.message; }";
+    assert_eq!(output(source), "true\n2\nCannot access property 'x' on nil.\n");
+}
+
+#[test]
+fn the_catch_block_only_runs_on_an_error() {
+    assert_eq!(output("try { print \"fine\"; } catch (e) { print \"never\"; }"), "fine\n");
+}
+
+#[test]
+fn the_innermost_try_catches() {
+    let source = "try {\n  try { [][1]; } catch (e) { print \"inner\"; }\n  print \"after inner\";\n} catch (e) { print \"outer\"; }";
+    assert_eq!(output(source), "inner\nafter inner\n");
+}
+
+#[test]
+fn the_error_variable_is_scoped_to_the_catch_block() {
+    let run = common::run("try { 1 / 0; } catch (e) {}\nprint e;");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 2] RuntimeError at 'e': Undefined variable 'e'."), "{}", run.stderr);
+}
+
+#[test]
+fn an_error_in_the_catch_block_goes_on_up() {
+    let run = common::run("try { 1 / 0; } catch (e) { nil + 1; }\nprint \"not reached\";");
+    assert_eq!(run.status, Some(70));
+    assert_eq!(run.stdout, "");
+}
+
+#[test]
+fn exit_is_not_an_error_and_is_not_caught() {
+    let run = common::run("try { exit(3); } catch (e) { print \"caught\"; }\nprint \"not reached\";");
+    assert_eq!(run.status, Some(3));
+    assert_eq!(run.stdout, "");
+}
+
+#[test]
+fn an_undefined_variable_is_caught_with_its_message_and_line() {
+    let source = "var x = 1;\ntry {\n  print x;\n  print missing;\n} catch (e) { print e.message; print e.line; }";
+    assert_eq!(output(source), "1\nUndefined variable 'missing'.\n4\n");
+}
+
+#[test]
+fn a_user_subclass_is_caught_with_its_own_fields() {
+    let source = "class NotFound < Error {
+  init(key) {
+    super.init(\"no \" + key);
+    this.key = key;
+  }
+}
+try { throw NotFound(\"k\"); } catch (e) { print e is NotFound; print e is Error; print e.key; print e.message; }";
+    assert_eq!(output(source), "true\ntrue\nk\nno k\n");
+}
+
+#[test]
+fn a_rethrown_error_keeps_its_original_line() {
+    let rethrow = "try {\n  print missing;\n} catch (e) {\n  throw e;\n}";
+    assert_eq!(output(&format!("try {{\n{}\n}} catch (e) {{ print e.line; }}", rethrow)), "3\n");
+
+    let run = common::run(rethrow);
+    assert_eq!(run.status, Some(70));
+    assert_eq!(run.stderr, "[line 2] RuntimeError: Undefined variable 'missing'.\n");
+}
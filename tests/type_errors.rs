@@ -0,0 +1,101 @@
+mod common;
+
+use common::error_of;
+use crafting_interpreters::{Interpreter, Parser, Scanner};
+
+#[test]
+fn a_comparison_names_the_operator_and_both_operands() {
+    assert_eq!(
+        error_of("print \"abc\" < 3;"),
+        "[line 1] RuntimeError at '<': Operator '<' expects numbers, got string (\"abc\") and number (3)."
+    );
+}
+
+#[test]
+fn arithmetic_names_the_operand_types() {
+    assert_eq!(
+        error_of("print nil - 1;"),
+        "[line 1] RuntimeError at '-': Operator '-' expects numbers, got nil (nil) and number (1)."
+    );
+    assert_eq!(
+        error_of("class A {}\nprint A > 1;"),
+        "[line 2] RuntimeError at '>': Operator '>' expects numbers, got class (A) and number (1)."
+    );
+}
+
+#[test]
+fn addition_says_it_also_takes_strings() {
+    assert_eq!(
+        error_of("print true + nil;"),
+        "[line 1] RuntimeError at '+': Operator '+' expects two numbers or a string, got boolean (true) and nil (nil)."
+    );
+}
+
+#[test]
+fn negation_names_its_operand() {
+    assert_eq!(
+        error_of("print -\"x\";"),
+        "[line 1] RuntimeError at '-': Operator '-' expects a number, got string (\"x\")."
+    );
+}
+
+#[test]
+fn the_book_wording_can_be_asked_for() {
+    let mut interpreter = Interpreter::new();
+    assert!(!interpreter.book_messages());
+    interpreter.set_book_messages(true);
+
+    let mut messages = Vec::new();
+    for source in ["print \"abc\" < 3;", "print -\"x\";", "print true + nil;"] {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+        let error = interpreter.interpret(Parser::new(tokens).parse()).unwrap_err();
+        messages.push(error.to_string());
+    }
+    assert_eq!(
+        messages,
+        [
+            "[line 1] RuntimeError at '<': Operands must be numbers.",
+            "[line 1] RuntimeError at '-': Operand must be a number.",
+            "[line 1] RuntimeError at '+': Operands must be two numbers or two strings.",
+        ]
+    );
+}
+
+#[test]
+fn calling_something_that_is_not_callable_says_what_it_was() {
+    let callees = [
+        ("\"hello\"(1)", "string"),
+        ("nil()", "nil"),
+        ("3()", "number"),
+        ("2.5()", "number"),
+        ("true()", "boolean"),
+        ("[1]()", "array"),
+        ("{1: 2}()", "map"),
+        ("C()()", "instance"),
+    ];
+    for (call, type_name) in callees {
+        assert_eq!(
+            error_of(&format!("class C {{}}\nprint {};", call)),
+            format!("[line 2] RuntimeError at '(': Can only call functions and classes, got {}.", type_name)
+        );
+    }
+}
+
+#[test]
+fn the_repl_carries_on_after_calling_something_that_is_not_callable() {
+    let run = common::repl("nil();\nprint \"still here\";\n");
+    assert!(run.stderr.contains("[line 1] RuntimeError at '(': Can only call functions and classes, got nil."), "{}", run.stderr);
+    assert!(run.stdout.contains("still here"), "{}", run.stdout);
+}
+
+#[test]
+fn a_property_of_nil_says_it_was_nil() {
+    assert_eq!(error_of("var a = nil;\nprint a.x;"), "[line 2] RuntimeError at 'x': Cannot access property 'x' on nil.");
+    assert_eq!(error_of("nil.method();"), "[line 1] RuntimeError at 'method': Cannot access property 'method' on nil.");
+}
+
+#[test]
+fn a_property_of_another_value_names_its_type() {
+    assert_eq!(error_of("print 5.x;"), "[line 1] RuntimeError at 'x': Only instances have properties, got number.");
+    assert_eq!(error_of("true.z();"), "[line 1] RuntimeError at 'z': Only instances have properties, got boolean.");
+}
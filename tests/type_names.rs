@@ -0,0 +1,42 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn type_names_each_kind_of_value() {
+    let cases = [
+        ("nil", "nil"),
+        ("true", "boolean"),
+        ("1", "number"),
+        ("2.5", "number"),
+        ("\"s\"", "string"),
+        ("f", "function"),
+        ("B().m", "function"),
+        ("clock", "native"),
+        ("A", "class"),
+        ("B()", "instance"),
+        ("[1]", "array"),
+        ("{}", "map"),
+        ("1..3", "range"),
+    ];
+    let mut source = "class A { m() {} }\nclass B < A {}\nfun f() {}\n".to_string();
+    let mut expected = String::new();
+    for (value, type_name) in cases {
+        source.push_str(&format!("print type({});\n", value));
+        expected.push_str(&format!("{}\n", type_name));
+    }
+    assert_eq!(output(&source), expected);
+}
+
+#[test]
+fn class_name_is_the_class_the_instance_was_made_from() {
+    let source = "class A {}\nclass B < A {}\nprint className(B());\nprint className(A());\nprint type(B()) == \"instance\";";
+    assert_eq!(output(source), "B\nA\ntrue\n");
+}
+
+#[test]
+fn class_name_takes_only_an_instance() {
+    let run = common::run("print className(1);");
+    assert_eq!(run.status, Some(70));
+    assert!(run.stderr.starts_with("[line 1] RuntimeError at ')': className() expects an instance, got number."), "{}", run.stderr);
+}
@@ -0,0 +1,67 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crafting_interpreters::{Environment, Evaluator, LenFn, Literal, Parser, Scanner, Token, TokenType, Value};
+
+const SOURCE: &str = "
+class Point { m() {} }
+fun f() {}
+var int = 3;
+var float = 2.5;
+var whole_float = 3.0;
+var bool = true;
+var string = \"a, b\";
+var nothing = nil;
+var native = len;
+var function = f;
+var klass = Point;
+var instance = Point();
+var method = instance.m;
+var array = [1, \"a\", nil];
+var map = {\"k\": [2]};
+var range = 1..4;
+";
+
+// each value of each kind, with how it prints and how `repr` shows it
+const EXPECTED: [(&str, &str, &str); 14] = [
+    ("int", "3", "3"),
+    ("float", "2.5", "2.5"),
+    ("whole_float", "3", "3"),
+    ("bool", "true", "true"),
+    ("string", "a, b", "\"a, b\""),
+    ("nothing", "nil", "nil"),
+    ("native", "<native fn>", "<native fn>"),
+    ("function", "<fn f>", "<fn f>"),
+    ("klass", "Point", "Point"),
+    ("instance", "Point instance", "Point instance"),
+    ("method", "<fn m of Point instance>", "<fn m of Point instance>"),
+    ("array", "[1, \"a\", nil]", "[1, \"a\", nil]"),
+    ("map", "{\"k\": [2]}", "{\"k\": [2]}"),
+    ("range", "1..4", "1..4"),
+];
+
+fn global(globals: &Rc<RefCell<Environment>>, name: &str) -> Value {
+    let token = Token::new(TokenType::Identifier, name.to_string(), Literal::Nil, 0);
+    globals.borrow().get(&token).unwrap()
+}
+
+#[test]
+fn display_and_repr_of_each_kind_of_value() {
+    let tokens = Scanner::new(SOURCE.to_string()).scan_tokens().clone();
+    let mut parser = Parser::new(tokens);
+    let statements = parser.parse();
+    assert!(parser.take_diagnostics().messages().is_empty());
+    let globals = Rc::new(RefCell::new(Environment::new_global()));
+    // a bare evaluator has no natives of its own
+    globals.borrow_mut().define("len".to_string(), Value::Callable(Rc::new(LenFn)));
+    let mut evaluator = Evaluator::with_output(globals.clone(), Box::new(std::io::sink()));
+    for statement in &statements {
+        evaluator.execute(statement).expect("the program should run");
+    }
+
+    for (name, display, repr) in EXPECTED {
+        let value = global(&globals, name);
+        assert_eq!(value.to_string(), display, "Display of {}", name);
+        assert_eq!(value.repr(), repr, "repr of {}", name);
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn a_program_with_only_warnings_still_runs_and_exits_0() {
+    let run = run("
+        fun f() {
+          var unused = 1;
+        }
+        f();
+        print \"ran\";
+    ");
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout, "ran\n");
+    assert_eq!(run.stderr, "[line 3 ] Warning  : Local variable 'unused' is never used.\n");
+}
+
+#[test]
+fn an_underscore_name_is_not_reported() {
+    let run = run("{ var _ignored = 1; }");
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stderr, "");
+}
+
+#[test]
+fn an_error_next_to_a_warning_still_fails() {
+    let run = run("{ var unused = 1; }\nprint 1 +;");
+    assert_eq!(run.status, Some(65));
+}
+
+#[test]
+fn warnings_on_one_line_come_in_a_stable_order() {
+    let source = "{\n  var b = 1; var a = 2; var c = 3;\n}\nfor (i, x in [1]) {}";
+    let expected = "[line 2 ] Warning  : Local variable 'a' is never used.
+[line 2 ] Warning  : Local variable 'b' is never used.
+[line 2 ] Warning  : Local variable 'c' is never used.
+[line 4 ] Warning  : Local variable 'i' is never used.
+[line 4 ] Warning  : Local variable 'x' is never used.
+";
+    for _ in 0..5 {
+        assert_eq!(run(source).stderr, expected);
+    }
+}